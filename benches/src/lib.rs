@@ -236,3 +236,20 @@ mod thai {
     simple_bench!(paragraph_medium, "fonts/NotoSansThai-Regular.ttf", "texts/thai/paragraph_medium.txt");
     simple_bench!(paragraph_long, "fonts/NotoSansThai-Regular.ttf", "texts/thai/paragraph_long.txt");
 }
+
+mod lookup_caches {
+    use super::*;
+    use test::Bencher;
+
+    // Builds the lookup caches of a font with enough GSUB lookups to be
+    // built on multiple threads. Run with and without the `parallel` feature
+    // to compare the serial and the parallel build. With a single core, both
+    // build the caches serially.
+    #[bench]
+    fn devanagari(bencher: &mut Bencher) {
+        let font_data = std::fs::read("fonts/NotoSansDevanagari-Regular.ttf").unwrap();
+        bencher.iter(|| {
+            test::black_box(rustybuzz::Face::from_slice(&font_data, 0).unwrap());
+        })
+    }
+}
@@ -249,12 +249,7 @@ fn parse_features(s: &str) -> Result<Vec<harfruzz::Feature>, String> {
 }
 
 fn parse_variations(s: &str) -> Result<Vec<harfruzz::Variation>, String> {
-    let mut variations = Vec::new();
-    for v in s.split(',') {
-        variations.push(harfruzz::Variation::from_str(&v)?);
-    }
-
-    Ok(variations)
+    harfruzz::Variation::parse_list(s).map_err(|e| e.to_string())
 }
 
 fn parse_cluster(s: &str) -> Result<harfruzz::BufferClusterLevel, String> {
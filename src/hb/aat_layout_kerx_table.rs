@@ -62,6 +62,10 @@ pub(crate) fn apply(
             continue;
         }
 
+        if !buffer.consume_budget(buffer.len) {
+            break;
+        }
+
         let reverse = buffer.direction.is_backward();
 
         if !seen_cross_stream && subtable.has_cross_stream {
@@ -166,7 +170,7 @@ fn apply_simple_kerning(
         let kern = subtable
             .glyphs_kerning(info[i].as_glyph(), info[j].as_glyph())
             .unwrap_or(0);
-        let kern = i32::from(kern);
+        let kern = i32::from(kern) * ctx.buffer.position_scale();
 
         let pos = &mut ctx.buffer.pos;
         if kern != 0 {
@@ -360,6 +364,7 @@ impl StateTableDriver<kerx::Subtable1<'_>, kerx::EntryData> for Driver1 {
                 // NOT seem to accumulate as otherwise implied by specs.
 
                 let mut has_gpos_attachment = false;
+                let scale = buffer.position_scale();
                 let glyph_mask = buffer.info[idx].mask;
                 let pos = &mut buffer.pos[idx];
 
@@ -372,12 +377,12 @@ impl StateTableDriver<kerx::Subtable1<'_>, kerx::EntryData> for Driver1 {
                             pos.set_attach_chain(0);
                             pos.y_offset = 0;
                         } else if pos.attach_type() != 0 {
-                            pos.y_offset += v;
+                            pos.y_offset += v * scale;
                             has_gpos_attachment = true;
                         }
                     } else if glyph_mask & plan.kern_mask != 0 {
-                        pos.x_advance += v;
-                        pos.x_offset += v;
+                        pos.x_advance += v * scale;
+                        pos.x_offset += v * scale;
                     }
                 } else {
                     if has_cross_stream {
@@ -387,13 +392,13 @@ impl StateTableDriver<kerx::Subtable1<'_>, kerx::EntryData> for Driver1 {
                             pos.set_attach_chain(0);
                             pos.x_offset = 0;
                         } else if pos.attach_type() != 0 {
-                            pos.x_offset += v;
+                            pos.x_offset += v * scale;
                             has_gpos_attachment = true;
                         }
                     } else if glyph_mask & plan.kern_mask != 0 {
                         if pos.y_offset == 0 {
-                            pos.y_advance += v;
-                            pos.y_offset += v;
+                            pos.y_advance += v * scale;
+                            pos.y_offset += v * scale;
                         }
                     }
                 }
@@ -440,9 +445,10 @@ impl StateTableDriver<kerx::Subtable4<'_>, kerx::EntryData> for Driver4<'_> {
                     .and_then(|list| list.get(u32::from(point.1)))
                     .unwrap_or_default();
 
+                let scale = buffer.position_scale();
                 let pos = buffer.cur_pos_mut();
-                pos.x_offset = i32::from(mark_anchor.x - curr_anchor.x);
-                pos.y_offset = i32::from(mark_anchor.y - curr_anchor.y);
+                pos.x_offset = i32::from(mark_anchor.x - curr_anchor.x) * scale;
+                pos.y_offset = i32::from(mark_anchor.y - curr_anchor.y) * scale;
             }
 
             buffer.cur_pos_mut().set_attach_type(attach_type::MARK);
@@ -125,6 +125,10 @@ pub fn apply<'a>(c: &mut hb_aat_apply_context_t<'a>, map: &'a mut hb_aat_map_t)
                 subtable.coverage.is_backwards() != c.buffer.direction.is_backward()
             };
 
+            if !c.buffer.consume_budget(c.buffer.len) {
+                return Some(());
+            }
+
             if reverse {
                 c.buffer.reverse();
             }
@@ -365,20 +369,17 @@ fn apply_subtable(kind: &morx::SubtableKind, ac: &mut hb_aat_apply_context_t) {
                 if let Some(range_flags) = ac.range_flags.as_ref() {
                     if let Some(last_range) = last_range.as_mut() {
                         let mut range = *last_range;
-                        if ac.buffer.idx < ac.buffer.len {
-                            // We need to access info
-                            let cluster = ac.buffer.cur(0).cluster;
-                            while cluster < range_flags[range].cluster_first {
-                                range -= 1;
-                            }
-
-                            while cluster > range_flags[range].cluster_last {
-                                range += 1;
-                            }
-
-                            *last_range = range;
+                        let cluster = ac.buffer.info[info].cluster;
+                        while cluster < range_flags[range].cluster_first {
+                            range -= 1;
                         }
 
+                        while cluster > range_flags[range].cluster_last {
+                            range += 1;
+                        }
+
+                        *last_range = range;
+
                         if range_flags[range].flags & ac.subtable_flags == 0 {
                             continue;
                         }
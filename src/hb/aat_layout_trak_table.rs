@@ -20,7 +20,7 @@ pub fn apply(plan: &hb_ot_shape_plan_t, face: &hb_font_t, buffer: &mut hb_buffer
     }
 
     if buffer.direction.is_horizontal() {
-        let tracking = trak.hor_tracking(ptem)?;
+        let tracking = trak.hor_tracking(ptem)? * buffer.position_scale();
         let advance_to_add = tracking;
         let offset_to_add = tracking / 2;
         foreach_grapheme!(buffer, start, end, {
@@ -30,7 +30,7 @@ pub fn apply(plan: &hb_ot_shape_plan_t, face: &hb_font_t, buffer: &mut hb_buffer
             }
         });
     } else {
-        let tracking = trak.ver_tracking(ptem)?;
+        let tracking = trak.ver_tracking(ptem)? * buffer.position_scale();
         let advance_to_add = tracking;
         let offset_to_add = tracking / 2;
         foreach_grapheme!(buffer, start, end, {
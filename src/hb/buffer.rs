@@ -1,11 +1,15 @@
-use alloc::{string::String, vec::Vec};
+use alloc::{string::String, sync::Arc, vec::Vec};
 use core::cmp::min;
 use core::convert::TryFrom;
+use core::ops::Range;
+use core::sync::atomic::{AtomicU32, Ordering};
 use ttf_parser::GlyphId;
 
 use super::buffer::glyph_flag::{SAFE_TO_INSERT_TATWEEL, UNSAFE_TO_BREAK, UNSAFE_TO_CONCAT};
-use super::face::hb_glyph_extents_t;
-use super::unicode::{CharExt, GeneralCategoryExt};
+use super::ot_layout::MAX_NESTING_LEVEL;
+use super::unicode::{
+    hb_modified_combining_class, hb_unicode_funcs_get, CharExt, GeneralCategoryExt, UnicodeFuncs,
+};
 use super::{hb_font_t, hb_mask_t};
 use crate::hb::set_digest::{hb_set_digest_ext, hb_set_digest_t};
 use crate::{script, BufferClusterLevel, BufferFlags, Direction, Language, Script, SerializeFlags};
@@ -122,6 +126,82 @@ pub struct GlyphPosition {
     pub(crate) var: u32,
 }
 
+/// Diagnostics collected while shaping a buffer.
+///
+/// Can be used by font QA tools to find out which parts of a font could
+/// not be handled natively by the shaper.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct ShapeDiagnostics {
+    /// Number of lookup subtables that were skipped because they are malformed.
+    pub skipped_subtables: usize,
+    /// Number of lookup subtables that were skipped because their format is
+    /// not supported.
+    pub unsupported_subtables: usize,
+    /// Number of lookups that were applied by the fallback layout implementation.
+    pub fallback_lookups: usize,
+    /// Number of lookups that were skipped because no glyph in the buffer
+    /// had their feature enabled.
+    pub skipped_lookups: usize,
+    /// Number of multiple substitutions that were skipped because they would
+    /// exceed the limit set by [`UnicodeBuffer::set_max_expansion_factor`].
+    pub skipped_expansions: usize,
+    /// Number of `.notdef` glyphs in the shaped output.
+    pub notdef_count: usize,
+    /// Whether marks were positioned using fallback mark positioning.
+    pub fallback_mark_positioning: bool,
+    /// Whether the font had no kerning data and fallback kerning was used.
+    pub fallback_kerning: bool,
+    /// Whether shaping was aborted because the operation or nesting limit
+    /// was exceeded, or because the [`ShapeBudget`] was exhausted.
+    ///
+    /// Also set when a [`ComplexShaper`](crate::ComplexShaper) hook wrote
+    /// codepoints that are not valid characters.
+    pub shaping_failed: bool,
+}
+
+/// A work budget for shaping.
+///
+/// Clones share the same budget, so it can be used for multiple buffers
+/// and cancelled from another thread.
+///
+/// Applying a lookup consumes one operation for each glyph it is applied at,
+/// and applying an AAT subtable one operation per glyph of the buffer.
+/// Once the budget is exhausted, the current lookup is stopped, the remaining
+/// ones are skipped and the partially shaped buffer is returned.
+#[derive(Clone, Debug)]
+pub struct ShapeBudget(Arc<AtomicU32>);
+
+impl ShapeBudget {
+    /// Creates a new budget with the given number of operations.
+    pub fn new(ops: u32) -> Self {
+        ShapeBudget(Arc::new(AtomicU32::new(ops)))
+    }
+
+    /// Returns the number of remaining operations.
+    pub fn remaining(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Exhausts the budget, which aborts shaping of all buffers using it.
+    pub fn cancel(&self) {
+        self.0.store(0, Ordering::Relaxed);
+    }
+
+    /// Consumes `ops` operations.
+    ///
+    /// Returns `false` if not enough operations were left.
+    pub(crate) fn consume(&self, ops: usize) -> bool {
+        let ops = u32::try_from(ops).unwrap_or(u32::MAX);
+        let left = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                Some(left.saturating_sub(ops))
+            })
+            .unwrap_or_default();
+        left >= ops
+    }
+}
+
 unsafe impl bytemuck::Zeroable for GlyphPosition {}
 unsafe impl bytemuck::Pod for GlyphPosition {}
 
@@ -193,9 +273,48 @@ impl hb_glyph_info_t {
         self.mask & glyph_flag::UNSAFE_TO_BREAK != 0
     }
 
+    /// Indicates that if input text is changed on one side of the beginning of the cluster
+    /// this glyph is part of, then the shaping results for the other side might change.
+    ///
+    /// Only produced when [`BufferFlags::PRODUCE_UNSAFE_TO_CONCAT`] is set.
+    /// Every glyph that is unsafe to break is also unsafe to concat.
+    pub fn unsafe_to_concat(&self) -> bool {
+        self.mask & glyph_flag::UNSAFE_TO_CONCAT != 0
+    }
+
+    /// Indicates that a substitution lookup replaced this glyph.
+    ///
+    /// Glyphs formed by a ligature or multiple substitution are substituted as well,
+    /// while the components consumed by a ligature are no longer present in the output.
+    pub fn substituted(&self) -> bool {
+        self.glyph_props() & GlyphPropsFlags::SUBSTITUTED.bits() != 0
+    }
+
+    /// Indicates that a positioning lookup changed the advance or offset of this glyph.
+    ///
+    /// Only produced when [`BufferFlags::PRODUCE_POSITIONED_FLAGS`] is set.
+    pub fn positioned(&self) -> bool {
+        self.glyph_props() & GlyphPropsFlags::POSITIONED.bits() != 0
+    }
+
+    /// Enables or disables the features of a mask for this glyph.
+    ///
+    /// See [`ShapePlan::feature_mask`](crate::ShapePlan::feature_mask).
+    #[inline]
+    pub fn set_feature_mask(&mut self, mask: u32, enabled: bool) {
+        if enabled {
+            self.mask |= mask;
+        } else {
+            self.mask &= !mask;
+        }
+    }
+
+    /// Codepoints that are not valid characters, which can only be set
+    /// through [`UnicodeBuffer::glyph_infos_mut`], are treated as U+FFFD
+    /// until they are replaced when shaping starts.
     #[inline]
     pub(crate) fn as_char(&self) -> char {
-        char::try_from(self.glyph_id).unwrap()
+        char::try_from(self.glyph_id).unwrap_or(char::REPLACEMENT_CHARACTER)
     }
 
     #[inline]
@@ -232,9 +351,13 @@ impl hb_glyph_info_t {
         v[0] = n;
     }
 
-    pub(crate) fn init_unicode_props(&mut self, scratch_flags: &mut hb_buffer_scratch_flags_t) {
+    pub(crate) fn init_unicode_props(
+        &mut self,
+        unicode: &dyn UnicodeFuncs,
+        scratch_flags: &mut hb_buffer_scratch_flags_t,
+    ) {
         let u = self.as_char();
-        let gc = u.general_category();
+        let gc = unicode.general_category(u);
         let mut props = gc.to_rb() as u16;
 
         if u as u32 >= 0x80 {
@@ -274,7 +397,8 @@ impl hb_glyph_info_t {
 
             if gc.is_mark() {
                 props |= UnicodeProps::CONTINUATION.bits();
-                props |= (u.modified_combining_class() as u16) << 8;
+                let ccc = hb_modified_combining_class(u, unicode.combining_class(u));
+                props |= (ccc as u16) << 8;
             }
         }
 
@@ -354,6 +478,7 @@ pub struct hb_buffer_t {
     pub flags: BufferFlags,
     pub cluster_level: hb_buffer_cluster_level_t,
     pub invisible: Option<GlyphId>,
+    pub not_found: GlyphId,
 
     // Buffer contents.
     pub direction: Direction,
@@ -391,6 +516,32 @@ pub struct hb_buffer_t {
     pub max_len: usize,
     /// Maximum allowed operations.
     pub max_ops: i32,
+    /// User-provided upper bound for `max_ops`.
+    pub max_ops_limit: Option<i32>,
+    /// Maximum len that multiple substitutions can expand the buffer to.
+    pub max_expanded_len: usize,
+    /// User-provided factor for `max_expanded_len`.
+    pub max_expansion_factor: Option<usize>,
+    /// User-provided vertical band that fallback-positioned marks are kept in.
+    pub fallback_mark_band: Option<(i32, i32)>,
+    /// Maximum nesting level of contextual lookups.
+    pub max_nesting_level: usize,
+    /// Work budget shared with other buffers.
+    pub budget: Option<ShapeBudget>,
+
+    pub diagnostics: ShapeDiagnostics,
+    /// Clusters at which the input grapheme clusters start.
+    pub grapheme_starts: Vec<u32>,
+    /// Glyph positions before positioning lookups were applied.
+    pub layout_positions: Vec<GlyphPosition>,
+
+    pub unicode: Option<Arc<dyn UnicodeFuncs>>,
+}
+
+impl Default for hb_buffer_t {
+    fn default() -> Self {
+        hb_buffer_t::new()
+    }
 }
 
 impl hb_buffer_t {
@@ -410,9 +561,16 @@ impl hb_buffer_t {
             flags: BufferFlags::empty(),
             cluster_level: HB_BUFFER_CLUSTER_LEVEL_DEFAULT,
             invisible: None,
+            not_found: GlyphId(0),
             scratch_flags: HB_BUFFER_SCRATCH_FLAG_DEFAULT,
             max_len: Self::MAX_LEN_DEFAULT,
             max_ops: Self::MAX_OPS_DEFAULT,
+            max_ops_limit: None,
+            max_expanded_len: usize::MAX,
+            max_expansion_factor: None,
+            fallback_mark_band: None,
+            max_nesting_level: MAX_NESTING_LEVEL,
+            budget: None,
             direction: Direction::Invalid,
             script: None,
             language: None,
@@ -432,6 +590,10 @@ impl hb_buffer_t {
                 ['\0', '\0', '\0', '\0', '\0'],
             ],
             context_len: [0, 0],
+            diagnostics: ShapeDiagnostics::default(),
+            grapheme_starts: Vec::new(),
+            layout_positions: Vec::new(),
+            unicode: None,
         }
     }
 
@@ -498,6 +660,21 @@ impl hb_buffer_t {
         digest
     }
 
+    /// Returns the factor font units are multiplied by in glyph positions.
+    ///
+    /// 64 with [`BufferFlags::PRODUCE_FRACTIONAL_POSITIONS`], 1 otherwise.
+    #[inline]
+    pub(crate) fn position_scale(&self) -> i32 {
+        if self
+            .flags
+            .contains(BufferFlags::PRODUCE_FRACTIONAL_POSITIONS)
+        {
+            64
+        } else {
+            1
+        }
+    }
+
     fn clear(&mut self) {
         self.direction = Direction::Invalid;
         self.script = None;
@@ -519,6 +696,9 @@ impl hb_buffer_t {
             ['\0', '\0', '\0', '\0', '\0'],
         ];
         self.context_len = [0, 0];
+        self.diagnostics = ShapeDiagnostics::default();
+        self.grapheme_starts.clear();
+        self.layout_positions.clear();
 
         self.serial = 0;
         self.scratch_flags = HB_BUFFER_SCRATCH_FLAG_DEFAULT;
@@ -605,15 +785,15 @@ impl hb_buffer_t {
                 self.reverse_range(start, i);
                 start = i;
             }
+        }
 
-            if merge_clusters {
-                self.merge_clusters(start, i);
-            }
+        if merge_clusters {
+            self.merge_clusters(start, self.len);
+        }
 
-            self.reverse_range(start, i);
+        self.reverse_range(start, self.len);
 
-            self.reverse();
-        }
+        self.reverse();
     }
 
     pub fn group_end<F>(&self, mut start: usize, group: F) -> usize
@@ -636,10 +816,25 @@ impl hb_buffer_t {
         }
     }
 
+    /// Replaces codepoints that are not valid characters with U+FFFD.
+    ///
+    /// Returns `true` if any codepoint was replaced.
+    pub fn replace_invalid_chars(&mut self) -> bool {
+        let mut replaced = false;
+        for info in &mut self.info[..self.len] {
+            if char::from_u32(info.glyph_id).is_none() {
+                info.glyph_id = u32::from(char::REPLACEMENT_CHARACTER);
+                replaced = true;
+            }
+        }
+
+        replaced
+    }
+
     pub fn guess_segment_properties(&mut self) {
         if self.script.is_none() {
             for info in &self.info {
-                match info.as_char().script() {
+                match hb_unicode_funcs_get(&self.unicode).script(info.as_char()) {
                     crate::script::COMMON | crate::script::INHERITED | crate::script::UNKNOWN => {}
                     s => {
                         self.script = Some(s);
@@ -647,6 +842,10 @@ impl hb_buffer_t {
                     }
                 }
             }
+
+            if self.script.is_none() {
+                self.script = Some(crate::script::LATIN);
+            }
         }
 
         if self.direction == Direction::Invalid {
@@ -835,6 +1034,13 @@ impl hb_buffer_t {
         self.idx += 1;
     }
 
+    /// Returns the union of all glyph masks in the buffer.
+    pub fn masks_union(&self) -> hb_mask_t {
+        self.info[..self.len]
+            .iter()
+            .fold(0, |union, info| union | info.mask)
+    }
+
     pub fn reset_masks(&mut self, mask: hb_mask_t) {
         for info in &mut self.info[..self.len] {
             info.mask = mask;
@@ -1205,6 +1411,18 @@ impl hb_buffer_t {
         true
     }
 
+    /// Checks whether replacing the current glyph by `count` glyphs keeps the
+    /// buffer within the expansion limit, and records it when it doesn't.
+    pub fn can_expand(&mut self, count: usize) -> bool {
+        let len = self.out_len + (self.len - self.idx) + count.saturating_sub(1);
+        if len > self.max_expanded_len {
+            self.diagnostics.skipped_expansions += 1;
+            return false;
+        }
+
+        true
+    }
+
     pub fn ensure(&mut self, size: usize) -> bool {
         if size < self.len {
             return true;
@@ -1309,6 +1527,7 @@ impl hb_buffer_t {
         self.serial = 0;
         self.shaping_failed = false;
         self.scratch_flags = HB_BUFFER_SCRATCH_FLAG_DEFAULT;
+        self.diagnostics = ShapeDiagnostics::default();
 
         if let Some(len) = self.len.checked_mul(hb_buffer_t::MAX_LEN_FACTOR) {
             self.max_len = len.max(hb_buffer_t::MAX_LEN_MIN);
@@ -1319,16 +1538,48 @@ impl hb_buffer_t {
                 self.max_ops = ops.max(hb_buffer_t::MAX_OPS_MIN);
             }
         }
+
+        if let Some(limit) = self.max_ops_limit {
+            self.max_ops = self.max_ops.min(limit);
+        }
+
+        if let Some(factor) = self.max_expansion_factor {
+            self.max_expanded_len = self.len.saturating_mul(factor);
+        }
     }
 
     // Called around shape()
     pub(crate) fn leave(&mut self) {
         self.max_len = hb_buffer_t::MAX_LEN_DEFAULT;
         self.max_ops = hb_buffer_t::MAX_OPS_DEFAULT;
+        self.max_expanded_len = usize::MAX;
         self.serial = 0;
         // Intentionally not resetting shaping_failed, such that it can be inspected.
     }
 
+    /// Charges the work budget for `ops` operations.
+    ///
+    /// Returns `false` and marks shaping as failed once the budget is exhausted.
+    pub(crate) fn consume_budget(&mut self, ops: usize) -> bool {
+        let Some(budget) = &self.budget else {
+            return true;
+        };
+
+        if !budget.consume(ops) {
+            self.shaping_failed = true;
+            return false;
+        }
+
+        true
+    }
+
+    /// Returns `true` once the work budget is exhausted.
+    pub(crate) fn is_budget_exhausted(&self) -> bool {
+        self.budget
+            .as_ref()
+            .is_some_and(|budget| budget.remaining() == 0)
+    }
+
     fn _infos_find_min_cluster(
         &self,
         info: &[hb_glyph_info_t],
@@ -1450,6 +1701,21 @@ impl hb_buffer_t {
         }
     }
 
+    /// Puts `chars` in front of the context of a side, ordered outward.
+    pub fn prepend_context(&mut self, side: usize, chars: impl Iterator<Item = char>) {
+        let old = self.context[side];
+        let old_len = self.context_len[side];
+        self.clear_context(side);
+        for (i, c) in chars
+            .chain(old[..old_len].iter().copied())
+            .enumerate()
+            .take(CONTEXT_LENGTH)
+        {
+            self.context[side][i] = c;
+            self.context_len[side] += 1;
+        }
+    }
+
     pub fn next_syllable(&self, mut start: usize) -> usize {
         if start >= self.len {
             return start;
@@ -1548,6 +1814,7 @@ bitflags::bitflags! {
         const SUBSTITUTED   = 0x10;
         const LIGATED       = 0x20;
         const MULTIPLIED    = 0x40;
+        const POSITIONED    = 0x80;
 
         const PRESERVE      = Self::SUBSTITUTED.bits() | Self::LIGATED.bits() | Self::MULTIPLIED.bits();
     }
@@ -1656,6 +1923,13 @@ impl UnicodeBuffer {
 
     /// Guess the segment properties (direction, language, script) for the
     /// current buffer.
+    ///
+    /// Properties that are already set are kept. The script is taken from the first
+    /// character with a strong script and the direction is the script's horizontal
+    /// direction. A buffer of only neutral characters, like digits or punctuation,
+    /// is guessed as left-to-right Latin.
+    ///
+    /// The same guess is made by [`shape`](crate::shape) for properties that are not set.
     #[inline]
     pub fn guess_segment_properties(&mut self) {
         self.0.guess_segment_properties()
@@ -1673,6 +1947,98 @@ impl UnicodeBuffer {
         self.0.flags
     }
 
+    /// Set the glyph used to replace default-ignorable characters.
+    ///
+    /// By default, the space glyph is used. Has no effect when
+    /// [`BufferFlags::PRESERVE_DEFAULT_IGNORABLES`] or
+    /// [`BufferFlags::REMOVE_DEFAULT_IGNORABLES`] are set.
+    #[inline]
+    pub fn set_invisible_glyph(&mut self, glyph: GlyphId) {
+        self.0.invisible = Some(glyph);
+    }
+
+    /// Get the glyph used to replace default-ignorable characters.
+    #[inline]
+    pub fn invisible_glyph(&self) -> Option<GlyphId> {
+        self.0.invisible
+    }
+
+    /// Set the glyph used for characters that are not in the font.
+    ///
+    /// By default, `.notdef` is used. Characters that can be decomposed into
+    /// characters of the font, or that are spaces with a fallback, are not affected.
+    #[inline]
+    pub fn set_not_found_glyph(&mut self, glyph: GlyphId) {
+        self.0.not_found = glyph;
+    }
+
+    /// Get the glyph used for characters that are not in the font.
+    #[inline]
+    pub fn not_found_glyph(&self) -> GlyphId {
+        self.0.not_found
+    }
+
+    /// Set the maximum number of operations performed while shaping.
+    ///
+    /// Protects against fonts that would take too long to shape. When the
+    /// limit is exceeded, the remaining nested lookups are skipped, which
+    /// leaves the buffer in a valid state, and
+    /// [`ShapeDiagnostics::shaping_failed`] is set.
+    ///
+    /// By default, the limit depends on the buffer length. It can only be lowered.
+    #[inline]
+    pub fn set_max_ops(&mut self, max_ops: u32) {
+        self.0.max_ops_limit = Some(i32::try_from(max_ops).unwrap_or(i32::MAX));
+    }
+
+    /// Set the maximum expansion of the buffer by multiple substitutions.
+    ///
+    /// Multiple substitutions that would make the buffer longer than `factor`
+    /// times its number of characters are skipped, leaving the glyph as is, and
+    /// counted in [`ShapeDiagnostics::skipped_expansions`]. Protects against
+    /// fonts that replace glyphs by huge sequences.
+    ///
+    /// By default, only the general length limit applies, which aborts shaping
+    /// once the buffer is 64 times longer than its input.
+    #[inline]
+    pub fn set_max_expansion_factor(&mut self, factor: u32) {
+        self.0.max_expansion_factor = Some(factor as usize);
+    }
+
+    /// Set the vertical band that fallback-positioned marks are kept in.
+    ///
+    /// `min_y` and `max_y` are in font units, relative to the baseline, with y
+    /// pointing up, like glyph extents. Marks stacked above a base that would
+    /// reach beyond `max_y`, or below it beyond `min_y`, are moved towards the
+    /// base, the outermost mark the most, so that stacks get compressed instead
+    /// of marks being drawn on top of each other. Useful for tight line spacing.
+    ///
+    /// Only applies to horizontal text shaped with fallback mark positioning,
+    /// which is used for fonts without GPOS mark positioning. The bounds are
+    /// swapped if `min_y` is greater than `max_y`.
+    #[inline]
+    pub fn set_fallback_mark_band(&mut self, min_y: i32, max_y: i32) {
+        self.0.fallback_mark_band = Some((min_y.min(max_y), min_y.max(max_y)));
+    }
+
+    /// Set the work budget used for shaping this buffer.
+    ///
+    /// Unlike [`set_max_ops`](Self::set_max_ops), the budget is consumed by
+    /// every lookup and can be shared between buffers.
+    #[inline]
+    pub fn set_budget(&mut self, budget: ShapeBudget) {
+        self.0.budget = Some(budget);
+    }
+
+    /// Set the maximum nesting level of contextual lookups.
+    ///
+    /// Defaults to 64, like in HarfBuzz, and can only be lowered,
+    /// since deeper nesting could overflow the stack.
+    #[inline]
+    pub fn set_max_nesting_level(&mut self, level: usize) {
+        self.0.max_nesting_level = level.min(MAX_NESTING_LEVEL);
+    }
+
     /// Set the cluster level of the buffer.
     #[inline]
     pub fn set_cluster_level(&mut self, cluster_level: BufferClusterLevel) {
@@ -1705,6 +2071,24 @@ impl UnicodeBuffer {
     pub fn clear(&mut self) {
         self.0.clear()
     }
+
+    /// Sets the Unicode functions used to get character properties.
+    ///
+    /// The built-in Unicode tables are used by default.
+    #[inline]
+    pub fn set_unicode_funcs(&mut self, funcs: Arc<dyn UnicodeFuncs>) {
+        self.0.unicode = Some(funcs);
+    }
+
+    /// Get the mutable glyph infos.
+    ///
+    /// Before shaping, `glyph_id` contains a codepoint. Codepoints that are not
+    /// valid characters are replaced with U+FFFD when shaping.
+    /// Mainly useful inside [`ComplexShaper::preprocess_text`](crate::ComplexShaper::preprocess_text).
+    #[inline]
+    pub fn glyph_infos_mut(&mut self) -> &mut [hb_glyph_info_t] {
+        &mut self.0.info[0..self.0.len]
+    }
 }
 
 impl core::fmt::Debug for UnicodeBuffer {
@@ -1724,6 +2108,39 @@ impl Default for UnicodeBuffer {
     }
 }
 
+/// The results of the shaping process, with each glyph property in its own array.
+///
+/// Created by [`GlyphBuffer::into_soa`] or filled by [`GlyphBuffer::write_soa`].
+/// All arrays have the same length and the glyph at index `i` is described by
+/// the `i`th element of each of them.
+#[derive(Clone, Default, Debug)]
+pub struct GlyphArrays {
+    /// The glyph ids.
+    pub glyphs: Vec<u32>,
+    /// The clusters, see [`GlyphInfo::cluster`](crate::GlyphInfo::cluster).
+    pub clusters: Vec<u32>,
+    /// The horizontal advances, see [`GlyphPosition::x_advance`].
+    pub x_advances: Vec<i32>,
+    /// The vertical advances, see [`GlyphPosition::y_advance`].
+    pub y_advances: Vec<i32>,
+    /// The horizontal offsets, see [`GlyphPosition::x_offset`].
+    pub x_offsets: Vec<i32>,
+    /// The vertical offsets, see [`GlyphPosition::y_offset`].
+    pub y_offsets: Vec<i32>,
+}
+
+impl GlyphArrays {
+    /// Removes all glyphs, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        self.clusters.clear();
+        self.x_advances.clear();
+        self.y_advances.clear();
+        self.x_offsets.clear();
+        self.y_offsets.clear();
+    }
+}
+
 /// A buffer that contains the results of the shaping process.
 pub struct GlyphBuffer(pub(crate) hb_buffer_t);
 
@@ -1756,6 +2173,96 @@ impl GlyphBuffer {
         &self.0.pos[0..self.0.len]
     }
 
+    /// Get the mutable glyph infos.
+    ///
+    /// Mainly useful inside [`ComplexShaper`](crate::ComplexShaper) hooks.
+    #[inline]
+    pub fn glyph_infos_mut(&mut self) -> &mut [hb_glyph_info_t] {
+        &mut self.0.info[0..self.0.len]
+    }
+
+    /// Get the diagnostics collected while shaping.
+    #[inline]
+    pub fn diagnostics(&self) -> &ShapeDiagnostics {
+        &self.0.diagnostics
+    }
+
+    /// Returns the range of `text` each glyph was shaped from.
+    ///
+    /// `text` must be the string the buffer was shaped from using
+    /// [`UnicodeBuffer::push_str`], so that clusters are byte offsets into it.
+    ///
+    /// A cluster covers the text up to the start of the next cluster, in any
+    /// glyph order. The glyphs of a ligature or of a reordered syllable share a
+    /// range that spans all of their characters, while the glyphs of a
+    /// decomposed character share the range of that character.
+    pub fn text_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut starts: Vec<u32> = self.glyph_infos().iter().map(|i| i.cluster).collect();
+        starts.sort_unstable();
+        starts.dedup();
+
+        self.glyph_infos()
+            .iter()
+            .map(|info| {
+                let next = starts.partition_point(|&cluster| cluster <= info.cluster);
+                let end = starts
+                    .get(next)
+                    .map_or(text.len(), |&cluster| cluster as usize);
+                info.cluster as usize..end
+            })
+            .collect()
+    }
+
+    /// Returns the index of the input grapheme cluster each glyph belongs to.
+    ///
+    /// Only produced when [`BufferFlags::PRODUCE_GRAPHEME_INDICES`] is set,
+    /// otherwise nothing is returned. Expects cluster values to increase in
+    /// input order, which is the case for text added using
+    /// [`UnicodeBuffer::push_str`].
+    pub fn grapheme_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        let starts = &self.0.grapheme_starts;
+        let infos = if starts.is_empty() {
+            &[][..]
+        } else {
+            self.glyph_infos()
+        };
+
+        infos.iter().map(move |info| {
+            starts
+                .partition_point(|&cluster| cluster <= info.cluster)
+                .saturating_sub(1)
+        })
+    }
+
+    /// Converts the buffer into separate arrays of glyph ids, clusters and
+    /// positions.
+    ///
+    /// Useful for uploading the results to a GPU or when only some of the
+    /// properties are needed.
+    pub fn into_soa(self) -> GlyphArrays {
+        let mut arrays = GlyphArrays::default();
+        self.write_soa(&mut arrays);
+        arrays
+    }
+
+    /// Same as [`into_soa`](Self::into_soa), but replaces the content of
+    /// existing arrays, so that their allocations can be reused.
+    pub fn write_soa(&self, out: &mut GlyphArrays) {
+        let infos = self.glyph_infos();
+        let positions = self.glyph_positions();
+        out.clear();
+        out.glyphs.extend(infos.iter().map(|info| info.glyph_id));
+        out.clusters.extend(infos.iter().map(|info| info.cluster));
+        out.x_advances
+            .extend(positions.iter().map(|pos| pos.x_advance));
+        out.y_advances
+            .extend(positions.iter().map(|pos| pos.y_advance));
+        out.x_offsets
+            .extend(positions.iter().map(|pos| pos.x_offset));
+        out.y_offsets
+            .extend(positions.iter().map(|pos| pos.y_offset));
+    }
+
     /// Clears the content of the glyph buffer and returns an empty
     /// `UnicodeBuffer` reusing the existing allocation.
     #[inline]
@@ -1816,8 +2323,7 @@ impl GlyphBuffer {
             }
 
             if flags.contains(SerializeFlags::GLYPH_EXTENTS) {
-                let mut extents = hb_glyph_extents_t::default();
-                face.glyph_extents(info.as_glyph(), &mut extents);
+                let extents = face.glyph_extents(info.as_glyph()).unwrap_or_default();
                 write!(
                     &mut s,
                     "<{},{},{},{}>",
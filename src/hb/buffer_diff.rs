@@ -0,0 +1,144 @@
+//! Comparison of shaping results.
+
+use core::fmt;
+
+use super::buffer::{glyph_flag, hb_glyph_info_t, GlyphBuffer, GlyphPosition};
+use crate::BufferDiffFlags;
+
+/// The difference between a [`GlyphBuffer`] and a reference one.
+///
+/// Created by [`GlyphBuffer::diff`]. Its `Display` implementation describes the first
+/// differing glyph in a human-readable way.
+#[derive(Clone, Copy, Debug)]
+pub struct GlyphBufferDiff {
+    /// The kinds of differences found in the whole buffer.
+    pub flags: BufferDiffFlags,
+    /// The index of the first glyph that differs, `None` when the buffers are equal.
+    ///
+    /// When one buffer is a prefix of the other, this is the length of the shorter one.
+    pub index: Option<usize>,
+    /// The glyph of the buffer at `index`, if any.
+    pub glyph: Option<(hb_glyph_info_t, GlyphPosition)>,
+    /// The glyph of the reference buffer at `index`, if any.
+    pub reference: Option<(hb_glyph_info_t, GlyphPosition)>,
+}
+
+impl GlyphBufferDiff {
+    /// Checks that no differences were found.
+    ///
+    /// A `.notdef` glyph in the buffer is not a difference.
+    #[inline]
+    pub fn is_equal(&self) -> bool {
+        self.index.is_none()
+    }
+}
+
+impl GlyphBuffer {
+    /// Compares the buffer to a `reference` one, like `hb_buffer_diff`.
+    ///
+    /// Glyph ids, clusters, glyph flags and positions are compared. Positions may
+    /// differ by up to `position_fuzz`, which allows for the rounding differences
+    /// of variable fonts. Like the positions, it is in 1/64 of a font unit when
+    /// the buffers were shaped with [`BufferFlags::PRODUCE_FRACTIONAL_POSITIONS`](crate::BufferFlags::PRODUCE_FRACTIONAL_POSITIONS),
+    /// and in font units otherwise. Glyphs are compared up to the length of the
+    /// shorter buffer.
+    pub fn diff(&self, reference: &GlyphBuffer, position_fuzz: u32) -> GlyphBufferDiff {
+        let infos = self.glyph_infos();
+        let positions = self.glyph_positions();
+        let ref_infos = reference.glyph_infos();
+        let ref_positions = reference.glyph_positions();
+
+        let mut flags = BufferDiffFlags::EQUAL;
+        let mut index = None;
+
+        if infos.len() != ref_infos.len() {
+            flags |= BufferDiffFlags::LENGTH_MISMATCH;
+        }
+
+        if infos.iter().any(|info| info.glyph_id == 0) {
+            flags |= BufferDiffFlags::NOTDEF_PRESENT;
+        }
+
+        let is_fuzzy_equal = |a: i32, b: i32| a.abs_diff(b) <= position_fuzz;
+
+        let glyphs = infos.iter().zip(positions);
+        let ref_glyphs = ref_infos.iter().zip(ref_positions);
+        for (i, ((info, pos), (ref_info, ref_pos))) in glyphs.zip(ref_glyphs).enumerate() {
+            let mut glyph_flags = BufferDiffFlags::EQUAL;
+            if info.glyph_id != ref_info.glyph_id {
+                glyph_flags |= BufferDiffFlags::CODEPOINT_MISMATCH;
+            }
+
+            if info.cluster != ref_info.cluster {
+                glyph_flags |= BufferDiffFlags::CLUSTER_MISMATCH;
+            }
+
+            if (info.mask ^ ref_info.mask) & glyph_flag::DEFINED != 0 {
+                glyph_flags |= BufferDiffFlags::GLYPH_FLAGS_MISMATCH;
+            }
+
+            if !is_fuzzy_equal(pos.x_advance, ref_pos.x_advance)
+                || !is_fuzzy_equal(pos.y_advance, ref_pos.y_advance)
+                || !is_fuzzy_equal(pos.x_offset, ref_pos.x_offset)
+                || !is_fuzzy_equal(pos.y_offset, ref_pos.y_offset)
+            {
+                glyph_flags |= BufferDiffFlags::POSITION_MISMATCH;
+            }
+
+            if !glyph_flags.is_empty() && index.is_none() {
+                index = Some(i);
+            }
+
+            flags |= glyph_flags;
+        }
+
+        if index.is_none() && flags.contains(BufferDiffFlags::LENGTH_MISMATCH) {
+            index = Some(infos.len().min(ref_infos.len()));
+        }
+
+        let glyph_at = |buffer: &GlyphBuffer, i: usize| {
+            let info = *buffer.glyph_infos().get(i)?;
+            let pos = *buffer.glyph_positions().get(i)?;
+            Some((info, pos))
+        };
+
+        GlyphBufferDiff {
+            flags,
+            index,
+            glyph: index.and_then(|i| glyph_at(self, i)),
+            reference: index.and_then(|i| glyph_at(reference, i)),
+        }
+    }
+}
+
+impl fmt::Display for GlyphBufferDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_glyph(
+            f: &mut fmt::Formatter<'_>,
+            glyph: Option<(hb_glyph_info_t, GlyphPosition)>,
+        ) -> fmt::Result {
+            match glyph {
+                Some((info, pos)) => write!(
+                    f,
+                    "id {} cluster {} advance {},{} offset {},{}",
+                    info.glyph_id,
+                    info.cluster,
+                    pos.x_advance,
+                    pos.y_advance,
+                    pos.x_offset,
+                    pos.y_offset
+                ),
+                None => write!(f, "no glyph"),
+            }
+        }
+
+        let Some(index) = self.index else {
+            return write!(f, "buffers are equal");
+        };
+
+        write!(f, "glyph {} differs: ", index)?;
+        write_glyph(f, self.glyph)?;
+        write!(f, ", expected ")?;
+        write_glyph(f, self.reference)
+    }
+}
@@ -0,0 +1,242 @@
+//! Flattening of COLR paint graphs into fills.
+
+use alloc::vec::Vec;
+
+use skrifa::color::{Brush, ColorPainter, CompositeMode, Extend, Transform as PaintTransform};
+use skrifa::raw::types::BoundingBox;
+use ttf_parser::colr::GradientExtend;
+use ttf_parser::{GlyphId, RgbaColor, Transform};
+
+/// A fill of a flattened color glyph.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ColorFill {
+    /// The glyph whose outline is filled.
+    ///
+    /// `None` when the fill isn't clipped by an outline, in which case it covers
+    /// the clip box of the color glyph.
+    pub glyph: Option<GlyphId>,
+    /// The transform of the glyph outline, in font units.
+    pub glyph_transform: Transform,
+    /// The color or gradient the glyph is filled with.
+    pub brush: ColorBrush,
+    /// The transform of the brush, in font units.
+    ///
+    /// Differs from `glyph_transform` when the paint graph transforms the brush only.
+    pub brush_transform: Transform,
+}
+
+/// The color or gradient of a [`ColorFill`].
+///
+/// Gradient stops are sorted and normalized to the range from 0 to 1, the points of
+/// the gradient being moved accordingly.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ColorBrush {
+    /// A solid color.
+    Solid(RgbaColor),
+    /// A linear gradient from `p0` to `p1`.
+    LinearGradient {
+        /// The start point.
+        p0: (f32, f32),
+        /// The end point.
+        p1: (f32, f32),
+        /// The color stops.
+        stops: Vec<ColorStop>,
+        /// How the gradient is extended beyond its stops.
+        extend: GradientExtend,
+    },
+    /// A radial gradient between two circles.
+    RadialGradient {
+        /// The center of the start circle.
+        c0: (f32, f32),
+        /// The radius of the start circle.
+        r0: f32,
+        /// The center of the end circle.
+        c1: (f32, f32),
+        /// The radius of the end circle.
+        r1: f32,
+        /// The color stops.
+        stops: Vec<ColorStop>,
+        /// How the gradient is extended beyond its stops.
+        extend: GradientExtend,
+    },
+    /// A sweep gradient around a center.
+    ///
+    /// The angles are in degrees, clockwise from the positive x axis.
+    SweepGradient {
+        /// The center.
+        center: (f32, f32),
+        /// The start angle.
+        start_angle: f32,
+        /// The end angle.
+        end_angle: f32,
+        /// The color stops.
+        stops: Vec<ColorStop>,
+        /// How the gradient is extended beyond its stops.
+        extend: GradientExtend,
+    },
+}
+
+/// A color stop of a gradient.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ColorStop {
+    /// The position on the color line, from 0 to 1.
+    pub offset: f32,
+    /// The color, with the alpha of the stop applied.
+    pub color: RgbaColor,
+}
+
+/// Collects the fills of a paint graph.
+///
+/// Fills clipped by more than one glyph outline and layers composited with a mode
+/// other than source-over can't be represented as a list of fills and make
+/// the whole glyph unsupported.
+pub(crate) struct hb_color_fills_context_t<F> {
+    palette_color: F,
+    transforms: Vec<PaintTransform>,
+    // Glyph outlines with their transform, `None` for clip boxes.
+    clips: Vec<Option<(GlyphId, PaintTransform)>>,
+    pub(crate) fills: Vec<ColorFill>,
+    pub(crate) unsupported: bool,
+}
+
+impl<F: Fn(u16) -> RgbaColor> hb_color_fills_context_t<F> {
+    /// `palette_color` resolves a palette entry, 0xFFFF being the foreground color.
+    pub(crate) fn new(palette_color: F) -> Self {
+        hb_color_fills_context_t {
+            palette_color,
+            transforms: Vec::new(),
+            clips: Vec::new(),
+            fills: Vec::new(),
+            unsupported: false,
+        }
+    }
+
+    fn current_transform(&self) -> PaintTransform {
+        self.transforms.last().copied().unwrap_or_default()
+    }
+
+    fn color(&self, palette_index: u16, alpha: f32) -> RgbaColor {
+        let mut color = (self.palette_color)(palette_index);
+        color.alpha = (f32::from(color.alpha) * alpha.clamp(0.0, 1.0)).round() as u8;
+        color
+    }
+
+    fn stops(&self, stops: &[skrifa::color::ColorStop]) -> Vec<ColorStop> {
+        stops
+            .iter()
+            .map(|stop| ColorStop {
+                offset: stop.offset,
+                color: self.color(stop.palette_index, stop.alpha),
+            })
+            .collect()
+    }
+}
+
+impl<F: Fn(u16) -> RgbaColor> ColorPainter for hb_color_fills_context_t<F> {
+    fn push_transform(&mut self, transform: PaintTransform) {
+        let transform = self.current_transform() * transform;
+        self.transforms.push(transform);
+    }
+
+    fn pop_transform(&mut self) {
+        self.transforms.pop();
+    }
+
+    fn push_clip_glyph(&mut self, glyph_id: skrifa::GlyphId) {
+        if self.clips.iter().any(Option::is_some) {
+            self.unsupported = true;
+        }
+
+        let glyph = GlyphId(glyph_id.to_u32() as u16);
+        self.clips.push(Some((glyph, self.current_transform())));
+    }
+
+    fn push_clip_box(&mut self, _: BoundingBox<f32>) {
+        // Clip boxes only bound the glyph and are not reported.
+        self.clips.push(None);
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    fn fill(&mut self, brush: Brush<'_>) {
+        let point = |p: skrifa::raw::types::Point<f32>| (p.x, p.y);
+        let brush = match brush {
+            Brush::Solid {
+                palette_index,
+                alpha,
+            } => ColorBrush::Solid(self.color(palette_index, alpha)),
+            Brush::LinearGradient {
+                p0,
+                p1,
+                color_stops,
+                extend,
+            } => ColorBrush::LinearGradient {
+                p0: point(p0),
+                p1: point(p1),
+                stops: self.stops(color_stops),
+                extend: convert_extend(extend),
+            },
+            Brush::RadialGradient {
+                c0,
+                r0,
+                c1,
+                r1,
+                color_stops,
+                extend,
+            } => ColorBrush::RadialGradient {
+                c0: point(c0),
+                r0,
+                c1: point(c1),
+                r1,
+                stops: self.stops(color_stops),
+                extend: convert_extend(extend),
+            },
+            Brush::SweepGradient {
+                c0,
+                start_angle,
+                end_angle,
+                color_stops,
+                extend,
+            } => ColorBrush::SweepGradient {
+                center: point(c0),
+                start_angle,
+                end_angle,
+                stops: self.stops(color_stops),
+                extend: convert_extend(extend),
+            },
+        };
+
+        let (glyph, glyph_transform) = match self.clips.iter().rev().find_map(|clip| *clip) {
+            Some((glyph, transform)) => (Some(glyph), transform),
+            None => (None, PaintTransform::default()),
+        };
+        self.fills.push(ColorFill {
+            glyph,
+            glyph_transform: convert_transform(glyph_transform),
+            brush,
+            brush_transform: convert_transform(self.current_transform()),
+        });
+    }
+
+    fn push_layer(&mut self, composite_mode: CompositeMode) {
+        if composite_mode != CompositeMode::SrcOver {
+            self.unsupported = true;
+        }
+    }
+
+    fn pop_layer(&mut self) {}
+}
+
+fn convert_transform(ts: PaintTransform) -> Transform {
+    Transform::new(ts.xx, ts.yx, ts.xy, ts.yy, ts.dx, ts.dy)
+}
+
+fn convert_extend(extend: Extend) -> GradientExtend {
+    match extend {
+        Extend::Repeat => GradientExtend::Repeat,
+        Extend::Reflect => GradientExtend::Reflect,
+        _ => GradientExtend::Pad,
+    }
+}
@@ -1,4 +1,5 @@
-use alloc::string::String;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::ops::{Bound, RangeBounds};
 
 use ttf_parser::Tag;
@@ -190,6 +191,17 @@ impl Language {
     pub fn as_str(&self) -> &str {
         self.0.as_str()
     }
+
+    /// Returns the OpenType language system tags for this BCP 47 language,
+    /// in priority order.
+    ///
+    /// Some languages map to several tags, like Chinese in Hong Kong, which
+    /// falls back to traditional Chinese. A private use subtag like
+    /// `x-hbotABC` selects the tag directly.
+    pub fn to_ot_tags(&self) -> impl Iterator<Item = Tag> {
+        let (_, languages) = super::tag::tags_from_script_and_language(None, Some(self));
+        languages.into_iter()
+    }
 }
 
 impl core::str::FromStr for Language {
@@ -258,6 +270,15 @@ impl Script {
     pub fn tag(&self) -> Tag {
         self.0
     }
+
+    /// Returns the OpenType script tags for this script, in priority order.
+    ///
+    /// Indic scripts and Myanmar have newer tags that select a different
+    /// shaping model, like `dev3` and `dev2` before `deva` for Devanagari.
+    pub fn to_ot_tags(&self) -> impl Iterator<Item = Tag> {
+        let (scripts, _) = super::tag::tags_from_script_and_language(Some(*self), None);
+        scripts.into_iter()
+    }
 }
 
 impl core::str::FromStr for Script {
@@ -510,7 +531,7 @@ impl Feature {
 impl core::str::FromStr for Feature {
     type Err = &'static str;
 
-    /// Parses a `Feature` form a string.
+    /// Parses a `Feature` from a string, using the `hb-shape --features` syntax.
     ///
     /// Possible values:
     ///
@@ -519,14 +540,15 @@ impl core::str::FromStr for Feature {
     /// - `-kern` -> kern .. 0
     /// - `kern=0` -> kern .. 0
     /// - `kern=1` -> kern .. 1
-    /// - `aalt=2` -> altr .. 2
+    /// - `aalt=2` -> aalt .. 2
     /// - `kern[]` -> kern .. 1
     /// - `kern[:]` -> kern .. 1
     /// - `kern[5:]` -> kern 5.. 1
     /// - `kern[:5]` -> kern ..=5 1
     /// - `kern[3:5]` -> kern 3..=5 1
     /// - `kern[3]` -> kern 3..=4 1
-    /// - `aalt[3:5]=2` -> kern 3..=5 1
+    /// - `aalt[3:5]=2` -> aalt 3..=5 2
+    /// - `"kern"` -> kern .. 1
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         fn parse(s: &str) -> Option<Feature> {
             if s.is_empty() {
@@ -654,6 +676,18 @@ mod tests_features {
     test!(parse_18, "kern=off", b"kern", 0, ..);
     test!(parse_19, "kern=oN", b"kern", 1, ..);
     test!(parse_20, "kern=oFf", b"kern", 0, ..);
+    test!(parse_21, "aalt=2", b"aalt", 2, ..);
+    test!(parse_22, "-aalt[3:5]=2", b"aalt", 2, 3..=5);
+    test!(parse_23, "\"liga\"", b"liga", 1, ..);
+    test!(parse_24, "- 'liga' [2:] ", b"liga", 0, 2..);
+    test!(parse_25, "cv1", b"cv1 ", 1, ..);
+
+    #[test]
+    fn parse_invalid() {
+        for text in ["", "-", "kern=", "kern[3:5", "kern]", "'kern", "kern=maybe"] {
+            assert!(Feature::from_str(text).is_err(), "{text:?}");
+        }
+    }
 }
 
 /// A font variation.
@@ -665,9 +699,62 @@ pub struct Variation {
     pub value: f32,
 }
 
+impl Variation {
+    /// Parses a comma-separated list of variations, like `wght=700, wdth=85`.
+    ///
+    /// Each entry uses the [`from_str`](core::str::FromStr::from_str) syntax.
+    /// An empty or blank string is an empty list.
+    pub fn parse_list(s: &str) -> Result<Vec<Variation>, InvalidVariation> {
+        use core::str::FromStr;
+
+        if s.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        s.split(',')
+            .enumerate()
+            .map(|(index, entry)| {
+                Variation::from_str(entry).map_err(|_| InvalidVariation {
+                    index,
+                    entry: entry.trim().to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// An invalid entry of a variation list, see [`Variation::parse_list`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InvalidVariation {
+    /// The index of the entry in the list.
+    pub index: usize,
+    /// The entry, without surrounding whitespace.
+    pub entry: String,
+}
+
+impl core::fmt::Display for InvalidVariation {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "invalid variation {:?} at index {}",
+            self.entry, self.index
+        )
+    }
+}
+
 impl core::str::FromStr for Variation {
     type Err = &'static str;
 
+    /// Parses a `Variation` from a string, using the `hb-shape --variations` syntax.
+    ///
+    /// Possible values:
+    ///
+    /// - `wght=700` -> wght 700
+    /// - `wght 700` -> wght 700
+    /// - ` wght = 700 ` -> wght 700
+    /// - `'wdth'=85.5` -> wdth 85.5
+    ///
+    /// Lists like `wght=700,wdth=85` are parsed by [`Variation::parse_list`].
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         fn parse(s: &str) -> Option<Variation> {
             if s.is_empty() {
@@ -687,7 +774,11 @@ impl core::str::FromStr for Variation {
                 p.consume_byte(quote)?;
             }
 
+            // The equal sign is optional.
+            p.skip_spaces();
             let _ = p.consume_byte(b'=');
+            p.skip_spaces();
+
             let value = p.consume_f32()?;
             p.skip_spaces();
 
@@ -702,6 +793,88 @@ impl core::str::FromStr for Variation {
     }
 }
 
+#[cfg(test)]
+mod tests_variations {
+    use super::*;
+    use core::str::FromStr;
+
+    macro_rules! test {
+        ($name:ident, $text:expr, $tag:expr, $value:expr) => {
+            #[test]
+            fn $name() {
+                assert_eq!(
+                    Variation::from_str($text).unwrap(),
+                    Variation {
+                        tag: Tag::from_bytes($tag),
+                        value: $value,
+                    }
+                );
+            }
+        };
+    }
+
+    test!(parse_01, "wght=700", b"wght", 700.0);
+    test!(parse_02, "wght 700", b"wght", 700.0);
+    test!(parse_03, " wght = 700 ", b"wght", 700.0);
+    test!(parse_04, "wdth=85.5", b"wdth", 85.5);
+    test!(parse_05, "slnt=-12", b"slnt", -12.0);
+    test!(parse_06, "'XHGT'=.5", b"XHGT", 0.5);
+    test!(parse_07, "\"opsz\" 12", b"opsz", 12.0);
+
+    #[test]
+    fn parse_list() {
+        assert_eq!(
+            Variation::parse_list(" wght=700 ,wdth 85 ").unwrap(),
+            [
+                Variation {
+                    tag: Tag::from_bytes(b"wght"),
+                    value: 700.0,
+                },
+                Variation {
+                    tag: Tag::from_bytes(b"wdth"),
+                    value: 85.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_list_invalid() {
+        assert_eq!(Variation::parse_list(" ").unwrap(), []);
+        assert_eq!(
+            Variation::parse_list("wght=700, weight=85").unwrap_err(),
+            InvalidVariation {
+                index: 1,
+                entry: "weight=85".into(),
+            }
+        );
+        assert_eq!(
+            Variation::parse_list("wght=700,").unwrap_err(),
+            InvalidVariation {
+                index: 1,
+                entry: "".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for text in [
+            "",
+            "wght",
+            "wght=",
+            "=700",
+            "weight=700",
+            "wght700",
+            "'wght=700",
+            "wght=700x",
+            "wght=a",
+        ] {
+            assert!(Variation::from_str(text).is_err(), "{text:?}");
+        }
+    }
+}
+
 pub trait TagExt {
     fn default_script() -> Self;
     fn default_language() -> Self;
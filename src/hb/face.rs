@@ -1,16 +1,140 @@
 #[cfg(not(feature = "std"))]
 use core_maths::CoreFloat;
 
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::hb::color::{hb_color_fills_context_t, ColorFill};
 use crate::hb::paint_extents::hb_paint_extents_context_t;
+use once_cell::race::OnceBox;
+use skrifa::raw::tables::glyf::{Anchor, Glyf, Glyph};
+use skrifa::raw::tables::gpos::AnchorTable;
+use skrifa::raw::tables::gvar::Gvar;
+use skrifa::raw::tables::loca::Loca;
+use skrifa::raw::types::F2Dot14;
+use skrifa::raw::TableProvider;
+use skrifa::MetadataProvider;
 use ttf_parser::gdef::GlyphClass;
-use ttf_parser::opentype_layout::LayoutTable;
-use ttf_parser::{GlyphId, RgbaColor};
+use ttf_parser::gsub::SubstitutionSubtable;
+use ttf_parser::opentype_layout::{LanguageSystem, LayoutTable};
+use ttf_parser::{GlyphId, NormalizedCoordinate, OutlineBuilder, RgbaColor, Tag};
 
 use super::buffer::GlyphPropsFlags;
 use super::fonta;
-use super::ot_layout::TableIndex;
+pub use super::fonta::ot::LookupCacheStats;
+use super::ot_layout::{LayoutTable as _, LayoutTableExt, TableIndex};
 use super::ot_layout_common::{PositioningTable, SubstitutionTable};
-use crate::Variation;
+use super::ot_layout_gsubgpos::{WouldApply, WouldApplyContext};
+use super::ot_math::{self, MathConstant, MathGlyphAssembly, MathGlyphVariant};
+use super::ot_var;
+use super::set_digest::{hb_set_digest_ext, hb_set_digest_t};
+use super::tag;
+use crate::{ComplexShaper, Direction, Language, PaletteFlags, Script, Variation};
+
+/// A named instance of a variable font.
+#[derive(Clone, PartialEq, Debug)]
+pub struct NamedInstance {
+    /// The `name` table ID of the instance subfamily name, like "Bold".
+    pub subfamily_name_id: u16,
+    /// The `name` table ID of the instance PostScript name, if any.
+    pub postscript_name_id: Option<u16>,
+    /// The user-space coordinates of the instance, one per axis.
+    pub coordinates: Vec<Variation>,
+}
+
+/// The `name` table IDs of a stylistic set or character variant feature.
+///
+/// Use [`ttf_parser::Face::names`] to resolve them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FeatureNameIds {
+    /// The ID of the feature label, like "Single-storey a".
+    pub label: Option<u16>,
+    /// The ID of the tooltip text of a character variant.
+    pub tooltip: Option<u16>,
+    /// The ID of the sample text of a character variant.
+    pub sample: Option<u16>,
+    /// The ID of the label of the first named parameter of a character
+    /// variant. The other parameters use the following IDs.
+    pub first_param: Option<u16>,
+    /// The number of named parameters of a character variant.
+    pub num_params: u16,
+}
+
+/// The optical size parameters of the GPOS `size` feature.
+///
+/// Sizes are in decipoints, i.e. tenths of a point.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SizeParams {
+    /// The size the font was designed for.
+    pub design_size: u16,
+    /// Identifies the fonts of a family that only differ in their design size,
+    /// or 0 if there is no recommended size range.
+    pub subfamily_id: u16,
+    /// The `name` table ID of the subfamily name, like "Caption".
+    pub subfamily_name_id: Option<u16>,
+    /// The exclusive start of the recommended size range, or 0.
+    pub range_start: u16,
+    /// The inclusive end of the recommended size range, or 0.
+    pub range_end: u16,
+}
+
+/// A `cmap` subtable, identified by its encoding record.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CmapSubtableInfo {
+    /// The platform ID, like 3 for Windows.
+    pub platform_id: u16,
+    /// The platform-specific encoding ID, like 0 for symbol and 1 for Unicode BMP
+    /// on the Windows platform.
+    pub encoding_id: u16,
+    /// The subtable format, like 4 or 12.
+    pub format: u16,
+}
+
+/// A feature of the AAT `feat` table.
+///
+/// Use [`ttf_parser::Face::names`] to resolve the name IDs.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AatFeature {
+    /// The feature type, like 37 for lower case.
+    pub kind: u16,
+    /// The `name` table ID of the feature label, like "Lower Case".
+    pub name_id: u16,
+    /// Whether the selectors are mutually exclusive. Otherwise, each of them
+    /// can be enabled and disabled independently.
+    pub exclusive: bool,
+    /// The index of the selector that is enabled by default, for exclusive
+    /// features.
+    pub default_index: Option<usize>,
+    /// The selectors of the feature.
+    pub selectors: Vec<AatFeatureSelector>,
+}
+
+/// A selector of an AAT feature.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AatFeatureSelector {
+    /// The `name` table ID of the selector label, like "Small Capitals".
+    pub name_id: u16,
+    /// The selector value that enables the setting.
+    pub enable: u16,
+    /// The selector value that disables the setting. This is the default
+    /// selector for exclusive features.
+    pub disable: u16,
+}
+
+impl FeatureNameIds {
+    /// Returns the IDs of the labels of the named parameters.
+    pub fn param_ids(&self) -> impl Iterator<Item = u16> {
+        let first = self.first_param.unwrap_or(0);
+        let count = if self.first_param.is_some() {
+            self.num_params
+        } else {
+            0
+        };
+        (0..count).map_while(move |i| first.checked_add(i))
+    }
+}
 
 /// A font face handle.
 #[derive(Clone)]
@@ -20,8 +144,19 @@ pub struct hb_font_t<'a> {
     pub(crate) units_per_em: u16,
     pixels_per_em: Option<(u16, u16)>,
     pub(crate) points_per_em: Option<f32>,
+    pub(crate) slant: f32,
+    embolden: (f32, f32, bool),
     pub(crate) gsub: Option<SubstitutionTable<'a>>,
     pub(crate) gpos: Option<PositioningTable<'a>>,
+    design_coords: Vec<f32>,
+    coords: Vec<NormalizedCoordinate>,
+    // `coords` for skrifa.
+    skrifa_coords: Vec<F2Dot14>,
+    shapers: Vec<(Script, Arc<dyn ComplexShaper>)>,
+    glyph_class_overrides: Vec<(GlyphId, GlyphClass)>,
+    face_index: u32,
+    // The lowest character of each glyph, built on first use.
+    reverse_cmap: OnceBox<Vec<Option<char>>>,
 }
 
 impl<'a> AsRef<ttf_parser::Face<'a>> for hb_font_t<'a> {
@@ -57,34 +192,121 @@ impl<'a> core::ops::DerefMut for hb_font_t<'a> {
 impl<'a> hb_font_t<'a> {
     /// Creates a new `Face` from data.
     ///
-    /// Data will be referenced, not owned.
+    /// Data will be referenced, not owned, so it can be memory-mapped. The face borrows
+    /// the data and can't outlive it, so shared data, e.g. an `Arc<[u8]>`, must be kept
+    /// alive as long as the face. `face_index` selects the face of a
+    /// font collection and must be 0 for other fonts, see [`face_count`](Self::face_count).
     pub fn from_slice(data: &'a [u8], face_index: u32) -> Option<Self> {
         let face = ttf_parser::Face::parse(data, face_index).ok()?;
         let font = fonta::Font::new(data, face_index)?;
-        Some(hb_font_t {
-            font,
-            units_per_em: face.units_per_em(),
-            pixels_per_em: None,
-            points_per_em: None,
-            gsub: face.tables().gsub.map(SubstitutionTable::new),
-            gpos: face.tables().gpos.map(PositioningTable::new),
-            ttfp_face: face,
-        })
+        Some(Self::from_parts(face, font, face_index))
+    }
+
+    /// Returns the number of faces in font data.
+    ///
+    /// This is the number of fonts in a collection (`ttc`/`otc`) and 1 for other fonts.
+    pub fn face_count(data: &[u8]) -> u32 {
+        ttf_parser::fonts_in_collection(data).unwrap_or(1)
+    }
+
+    /// Returns the index of the face in its font collection, 0 for other fonts.
+    pub fn face_index(&self) -> u32 {
+        self.face_index
+    }
+
+    /// Creates a new `Face` from data that only shapes the given glyphs.
+    ///
+    /// Only the `GSUB` and `GPOS` lookups whose coverage may contain one of
+    /// `glyphs` are built, which makes loading a large font faster when it is
+    /// used for a short text.
+    ///
+    /// Lookups that were not built are never skipped: they are applied by the
+    /// slower fallback layout implementation, with the same result. Each of them
+    /// is counted in
+    /// [`ShapeDiagnostics::fallback_lookups`](crate::ShapeDiagnostics::fallback_lookups),
+    /// which shows when `glyphs` was missing some glyphs of the text.
+    /// `glyphs` should also contain the glyphs that lookups can produce, e.g.
+    /// all glyphs of a font subset.
+    pub fn from_slice_for_glyphs(
+        data: &'a [u8],
+        face_index: u32,
+        glyphs: &[GlyphId],
+    ) -> Option<Self> {
+        let mut digest = hb_set_digest_t::new();
+        digest.add_array(glyphs.iter().copied());
+        let face = ttf_parser::Face::parse(data, face_index).ok()?;
+        let font = fonta::Font::with_glyphs(data, face_index, &digest)?;
+        Some(Self::from_parts(face, font, face_index))
+    }
+
+    /// Creates a new `Face` from data and the lookup caches of a previously
+    /// loaded face, see [`lookup_caches`](Self::lookup_caches).
+    ///
+    /// This skips building the lookup caches of the `GSUB` and `GPOS` tables,
+    /// which takes most of the loading time of large fonts.
+    ///
+    /// Returns `None` if the caches are malformed or were created
+    /// from a face with different `GSUB` or `GPOS` tables.
+    pub fn from_slice_with_lookup_caches(
+        data: &'a [u8],
+        face_index: u32,
+        caches: &[u8],
+    ) -> Option<Self> {
+        let face = ttf_parser::Face::parse(data, face_index).ok()?;
+        let font = fonta::Font::with_lookup_caches(data, face_index, caches)?;
+        Some(Self::from_parts(face, font, face_index))
+    }
+
+    /// Serializes the lookup caches of the face.
+    ///
+    /// The result can be stored and passed to
+    /// [`from_slice_with_lookup_caches`](Self::from_slice_with_lookup_caches)
+    /// together with the same font data, e.g. after a process restart.
+    pub fn lookup_caches(&self) -> Vec<u8> {
+        let mut caches = Vec::new();
+        self.font.ot.write_caches(&self.font.font, &mut caches);
+        caches
+    }
+
+    /// Returns statistics about the lookup cache of the `GSUB` or `GPOS` table.
+    ///
+    /// Returns `None` if the face has no such table.
+    pub fn lookup_cache_stats(&self, table_index: TableIndex) -> Option<LookupCacheStats> {
+        let lookups = match table_index {
+            TableIndex::GSUB => &self.font.ot.gsub.as_ref()?.lookups,
+            TableIndex::GPOS => &self.font.ot.gpos.as_ref()?.lookups,
+        };
+        Some(lookups.stats())
     }
 
     /// Creates a new [`Face`] from [`ttf_parser::Face`].
     ///
     /// Data will be referenced, not owned.
     pub fn from_face(face: ttf_parser::Face<'a>) -> Self {
-        let font = fonta::Font::new(face.raw_face().data, 0).unwrap();
+        let data = face.raw_face().data;
+        let face_index = collection_index(&face);
+        let font = fonta::Font::new(data, face_index).unwrap();
+        Self::from_parts(face, font, face_index)
+    }
+
+    fn from_parts(face: ttf_parser::Face<'a>, font: fonta::Font<'a>, face_index: u32) -> Self {
         hb_font_t {
-            font,
             units_per_em: face.units_per_em(),
             pixels_per_em: None,
+            slant: 0.0,
+            embolden: (0.0, 0.0, false),
             points_per_em: None,
             gsub: face.tables().gsub.map(SubstitutionTable::new),
             gpos: face.tables().gpos.map(PositioningTable::new),
+            design_coords: ot_var::default_design_coords(&font.font),
+            coords: face.variation_coordinates().to_vec(),
+            skrifa_coords: to_skrifa_coords(face.variation_coordinates()),
+            shapers: Vec::new(),
+            glyph_class_overrides: Vec::new(),
+            face_index,
+            reverse_cmap: OnceBox::new(),
             ttfp_face: face,
+            font,
         }
     }
 
@@ -108,6 +330,7 @@ impl<'a> hb_font_t<'a> {
     #[inline]
     pub fn set_pixels_per_em(&mut self, ppem: Option<(u16, u16)>) {
         self.pixels_per_em = ppem;
+        self.font.ppem = ppem;
     }
 
     /// Sets point size per EM.
@@ -120,12 +343,321 @@ impl<'a> hb_font_t<'a> {
         self.points_per_em = ptem;
     }
 
+    /// Sets the synthetic slant.
+    ///
+    /// `slant` is the ratio of the horizontal shear to the height, e.g. 0.2 for a
+    /// faux-italic. Glyph outlines are not changed; a renderer has to shear them by the
+    /// same ratio. Shaping shifts the x offset of each glyph by `slant` times its
+    /// y offset, which keeps marks and vertical text aligned with the sheared outlines.
+    ///
+    /// 0 by default.
+    #[inline]
+    pub fn set_synthetic_slant(&mut self, slant: f32) {
+        self.slant = slant;
+    }
+
+    /// Returns the synthetic slant.
+    #[inline]
+    pub fn synthetic_slant(&self) -> f32 {
+        self.slant
+    }
+
+    /// Sets the synthetic bold.
+    ///
+    /// `x_embolden` and `y_embolden` are the strengths by which a renderer expands glyph
+    /// outlines, as fractions of the em, e.g. 0.02. Glyph outlines are not changed.
+    ///
+    /// Unless `in_place` is set, the outline is expected to grow to the right and to the
+    /// top, and non-zero horizontal and vertical advances grow by the strength. Otherwise,
+    /// it grows evenly to the left and right and advances are kept. Glyph extents grow in
+    /// both cases.
+    ///
+    /// Disabled by default.
+    #[inline]
+    pub fn set_synthetic_bold(&mut self, x_embolden: f32, y_embolden: f32, in_place: bool) {
+        self.embolden = (x_embolden, y_embolden, in_place);
+    }
+
+    /// Returns the synthetic bold as `(x_embolden, y_embolden, in_place)`.
+    #[inline]
+    pub fn synthetic_bold(&self) -> (f32, f32, bool) {
+        self.embolden
+    }
+
+    // The synthetic bold strengths in font units.
+    fn embolden_strength(&self) -> (i32, i32) {
+        let upem = f32::from(self.units_per_em);
+        (
+            (self.embolden.0 * upem).round() as i32,
+            (self.embolden.1 * upem).round() as i32,
+        )
+    }
+
+    /// Registers a custom shaper for a script.
+    ///
+    /// Replaces the built-in shaper and any previously registered one
+    /// for this script in shape plans created for this face.
+    pub fn register_shaper(&mut self, script: Script, shaper: Arc<dyn ComplexShaper>) {
+        self.shapers.retain(|(s, _)| *s != script);
+        self.shapers.push((script, shaper));
+    }
+
+    pub(crate) fn custom_shaper(&self, script: Script) -> Option<&Arc<dyn ComplexShaper>> {
+        self.shapers
+            .iter()
+            .find(|(s, _)| *s == script)
+            .map(|(_, shaper)| shaper)
+    }
+
+    /// Overrides the `GDEF` glyph class of a glyph.
+    ///
+    /// The override is consulted before `GDEF` and before classes synthesized
+    /// for fonts without `GDEF` glyph classes, so it affects lookup flags like
+    /// `IGNORE_MARKS` as well as mark attachment. Glyphs overridden to marks keep
+    /// their `GDEF` mark attachment class.
+    ///
+    /// Passing `None` removes the override.
+    pub fn set_glyph_class_override(&mut self, glyph: GlyphId, class: Option<GlyphClass>) {
+        let index = self
+            .glyph_class_overrides
+            .binary_search_by_key(&glyph, |(g, _)| *g);
+        match (index, class) {
+            (Ok(i), Some(class)) => self.glyph_class_overrides[i].1 = class,
+            (Ok(i), None) => {
+                self.glyph_class_overrides.remove(i);
+            }
+            (Err(i), Some(class)) => self.glyph_class_overrides.insert(i, (glyph, class)),
+            (Err(_), None) => {}
+        }
+    }
+
+    pub(crate) fn glyph_class_override(&self, glyph: GlyphId) -> Option<GlyphClass> {
+        self.glyph_class_overrides
+            .binary_search_by_key(&glyph, |(g, _)| *g)
+            .ok()
+            .map(|i| self.glyph_class_overrides[i].1)
+    }
+
     /// Sets font variations.
+    ///
+    /// Values are normalized using `fvar` and mapped through `avar`,
+    /// including version 2 tables.
+    ///
+    /// The variations of the underlying [`ttf_parser::Face`] are set as well,
+    /// but since it doesn't support `avar2`, its normalized coordinates can differ.
     pub fn set_variations(&mut self, variations: &[Variation]) {
         for variation in variations {
-            self.set_variation(variation.tag, variation.value);
+            self.ttfp_face.set_variation(variation.tag, variation.value);
+        }
+
+        ot_var::set_design_coords(&self.font.font, variations, &mut self.design_coords);
+        ot_var::normalize_coords(&self.font.font, &self.design_coords, &mut self.coords);
+        self.skrifa_coords = to_skrifa_coords(&self.coords);
+        self.font.set_coords(&self.coords);
+    }
+
+    /// Returns the current normalized variation coordinates.
+    #[inline]
+    pub fn variation_coordinates(&self) -> &[NormalizedCoordinate] {
+        &self.coords
+    }
+
+    fn has_nonzero_coords(&self) -> bool {
+        self.coords.iter().any(|coord| coord.get() != 0)
+    }
+
+    /// Returns the named instances defined in the `fvar` table.
+    pub fn named_instances(&self) -> impl Iterator<Item = NamedInstance> + '_ {
+        let fvar = self.font.font.fvar().ok();
+        let axes = fvar
+            .as_ref()
+            .and_then(|fvar| fvar.axes().ok())
+            .unwrap_or_default();
+        let instances = fvar.as_ref().and_then(|fvar| fvar.instances().ok());
+        let count = instances.as_ref().map(|i| i.len()).unwrap_or(0);
+
+        (0..count).filter_map(move |index| {
+            let instance = instances.as_ref()?.get(index).ok()?;
+            let coordinates = axes
+                .iter()
+                .zip(instance.coordinates)
+                .map(|(axis, value)| Variation {
+                    tag: Tag::from_bytes(&axis.axis_tag().into_bytes()),
+                    value: value.get().to_f32(),
+                })
+                .collect();
+
+            Some(NamedInstance {
+                subfamily_name_id: instance.subfamily_name_id.to_u16(),
+                postscript_name_id: instance
+                    .post_script_name_id
+                    .map(|name_id| name_id.to_u16())
+                    .filter(|name_id| *name_id != 0xFFFF),
+                coordinates,
+            })
+        })
+    }
+
+    /// Sets font variations from a named instance.
+    ///
+    /// The instance coordinates are normalized the same way as in
+    /// [`set_variations`](Self::set_variations), including the `avar` mapping.
+    ///
+    /// Returns `false` when there is no such instance.
+    pub fn set_named_instance(&mut self, index: usize) -> bool {
+        let Some(instance) = self.named_instances().nth(index) else {
+            return false;
+        };
+
+        self.set_variations(&instance.coordinates);
+        true
+    }
+
+    /// Returns the COLRv0 layers of a color glyph, from bottom to top.
+    ///
+    /// Each layer is a glyph and its color in the given CPAL palette.
+    /// `None` means that the layer is drawn using the foreground (text) color.
+    /// Layers referencing a missing palette entry are skipped.
+    ///
+    /// The iterator is empty for glyphs without COLRv0 layers.
+    /// COLRv1 glyphs can be flattened using [`color_fills`](Self::color_fills).
+    pub fn color_layers(
+        &self,
+        glyph: GlyphId,
+        palette_index: u16,
+    ) -> impl Iterator<Item = (GlyphId, Option<RgbaColor>)> + '_ {
+        let colr = self.font.font.colr().ok();
+        let layers = colr
+            .as_ref()
+            .and_then(|colr| colr.v0_base_glyph(glyph.0.into()).ok().flatten())
+            .unwrap_or_default();
+
+        layers.filter_map(move |index| {
+            let (layer_glyph, color_index) = colr.as_ref()?.v0_layer(index).ok()?;
+            let color = match color_index {
+                0xFFFF => None,
+                _ => Some(self.palette_color(palette_index, color_index)?),
+            };
+            Some((GlyphId(layer_glyph.to_u16()), color))
+        })
+    }
+
+    /// Returns the fills of a COLRv1 or COLRv0 color glyph, from bottom to top.
+    ///
+    /// The paint graph is evaluated at the current variation coordinates, which
+    /// applies variable color stops, gradient points and transforms. Colors are taken
+    /// from the given CPAL palette, `foreground` being used for the text color and for
+    /// missing palette entries.
+    ///
+    /// `None` for glyphs without a color glyph and for paint graphs that can't be
+    /// represented as a list of fills: fills clipped by several glyph outlines and
+    /// layers composited with a mode other than source-over. Those can be drawn using
+    /// [`ttf_parser::Face::paint_color_glyph`].
+    pub fn color_fills(
+        &self,
+        glyph: GlyphId,
+        palette_index: u16,
+        foreground: RgbaColor,
+    ) -> Option<Vec<ColorFill>> {
+        let color_glyph = self.font.font.color_glyphs().get(glyph.0.into())?;
+        let mut ctx = hb_color_fills_context_t::new(|entry_index| match entry_index {
+            0xFFFF => foreground,
+            _ => self
+                .palette_color(palette_index, entry_index)
+                .unwrap_or(foreground),
+        });
+        color_glyph
+            .paint(self.skrifa_coords.as_slice(), &mut ctx)
+            .ok()?;
+
+        (!ctx.unsupported).then_some(ctx.fills)
+    }
+
+    /// Returns the SVG document of a glyph.
+    ///
+    /// A single document can cover a range of glyphs, in which case the glyph is
+    /// the element with the `glyph<ID>` id. The document is returned as is,
+    /// which means that it can be gzip-compressed (starting with `1F 8B`).
+    pub fn glyph_svg(&self, glyph: GlyphId) -> Option<&'a [u8]> {
+        self.ttfp_face
+            .glyph_svg_image(glyph)
+            .map(|document| document.data)
+    }
+
+    /// Returns the number of CPAL color palettes.
+    pub fn palette_count(&self) -> u16 {
+        self.font
+            .font
+            .cpal()
+            .map(|cpal| cpal.num_palettes())
+            .unwrap_or(0)
+    }
+
+    /// Returns the colors of a CPAL palette.
+    ///
+    /// The iterator is empty when there is no such palette.
+    pub fn palette(&self, palette_index: u16) -> impl Iterator<Item = RgbaColor> + '_ {
+        let count = match self.font.font.cpal() {
+            Ok(cpal) if palette_index < cpal.num_palettes() => cpal.num_palette_entries(),
+            _ => 0,
+        };
+
+        (0..count).filter_map(move |entry_index| self.palette_color(palette_index, entry_index))
+    }
+
+    /// Returns the flags of a CPAL palette.
+    ///
+    /// Only CPAL version 1 tables store flags. Empty otherwise.
+    pub fn palette_flags(&self, palette_index: u16) -> PaletteFlags {
+        self.font
+            .font
+            .cpal()
+            .ok()
+            .and_then(|cpal| cpal.palette_types_array()?.ok())
+            .and_then(|types| types.get(usize::from(palette_index)))
+            .map(|flags| PaletteFlags::from_bits_truncate(flags.get().bits()))
+            .unwrap_or_default()
+    }
+
+    /// Returns the `name` table ID of a CPAL palette label.
+    ///
+    /// Use [`ttf_parser::Face::names`] to resolve it.
+    pub fn palette_name_id(&self, palette_index: u16) -> Option<u16> {
+        let cpal = self.font.font.cpal().ok()?;
+        let labels = cpal.palette_labels_array()?.ok()?;
+        let name_id = labels.get(usize::from(palette_index))?.get();
+        (name_id != 0xFFFF).then_some(name_id)
+    }
+
+    /// Returns the `name` table ID of a CPAL palette entry label.
+    ///
+    /// Entry labels are shared by all palettes.
+    /// Use [`ttf_parser::Face::names`] to resolve it.
+    pub fn palette_entry_name_id(&self, entry_index: u16) -> Option<u16> {
+        let cpal = self.font.font.cpal().ok()?;
+        let labels = cpal.palette_entry_labels_array()?.ok()?;
+        let name_id = labels.get(usize::from(entry_index))?.get().to_u16();
+        (name_id != 0xFFFF).then_some(name_id)
+    }
+
+    fn palette_color(&self, palette_index: u16, entry_index: u16) -> Option<RgbaColor> {
+        let cpal = self.font.font.cpal().ok()?;
+        if entry_index >= cpal.num_palette_entries() {
+            return None;
         }
-        self.font.set_coords(self.ttfp_face.variation_coordinates());
+
+        let first = cpal
+            .color_record_indices()
+            .get(usize::from(palette_index))?
+            .get();
+        let records = cpal.color_records_array()?.ok()?;
+        let record = records.get(usize::from(first) + usize::from(entry_index))?;
+        Some(RgbaColor::new(
+            record.red(),
+            record.green(),
+            record.blue(),
+            record.alpha(),
+        ))
     }
 
     pub(crate) fn has_glyph(&self, c: u32) -> bool {
@@ -138,29 +670,130 @@ impl<'a> hb_font_t<'a> {
             .map(|gid| GlyphId(gid.to_u32() as u16)) // TODO: remove as u16 when fully on read-fonts GlyphId
     }
 
-    pub(crate) fn glyph_variation_index(&self, c: char, vs: char) -> Option<GlyphId> {
+    /// Returns the glyph of a character.
+    ///
+    /// Uses the same `cmap` subtable as shaping, see [`preferred_cmap`](Self::preferred_cmap).
+    /// Unlike [`ttf_parser::Face::glyph_index`], this means that characters in the
+    /// U+0000..U+00FF range are also looked up at U+F000..U+F0FF in symbol fonts,
+    /// like HarfBuzz and FreeType do.
+    pub fn glyph_index(&self, c: char) -> Option<GlyphId> {
+        self.get_nominal_glyph(u32::from(c))
+    }
+
+    /// Returns the `cmap` subtable used for mapping characters to glyphs.
+    ///
+    /// A Windows symbol subtable (3, 0) is preferred over Unicode ones, followed by
+    /// the 32-bit and 16-bit Unicode subtables and finally the Macintosh Roman one.
+    /// Characters in the U+0000..U+00FF range that a symbol subtable doesn't map
+    /// are looked up at U+F000..U+F0FF. Returns `None` when the font has no
+    /// supported subtable.
+    pub fn preferred_cmap(&self) -> Option<CmapSubtableInfo> {
+        let (platform_id, encoding_id, format) = self.font.charmap.subtable_info()?;
+        Some(CmapSubtableInfo {
+            platform_id,
+            encoding_id,
+            format,
+        })
+    }
+
+    /// Returns the glyph of a character followed by a variation selector.
+    ///
+    /// Uses the `cmap` format 14 subtable. Sequences listed as default
+    /// variations map to the nominal glyph of `c`. Returns `None` when the
+    /// sequence is not supported by the font.
+    pub fn glyph_variation_index(&self, c: char, vs: char) -> Option<GlyphId> {
         self.font
             .nominal_variant_glyph(c as u32, vs as u32)
             .map(|gid| GlyphId(gid.to_u32() as u16)) // TODO: remove as u16 when fully on read-fonts GlyphId
     }
 
-    pub(crate) fn glyph_h_advance(&self, glyph: GlyphId) -> i32 {
-        self.glyph_advance(glyph, false) as i32
+    /// Returns the character a glyph is mapped from by the `cmap` table.
+    ///
+    /// When several characters map to the glyph, the lowest one is returned.
+    /// The reverse mapping is built on the first call. Returns `None` for glyphs
+    /// that can only be reached through layout, like ligatures and alternates.
+    pub fn glyph_to_unicode(&self, glyph: GlyphId) -> Option<char> {
+        let reverse_cmap = self
+            .reverse_cmap
+            .get_or_init(|| Box::new(self.build_reverse_cmap()));
+        reverse_cmap.get(usize::from(glyph.0)).copied().flatten()
+    }
+
+    fn build_reverse_cmap(&self) -> Vec<Option<char>> {
+        let mut reverse_cmap = vec![None; usize::from(self.number_of_glyphs())];
+        let Some(cmap) = self.tables().cmap else {
+            return reverse_cmap;
+        };
+
+        for subtable in cmap.subtables {
+            subtable.codepoints(|c| {
+                // Codepoints are mapped again like when shaping, so that only the
+                // subtable in use counts and non-Unicode codepoints are skipped.
+                let (Some(ch), Some(glyph)) = (char::from_u32(c), self.get_nominal_glyph(c)) else {
+                    return;
+                };
+
+                if let Some(entry) = reverse_cmap.get_mut(usize::from(glyph.0)) {
+                    if entry.is_none_or(|prev| ch < prev) {
+                        *entry = Some(ch);
+                    }
+                }
+            });
+        }
+
+        reverse_cmap
     }
 
-    pub(crate) fn glyph_v_advance(&self, glyph: GlyphId) -> i32 {
-        -(self.glyph_advance(glyph, true) as i32)
+    /// Returns the horizontal advance of a glyph, in font units.
+    ///
+    /// Includes `HVAR` deltas for the current variation coordinates and the
+    /// [synthetic bold](Self::set_synthetic_bold).
+    pub fn glyph_h_advance(&self, glyph: GlyphId) -> i32 {
+        let advance = self.glyph_advance(glyph, false) as i32;
+        if advance != 0 && !self.embolden.2 {
+            advance + self.embolden_strength().0
+        } else {
+            advance
+        }
+    }
+
+    /// Returns the vertical advance of a glyph, in font units.
+    ///
+    /// The advance is negative, since the y axis goes up.
+    /// Includes `VVAR` deltas for the current variation coordinates and the
+    /// [synthetic bold](Self::set_synthetic_bold).
+    pub fn glyph_v_advance(&self, glyph: GlyphId) -> i32 {
+        let advance = -(self.glyph_advance(glyph, true) as i32);
+        if advance != 0 && !self.embolden.2 {
+            advance - self.embolden_strength().1
+        } else {
+            advance
+        }
     }
 
     fn glyph_advance(&self, glyph: GlyphId, is_vertical: bool) -> u32 {
         let face = &self.ttfp_face;
+        let tables = face.tables();
+        // The phantom points are only used without the metrics variations of the direction.
+        let has_metrics_variations = if is_vertical {
+            tables.vvar.is_some()
+        } else {
+            tables.hvar.is_some()
+        };
+        let phantom_deltas =
+            if face.is_variable() && self.has_nonzero_coords() && !has_metrics_variations {
+                self.glyph_phantom_deltas(glyph)
+            } else {
+                None
+            };
+
         if face.is_variable()
-            && face.has_non_default_variation_coordinates()
-            && face.tables().hvar.is_none()
-            && face.tables().vvar.is_none()
-            && face.glyph_phantom_points(glyph).is_none()
+            && self.has_nonzero_coords()
+            && tables.hvar.is_none()
+            && tables.vvar.is_none()
+            && phantom_deltas.is_none()
         {
-            return match face.glyph_bounding_box(glyph) {
+            return match self.glyph_bbox(glyph) {
                 Some(bbox) => {
                     (if is_vertical {
                         bbox.y_max + bbox.y_min
@@ -173,39 +806,130 @@ impl<'a> hb_font_t<'a> {
         }
 
         if is_vertical {
-            if face.tables().vmtx.is_some() {
-                return face.glyph_ver_advance(glyph).unwrap_or(0) as u32;
+            if let Some(vmtx) = tables.vmtx {
+                let delta = match tables.vvar {
+                    Some(vvar) => vvar.advance_offset(glyph, &self.coords),
+                    None => phantom_deltas.map(|(_, bottom)| bottom),
+                };
+                return vmtx
+                    .advance(glyph)
+                    .and_then(|advance| vary_metric(advance, delta))
+                    .unwrap_or(0) as u32;
             } else {
                 // TODO: Original code calls `h_extents_with_fallback`
-                return (face.ascender() - face.descender()) as u32;
+                return (self.ascender() - self.descender()) as u32;
             }
-        } else if !is_vertical && face.tables().hmtx.is_some() {
-            face.glyph_hor_advance(glyph).unwrap_or(0) as u32
+        } else if let Some(hmtx) = tables.hmtx {
+            let delta = match tables.hvar {
+                Some(hvar) => hvar.advance_offset(glyph, &self.coords),
+                None => phantom_deltas.map(|(right, _)| right),
+            };
+            hmtx.advance(glyph)
+                .and_then(|advance| vary_metric(advance, delta))
+                .unwrap_or(0) as u32
         } else {
             face.units_per_em() as u32
         }
     }
 
-    pub(crate) fn glyph_h_origin(&self, glyph: GlyphId) -> i32 {
-        self.glyph_h_advance(glyph) / 2
+    /// Returns the `gvar` deltas of the right and bottom phantom points of a glyph.
+    fn glyph_phantom_deltas(&self, glyph: GlyphId) -> Option<(f32, f32)> {
+        ot_var::phantom_deltas(
+            &self.font.font,
+            &self.skrifa_coords,
+            skrifa::GlyphId::from(glyph.0),
+        )
+    }
+
+    /// Returns the ascender, including `MVAR` deltas.
+    fn ascender(&self) -> i32 {
+        let face = &self.ttfp_face;
+        let os2 = face.tables().os2;
+        // ttf-parser's ascender selection, with `MVAR` applied at our coordinates.
+        if let Some(os2) = os2.filter(|os2| os2.use_typographic_metrics()) {
+            return self.vary_by_mvar(b"hasc", os2.typographic_ascender());
+        }
+
+        let value = face.tables().hhea.ascender;
+        match os2 {
+            Some(os2) if value == 0 && os2.typographic_ascender() != 0 => {
+                self.vary_by_mvar(b"hasc", os2.typographic_ascender())
+            }
+            Some(os2) if value == 0 => self.vary_by_mvar(b"hcla", os2.windows_ascender()),
+            _ => i32::from(value),
+        }
+    }
+
+    /// Returns the descender, including `MVAR` deltas.
+    fn descender(&self) -> i32 {
+        let face = &self.ttfp_face;
+        let os2 = face.tables().os2;
+        if let Some(os2) = os2.filter(|os2| os2.use_typographic_metrics()) {
+            return self.vary_by_mvar(b"hdsc", os2.typographic_descender());
+        }
+
+        let value = face.tables().hhea.descender;
+        match os2 {
+            Some(os2) if value == 0 && os2.typographic_descender() != 0 => {
+                self.vary_by_mvar(b"hdsc", os2.typographic_descender())
+            }
+            Some(os2) if value == 0 => self.vary_by_mvar(b"hcld", os2.windows_descender()),
+            _ => i32::from(value),
+        }
+    }
+
+    fn vary_by_mvar(&self, tag: &[u8; 4], value: i16) -> i32 {
+        let delta = self
+            .ttfp_face
+            .tables()
+            .mvar
+            .and_then(|mvar| mvar.metric_offset(Tag::from_bytes(tag), &self.coords))
+            .unwrap_or(0.0);
+        let varied = f32::from(value) + delta;
+        if varied > f32::from(i16::MIN) - 1.0 && varied < f32::from(i16::MAX) + 1.0 {
+            varied as i32
+        } else {
+            i32::from(value)
+        }
+    }
+
+    /// Returns the vertical origin of a glyph as `(x, y)`, relative to its
+    /// horizontal origin, like `hb_font_get_glyph_v_origin` in HarfBuzz.
+    ///
+    /// The x coordinate is half of the [horizontal advance](Self::glyph_h_advance).
+    /// The y coordinate uses `VORG` when present. Otherwise, it's computed from the
+    /// glyph extents and the top side bearing from `vmtx`, or centers the glyph
+    /// between the ascender and descender when there is no `vmtx`.
+    pub fn glyph_v_origin(&self, glyph: GlyphId) -> (i32, i32) {
+        (
+            self.glyph_h_advance(glyph) / 2,
+            self.glyph_v_origin_y(glyph),
+        )
     }
 
-    pub(crate) fn glyph_v_origin(&self, glyph: GlyphId) -> i32 {
-        match self.ttfp_face.glyph_y_origin(glyph) {
+    fn glyph_v_origin_y(&self, glyph: GlyphId) -> i32 {
+        let tables = self.ttfp_face.tables();
+        let y_origin = tables.vorg.and_then(|vorg| {
+            let delta = tables
+                .vvar
+                .and_then(|vvar| vvar.vertical_origin_offset(glyph, &self.coords));
+            vary_metric(vorg.glyph_y_origin(glyph), delta)
+        });
+        match y_origin {
             Some(y) => i32::from(y),
             None => {
-                let mut extents = hb_glyph_extents_t::default();
-                if self.glyph_extents(glyph, &mut extents) {
-                    if self.ttfp_face.tables().vmtx.is_some() {
+                // Like in HarfBuzz, the synthetic bold doesn't move the origin.
+                if let Some(extents) = self.glyph_extents_unemboldened(glyph) {
+                    if tables.vmtx.is_some() {
                         extents.y_bearing + self.glyph_side_bearing(glyph, true)
                     } else {
-                        let advance = self.ttfp_face.ascender() - self.ttfp_face.descender();
-                        let diff = advance as i32 - -extents.height;
+                        let advance = self.ascender() - self.descender();
+                        let diff = advance - -extents.height;
                         return extents.y_bearing + (diff >> 1);
                     }
                 } else {
                     // TODO: Original code calls `h_extents_with_fallback`
-                    self.ttfp_face.ascender() as i32
+                    self.ascender()
                 }
             }
         }
@@ -213,25 +937,55 @@ impl<'a> hb_font_t<'a> {
 
     pub(crate) fn glyph_side_bearing(&self, glyph: GlyphId, is_vertical: bool) -> i32 {
         let face = &self.ttfp_face;
-        if face.is_variable() && face.tables().hvar.is_none() && face.tables().vvar.is_none() {
-            return match face.glyph_bounding_box(glyph) {
+        let tables = face.tables();
+        if face.is_variable() && tables.hvar.is_none() && tables.vvar.is_none() {
+            return match self.glyph_bbox(glyph) {
                 Some(bbox) => (if is_vertical { bbox.x_min } else { bbox.y_min }) as i32,
                 None => 0,
             };
         }
 
-        if is_vertical {
-            face.glyph_ver_side_bearing(glyph).unwrap_or(0) as i32
+        let bearing = if is_vertical {
+            tables.vmtx.and_then(|vmtx| {
+                let delta = tables
+                    .vvar
+                    .and_then(|vvar| vvar.top_side_bearing_offset(glyph, &self.coords));
+                vary_metric(vmtx.side_bearing(glyph)?, delta)
+            })
         } else {
-            face.glyph_hor_side_bearing(glyph).unwrap_or(0) as i32
+            tables.hmtx.and_then(|hmtx| {
+                let delta = tables
+                    .hvar
+                    .and_then(|hvar| hvar.left_side_bearing_offset(glyph, &self.coords));
+                vary_metric(hmtx.side_bearing(glyph)?, delta)
+            })
+        };
+        bearing.map(i32::from).unwrap_or(0)
+    }
+
+    /// Returns the ink bounding box of a glyph.
+    ///
+    /// Uses PNG bitmaps, COLR glyphs or outlines, in this order, and honors the variation
+    /// coordinates. Empty glyphs, like a space, have zero extents.
+    /// `None` when the glyph has no outline nor image.
+    ///
+    /// Non-empty extents include the [synthetic bold](Self::set_synthetic_bold).
+    pub fn glyph_extents(&self, glyph: GlyphId) -> Option<hb_glyph_extents_t> {
+        let mut extents = self.glyph_extents_unemboldened(glyph)?;
+        let (x_strength, y_strength) = self.embolden_strength();
+        if (x_strength != 0 || y_strength != 0) && extents != hb_glyph_extents_t::default() {
+            // Like in HarfBuzz, only the x bearing depends on `in_place`.
+            if self.embolden.2 {
+                extents.x_bearing -= x_strength / 2;
+            }
+            extents.y_bearing += y_strength;
+            extents.width += x_strength;
+            extents.height -= y_strength;
         }
+        Some(extents)
     }
 
-    pub(crate) fn glyph_extents(
-        &self,
-        glyph: GlyphId,
-        glyph_extents: &mut hb_glyph_extents_t,
-    ) -> bool {
+    fn glyph_extents_unemboldened(&self, glyph: GlyphId) -> Option<hb_glyph_extents_t> {
         let pixels_per_em = match self.pixels_per_em {
             Some(ppem) => ppem.0,
             None => core::u16::MAX,
@@ -241,102 +995,618 @@ impl<'a> hb_font_t<'a> {
             // HarfBuzz also supports only PNG.
             if img.format == ttf_parser::RasterImageFormat::PNG {
                 let scale = self.units_per_em as f32 / img.pixels_per_em as f32;
-                glyph_extents.x_bearing = (f32::from(img.x) * scale).round() as i32;
-                glyph_extents.y_bearing =
-                    ((f32::from(img.y) + f32::from(img.height)) * scale).round() as i32;
-                glyph_extents.width = (f32::from(img.width) * scale).round() as i32;
-                glyph_extents.height = (-f32::from(img.height) * scale).round() as i32;
-                return true;
+                return Some(hb_glyph_extents_t {
+                    x_bearing: (f32::from(img.x) * scale).round() as i32,
+                    y_bearing: ((f32::from(img.y) + f32::from(img.height)) * scale).round() as i32,
+                    width: (f32::from(img.width) * scale).round() as i32,
+                    height: (-f32::from(img.height) * scale).round() as i32,
+                });
             }
         // TODO: Add tests for this. We should use all glyphs from
         // https://github.com/googlefonts/color-fonts/blob/main/fonts/test_glyphs-glyf_colr_1_no_cliplist.ttf
         // and test their output against harfbuzz.
         } else if let Some(colr) = self.ttfp_face.tables().colr {
             if colr.is_simple() {
-                return false;
+                return None;
             }
 
             if let Some(clip_box) = colr.clip_box(glyph, self.variation_coordinates()) {
                 // Floor
-                glyph_extents.x_bearing = (clip_box.x_min).round() as i32;
-                glyph_extents.y_bearing = (clip_box.y_max).round() as i32;
-                glyph_extents.width = (clip_box.x_max - clip_box.x_min).round() as i32;
-                glyph_extents.height = (clip_box.y_min - clip_box.y_max).round() as i32;
-                return true;
+                return Some(hb_glyph_extents_t {
+                    x_bearing: (clip_box.x_min).round() as i32,
+                    y_bearing: (clip_box.y_max).round() as i32,
+                    width: (clip_box.x_max - clip_box.x_min).round() as i32,
+                    height: (clip_box.y_min - clip_box.y_max).round() as i32,
+                });
             }
 
-            let mut extents_data = hb_paint_extents_context_t::new(&self.ttfp_face);
-            let ret = colr
-                .paint(
-                    glyph,
-                    0,
-                    &mut extents_data,
-                    self.variation_coordinates(),
-                    RgbaColor::new(0, 0, 0, 0),
-                )
-                .is_some();
+            let mut extents_data = hb_paint_extents_context_t::new(self);
+            colr.paint(
+                glyph,
+                0,
+                &mut extents_data,
+                self.variation_coordinates(),
+                RgbaColor::new(0, 0, 0, 0),
+            )?;
 
             let e = extents_data.get_extents();
             if e.is_void() {
-                glyph_extents.x_bearing = 0;
-                glyph_extents.y_bearing = 0;
-                glyph_extents.width = 0;
-                glyph_extents.height = 0;
-            } else {
-                glyph_extents.x_bearing = e.x_min as i32;
-                glyph_extents.y_bearing = e.y_max as i32;
-                glyph_extents.width = (e.x_max - e.x_min) as i32;
-                glyph_extents.height = (e.y_min - e.y_max) as i32;
+                return Some(hb_glyph_extents_t::default());
             }
 
-            return ret;
+            return Some(hb_glyph_extents_t {
+                x_bearing: e.x_min as i32,
+                y_bearing: e.y_max as i32,
+                width: (e.x_max - e.x_min) as i32,
+                height: (e.y_min - e.y_max) as i32,
+            });
         }
 
-        let mut bbox = None;
-
-        if let Some(glyf) = self.ttfp_face.tables().glyf {
-            bbox = glyf.bbox(glyph);
-        }
+        let tables = self.ttfp_face.tables();
+        let bbox = if let Some(glyf) = tables.glyf {
+            // Like HarfBuzz, use the stored bounding box unless the glyph is varied.
+            if tables.gvar.is_some() && self.has_nonzero_coords() {
+                self.glyph_bbox(glyph)
+            } else {
+                glyf.bbox(glyph)
+            }
+        } else if tables.cff2.is_some() {
+            // Like for other outlines, a glyph that can't be drawn has zero extents.
+            let mut points = ContourPoints::default();
+            if self.cff2_outline(glyph, &mut points).is_none() {
+                return Some(hb_glyph_extents_t::default());
+            }
+            return Some(points.extents());
+        } else if tables.cff.is_some() {
+            self.glyph_bbox(glyph)
+        } else {
+            return None;
+        };
 
         // See https://github.com/RazrFalcon/harfruzz/pull/98#issuecomment-1948430785
-        if self.ttfp_face.tables().glyf.is_some() && bbox.is_none() {
+        let Some(bbox) = bbox else {
             // Empty glyph; zero extents.
-            return true;
+            return Some(hb_glyph_extents_t::default());
+        };
+
+        Some(hb_glyph_extents_t {
+            x_bearing: i32::from(bbox.x_min),
+            y_bearing: i32::from(bbox.y_max),
+            width: i32::from(bbox.width()),
+            height: i32::from(bbox.y_min - bbox.y_max),
+        })
+    }
+
+    /// Outlines a glyph at the current variation coordinates.
+    pub(crate) fn glyph_outline(
+        &self,
+        glyph: GlyphId,
+        builder: &mut dyn OutlineBuilder,
+    ) -> Option<ttf_parser::Rect> {
+        // Same as `ttf_parser::Face::outline_glyph`, which uses the face's own coordinates.
+        let tables = self.ttfp_face.tables();
+        if let Some(gvar) = tables.gvar {
+            return gvar.outline(tables.glyf?, &self.coords, glyph, builder);
         }
 
-        let Some(bbox) = bbox else {
-            return false;
-        };
+        if tables.glyf.is_none() && tables.cff.is_none() {
+            if let Some(cff2) = tables.cff2 {
+                return cff2.outline(&self.coords, glyph, builder).ok();
+            }
+        }
+
+        self.ttfp_face.outline_glyph(glyph, builder)
+    }
 
-        glyph_extents.x_bearing = i32::from(bbox.x_min);
-        glyph_extents.y_bearing = i32::from(bbox.y_max);
-        glyph_extents.width = i32::from(bbox.width());
-        glyph_extents.height = i32::from(bbox.y_min - bbox.y_max);
+    /// Returns the tight bounding box of a glyph at the current variation coordinates.
+    pub(crate) fn glyph_bbox(&self, glyph: GlyphId) -> Option<ttf_parser::Rect> {
+        self.glyph_outline(glyph, &mut NoopOutline)
+    }
+
+    /// Returns the coordinates of a contour point of a glyph outline.
+    ///
+    /// `glyf` points are numbered as stored, the points of composite glyphs being the
+    /// ones of their components, in order. They are unhinted, with the `gvar` variations
+    /// applied.
+    ///
+    /// CFF and CFF2 points are numbered in the order the charstring emits them, including
+    /// the control points of curves. Like in FreeType, the last point of a contour is left
+    /// out when it coincides with the contour's start. The variation coordinates are
+    /// honored.
+    ///
+    /// `None` when the outline has no such point.
+    pub fn glyph_contour_point(&self, glyph: GlyphId, point_index: u16) -> Option<(i32, i32)> {
+        let points = self.glyph_contour_points(glyph)?;
+        let (x, y) = *points.get(usize::from(point_index))?;
+        Some((x.round() as i32, y.round() as i32))
+    }
+
+    /// Returns the contour points of a glyph outline, numbered like in
+    /// [`glyph_contour_point`](Self::glyph_contour_point).
+    fn glyph_contour_points(&self, glyph: GlyphId) -> Option<Vec<(f32, f32)>> {
+        let tables = self.ttfp_face.tables();
+        let mut points = ContourPoints::default();
+        if let (Ok(loca), Ok(glyf)) = (self.font.font.loca(None), self.font.font.glyf()) {
+            let gvar = self.font.font.gvar().ok();
+            let variations = gvar
+                .as_ref()
+                .filter(|_| self.has_nonzero_coords())
+                .map(|gvar| (gvar, self.skrifa_coords.as_slice()));
+            glyf_points(
+                &loca,
+                &glyf,
+                variations,
+                skrifa::GlyphId::from(glyph.0),
+                0,
+                &mut points.points,
+            )?;
+        } else if tables.cff2.is_some() {
+            self.cff2_outline(glyph, &mut points)?;
+        } else if tables.cff.is_some() {
+            self.ttfp_face.outline_glyph(glyph, &mut points)?;
+            points.close();
+        } else {
+            return None;
+        }
+
+        Some(points.points)
+    }
+
+    // Unlike ttf-parser, skrifa honors the `vsindex` of Private DICTs, which selects
+    // the item variation data used by the blends of the glyph's charstring.
+    fn cff2_outline(&self, glyph: GlyphId, points: &mut ContourPoints) -> Option<()> {
+        use skrifa::instance::{LocationRef, Size};
+        use skrifa::outline::DrawSettings;
+
+        let outline = self
+            .font
+            .font
+            .outline_glyphs()
+            .get(skrifa::GlyphId::from(glyph.0))?;
+        let settings =
+            DrawSettings::unhinted(Size::unscaled(), LocationRef::new(&self.skrifa_coords));
+        outline.draw(settings, &mut *points).ok()?;
+        points.close();
+        Some(())
+    }
+
+    /// Resolves a `GPOS` anchor attached to a glyph.
+    ///
+    /// Like in HarfBuzz, contour point anchors only use the outline's point for the axes
+    /// with a ppem set, and fall back to the design coordinates when the glyph has no
+    /// such point. The anchor is in font units multiplied by `scale`.
+    pub(crate) fn resolve_anchor(
+        &self,
+        anchor: &AnchorTable,
+        glyph: GlyphId,
+        scale: i32,
+        contour_points: &mut ContourPointCache,
+    ) -> (i32, i32) {
+        let (x, y) = self.font.resolve_anchor(anchor, scale);
+
+        if let (AnchorTable::Format2(anchor), Some((x_ppem, y_ppem))) = (anchor, self.pixels_per_em)
+        {
+            let point = contour_points
+                .get(self, glyph)
+                .get(usize::from(anchor.anchor_point()));
+            if let Some(&(px, py)) = point {
+                let (px, py) = (px.round() as i32, py.round() as i32);
+                return (
+                    if x_ppem != 0 { px * scale } else { x },
+                    if y_ppem != 0 { py * scale } else { y },
+                );
+            }
+        }
 
-        return true;
+        (x, y)
     }
 
-    pub(crate) fn glyph_name(&self, glyph: GlyphId) -> Option<&str> {
+    /// Returns the name of a glyph.
+    ///
+    /// Names are read from the `post` table and, for fonts without names in it
+    /// (`post` version 3.0), from the CFF charset.
+    ///
+    /// Like HarfBuzz, no `uniXXXX` names are synthesized for the other glyphs,
+    /// so that buffer serialization prints them as `gidNNN` just like
+    /// `hb-shape` does. [`glyph_to_unicode`](Self::glyph_to_unicode) can be
+    /// used to build such names, and [`glyph_index_by_name`](Self::glyph_index_by_name)
+    /// resolves them.
+    pub fn glyph_name(&self, glyph: GlyphId) -> Option<&str> {
         self.ttfp_face.glyph_name(glyph)
     }
 
+    /// Returns the glyph with a name.
+    ///
+    /// Besides the names returned by [`glyph_name`](Self::glyph_name), this
+    /// resolves `uniXXXX` names using `cmap`, `cidNNNNN` names using the CFF
+    /// charset of CID-keyed fonts and `gidNNN` names.
+    pub fn glyph_index_by_name(&self, name: &str) -> Option<GlyphId> {
+        if let Some(glyph) = self.ttfp_face.glyph_index_by_name(name) {
+            return Some(glyph);
+        }
+
+        if let Some(hex) = name.strip_prefix("uni") {
+            if hex.len() != 4 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            return self.get_nominal_glyph(u32::from_str_radix(hex, 16).ok()?);
+        }
+
+        if let Some(cid) = name.strip_prefix("cid") {
+            let cid: u16 = parse_decimal(cid)?;
+            let cff = self.tables().cff.as_ref()?;
+            return (0..self.number_of_glyphs())
+                .map(GlyphId)
+                .find(|glyph| cff.glyph_cid(*glyph) == Some(cid));
+        }
+
+        if let Some(index) = name.strip_prefix("gid") {
+            let index: u16 = parse_decimal(index)?;
+            return (index < self.number_of_glyphs()).then_some(GlyphId(index));
+        }
+
+        None
+    }
+
     pub(crate) fn glyph_props(&self, glyph: GlyphId) -> u16 {
-        let table = match self.tables().gdef {
-            Some(v) => v,
-            None => return 0,
-        };
+        let table = self.tables().gdef;
+        let class = self
+            .glyph_class_override(glyph)
+            .or_else(|| table?.glyph_class(glyph));
 
-        match table.glyph_class(glyph) {
+        match class {
             Some(GlyphClass::Base) => GlyphPropsFlags::BASE_GLYPH.bits(),
             Some(GlyphClass::Ligature) => GlyphPropsFlags::LIGATURE.bits(),
             Some(GlyphClass::Mark) => {
-                let class = table.glyph_mark_attachment_class(glyph);
+                let class = table.map_or(0, |table| table.glyph_mark_attachment_class(glyph));
                 (class << 8) | GlyphPropsFlags::MARK.bits()
             }
             _ => 0,
         }
     }
 
+    /// Returns the contour point indices of a glyph from the `GDEF` attachment point list.
+    ///
+    /// The iterator is empty when the glyph has no attachment points.
+    pub fn attachment_points(&self, glyph: GlyphId) -> impl Iterator<Item = u16> + 'a {
+        let points = (|| {
+            let list = self.font.ot.gdef.as_ref()?.attach_list()?.ok()?;
+            let index = list.coverage().ok()?.get(skrifa::GlyphId::from(glyph.0))?;
+            let point = list.attach_points().get(usize::from(index)).ok()?;
+            Some(point.point_indices())
+        })();
+
+        points.unwrap_or_default().iter().map(|index| index.get())
+    }
+
+    /// Returns the tags of the features available for a script and language.
+    ///
+    /// The script and language systems are selected the same way as during shaping,
+    /// which means that the default ones are used when there is no exact match.
+    /// The required feature is not included, see
+    /// [`layout_required_feature`](Self::layout_required_feature).
+    pub fn layout_features(
+        &self,
+        table_index: TableIndex,
+        script: Option<Script>,
+        language: Option<&Language>,
+    ) -> impl Iterator<Item = Tag> + '_ {
+        let mut tags: Vec<Tag> = Vec::new();
+        if let Some((table, sys)) = self.layout_language_system(table_index, script, language) {
+            for index in sys.feature_indices {
+                let Some(feature) = table.features.get(index) else {
+                    continue;
+                };
+
+                if !tags.contains(&feature.tag) {
+                    tags.push(feature.tag);
+                }
+            }
+        }
+
+        tags.into_iter()
+    }
+
+    /// Returns the tag of the required feature for a script and language.
+    ///
+    /// Required features are always applied during shaping and can't be disabled.
+    pub fn layout_required_feature(
+        &self,
+        table_index: TableIndex,
+        script: Option<Script>,
+        language: Option<&Language>,
+    ) -> Option<Tag> {
+        let (table, sys) = self.layout_language_system(table_index, script, language)?;
+        Some(table.features.get(sys.required_feature?)?.tag)
+    }
+
+    /// Returns the `name` table IDs of a GSUB stylistic set (`ssXX`) or
+    /// character variant (`cvXX`) feature.
+    ///
+    /// Returns `None` for other features and when the feature has no parameters.
+    pub fn feature_name_ids(&self, feature: Tag) -> Option<FeatureNameIds> {
+        use skrifa::raw::tables::layout::FeatureParams;
+
+        let name_id = |id: skrifa::raw::types::NameId| {
+            let id = id.to_u16();
+            (id != 0 && id != 0xFFFF).then_some(id)
+        };
+
+        let feature_list = self.font.font.gsub().ok()?.feature_list().ok()?;
+        let record = feature_list
+            .feature_records()
+            .iter()
+            .find(|record| record.feature_tag().to_be_bytes() == feature.to_bytes())?;
+        let params = record
+            .feature(feature_list.offset_data())
+            .ok()?
+            .feature_params()?
+            .ok()?;
+
+        match params {
+            FeatureParams::StylisticSet(params) => Some(FeatureNameIds {
+                label: name_id(params.ui_name_id()),
+                tooltip: None,
+                sample: None,
+                first_param: None,
+                num_params: 0,
+            }),
+            FeatureParams::CharacterVariant(params) => Some(FeatureNameIds {
+                label: name_id(params.feat_ui_label_name_id()),
+                tooltip: name_id(params.feat_ui_tooltip_text_name_id()),
+                sample: name_id(params.sample_text_name_id()),
+                first_param: name_id(params.first_param_ui_label_name_id()),
+                num_params: params.num_named_parameters(),
+            }),
+            FeatureParams::Size(_) => None,
+        }
+    }
+
+    /// Returns the optical size parameters of the GPOS `size` feature.
+    ///
+    /// Parameters that are inconsistent, like a design size outside of the size
+    /// range, are skipped in favor of the ones of a later `size` feature. Returns
+    /// `None` if the font has no such feature with valid parameters.
+    pub fn optical_size(&self) -> Option<SizeParams> {
+        use skrifa::raw::tables::layout::FeatureParams;
+
+        let feature_list = self.font.font.gpos().ok()?.feature_list().ok()?;
+        feature_list
+            .feature_records()
+            .iter()
+            .filter(|record| record.feature_tag() == skrifa::raw::types::Tag::new(b"size"))
+            .find_map(|record| {
+                match record
+                    .feature(feature_list.offset_data())
+                    .ok()?
+                    .feature_params()?
+                    .ok()?
+                {
+                    FeatureParams::Size(params) => Some(SizeParams {
+                        design_size: params.design_size(),
+                        subfamily_id: params.identifier(),
+                        subfamily_name_id: Some(params.name_entry()).filter(|id| *id != 0),
+                        range_start: params.range_start(),
+                        range_end: params.range_end(),
+                    })
+                    .filter(is_valid_size_params),
+                    _ => None,
+                }
+            })
+    }
+
+    /// Returns a math layout constant of the MATH table.
+    ///
+    /// Values in font units include the device deltas at the current variation
+    /// coordinates and pixels per EM. Percentages are returned as is.
+    ///
+    /// `None` when the font has no MATH table or no math constants.
+    pub fn math_constant(&self, constant: MathConstant) -> Option<i32> {
+        ot_math::hb_ot_math_get_constant(self, constant)
+    }
+
+    /// Returns the italic correction of a glyph from the MATH table, in font units.
+    ///
+    /// `None` when the glyph has no italic correction.
+    pub fn math_italic_correction(&self, glyph: GlyphId) -> Option<i32> {
+        ot_math::hb_ot_math_get_glyph_italics_correction(self, glyph)
+    }
+
+    /// Returns the horizontal position of the top accent attachment of a glyph
+    /// from the MATH table, in font units.
+    ///
+    /// `None` when the glyph has no attachment point. HarfBuzz then falls back
+    /// to half of the advance, which is left to the caller.
+    pub fn math_top_accent_attachment(&self, glyph: GlyphId) -> Option<i32> {
+        ot_math::hb_ot_math_get_glyph_top_accent_attachment(self, glyph)
+    }
+
+    /// Returns the minimum overlap of connecting parts in glyph assemblies of
+    /// the MATH table, in font units.
+    pub fn math_min_connector_overlap(&self) -> Option<u16> {
+        ot_math::hb_ot_math_get_min_connector_overlap(self)
+    }
+
+    /// Returns the size variants of a glyph from the MATH table, from smallest
+    /// to largest.
+    ///
+    /// Horizontal directions return the variants for stretching horizontally,
+    /// like for over braces, and vertical ones those for stretching vertically,
+    /// like for parentheses. The list usually starts with the glyph itself.
+    pub fn math_glyph_variants(
+        &self,
+        glyph: GlyphId,
+        direction: Direction,
+    ) -> impl Iterator<Item = MathGlyphVariant> + '_ {
+        ot_math::hb_ot_math_get_glyph_variants(self, glyph, direction)
+    }
+
+    /// Returns the assembly for building a glyph larger than its biggest
+    /// variant from the MATH table, stretching in the given direction.
+    pub fn math_glyph_assembly(
+        &self,
+        glyph: GlyphId,
+        direction: Direction,
+    ) -> Option<MathGlyphAssembly> {
+        ot_math::hb_ot_math_get_glyph_assembly(self, glyph, direction)
+    }
+
+    /// Returns the label of a GSUB stylistic set (`ssXX`) or character
+    /// variant (`cvXX`) feature.
+    ///
+    /// Prefers the US English name and falls back to any Unicode name.
+    #[cfg(feature = "std")]
+    pub fn feature_name(&self, feature: Tag) -> Option<alloc::string::String> {
+        let name_id = self.feature_name_ids(feature)?.label?;
+        let names = self
+            .ttfp_face
+            .names()
+            .into_iter()
+            .filter(|name| name.name_id == name_id && name.is_unicode());
+
+        names
+            .clone()
+            .find(|name| name.language_id == 0x0409)
+            .and_then(|name| name.to_string())
+            .or_else(|| names.filter_map(|name| name.to_string()).next())
+    }
+
+    /// Checks whether a GSUB feature would substitute a glyph sequence.
+    ///
+    /// The whole sequence has to be matched by a single lookup of the feature,
+    /// like `f` and `i` by a ligature. The backtrack and lookahead of contextual
+    /// lookups are not checked.
+    pub fn would_substitute(
+        &self,
+        feature: Tag,
+        script: Option<Script>,
+        language: Option<&Language>,
+        glyphs: &[GlyphId],
+    ) -> bool {
+        let Some(gsub) = self.gsub.as_ref().filter(|_| !glyphs.is_empty()) else {
+            return false;
+        };
+
+        let Some((table, sys)) = self.layout_language_system(TableIndex::GSUB, script, language)
+        else {
+            return false;
+        };
+
+        let ctx = WouldApplyContext {
+            glyphs,
+            zero_context: false,
+        };
+
+        sys.required_feature
+            .into_iter()
+            .chain(sys.feature_indices)
+            .filter_map(|index| table.features.get(index))
+            .filter(|record| record.tag == feature)
+            .flat_map(|record| record.lookup_indices)
+            .filter_map(|index| gsub.get_lookup(index))
+            .any(|lookup| lookup.would_apply(&ctx))
+    }
+
+    /// Returns the alternates of a glyph in the GSUB alternate substitution
+    /// lookups of a feature, like `aalt` or `salt`.
+    ///
+    /// Lookups of the feature in all scripts and languages are used, in lookup
+    /// order. Alternates listed by several lookups are returned once.
+    pub fn glyph_alternates(
+        &self,
+        glyph: GlyphId,
+        feature: Tag,
+    ) -> impl Iterator<Item = GlyphId> + '_ {
+        let mut alternates: Vec<GlyphId> = Vec::new();
+        if let Some(gsub) = self.gsub.as_ref() {
+            let mut lookup_indices: Vec<u16> = gsub
+                .inner
+                .features
+                .into_iter()
+                .filter(|record| record.tag == feature)
+                .flat_map(|record| record.lookup_indices)
+                .collect();
+            lookup_indices.sort_unstable();
+            lookup_indices.dedup();
+
+            for lookup in lookup_indices
+                .into_iter()
+                .filter_map(|index| gsub.get_lookup(index))
+            {
+                for subtable in &lookup.subtables {
+                    let SubstitutionSubtable::Alternate(subtable) = subtable else {
+                        continue;
+                    };
+
+                    let Some(set) = subtable
+                        .coverage
+                        .get(glyph)
+                        .and_then(|index| subtable.alternate_sets.get(index))
+                    else {
+                        continue;
+                    };
+
+                    for alternate in set.alternates {
+                        if !alternates.contains(&alternate) {
+                            alternates.push(alternate);
+                        }
+                    }
+                }
+            }
+        }
+
+        alternates.into_iter()
+    }
+
+    /// Returns the features of the AAT `feat` table.
+    ///
+    /// The features and selectors are listed in font order.
+    pub fn aat_features(&self) -> impl Iterator<Item = AatFeature> + '_ {
+        let names = self.tables().feat.map(|feat| feat.names);
+        names.into_iter().flatten().map(|feature| {
+            let default_index = feature
+                .exclusive
+                .then_some(usize::from(feature.default_setting_index));
+            let default_selector = default_index
+                .and_then(|index| feature.setting_names.get(index as u16))
+                .map(|setting| setting.setting);
+
+            AatFeature {
+                kind: feature.feature,
+                name_id: feature.name_index,
+                exclusive: feature.exclusive,
+                default_index,
+                selectors: feature
+                    .setting_names
+                    .into_iter()
+                    .map(|setting| AatFeatureSelector {
+                        name_id: setting.name_index,
+                        enable: setting.setting,
+                        disable: default_selector.unwrap_or(setting.setting.wrapping_add(1)),
+                    })
+                    .collect(),
+            }
+        })
+    }
+
+    pub(crate) fn layout_language_system(
+        &self,
+        table_index: TableIndex,
+        script: Option<Script>,
+        language: Option<&Language>,
+    ) -> Option<(&LayoutTable<'a>, LanguageSystem<'a>)> {
+        let table = self.layout_table(table_index)?;
+        let (script_tags, lang_tags) = tag::tags_from_script_and_language(script, language);
+        let (_, script_index, _) = table.select_script(&script_tags)?;
+        let script = table.scripts.get(script_index)?;
+        let sys = match table.select_script_language(script_index, &lang_tags) {
+            Some(index) => script.languages.get(index)?,
+            None => script.default_language?,
+        };
+
+        Some((table, sys))
+    }
+
     pub(crate) fn layout_table(&self, table_index: TableIndex) -> Option<&LayoutTable<'a>> {
         match table_index {
             TableIndex::GSUB => self.gsub.as_ref().map(|table| &table.inner),
@@ -351,14 +1621,317 @@ impl<'a> hb_font_t<'a> {
     }
 }
 
-#[derive(Clone, Copy, Default)]
+/// Glyph extents, in font units.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
 #[repr(C)]
 pub struct hb_glyph_extents_t {
+    /// Distance from the origin to the left-most ink.
     pub x_bearing: i32,
+    /// Distance from the baseline to the top-most ink.
     pub y_bearing: i32,
+    /// Distance from the left-most to the right-most ink.
     pub width: i32,
+    /// Distance from the top-most to the bottom-most ink. Negative for y-up fonts.
     pub height: i32,
 }
 
 unsafe impl bytemuck::Zeroable for hb_glyph_extents_t {}
 unsafe impl bytemuck::Pod for hb_glyph_extents_t {}
+
+/// The maximum nesting level of composite glyphs.
+const MAX_COMPOSITE_DEPTH: u8 = 8;
+
+/// Collects the points of a `glyf` outline, in design units.
+///
+/// The `gvar` deltas at the coordinates are applied when `variations` is set.
+fn glyf_points(
+    loca: &Loca,
+    glyf: &Glyf,
+    variations: Option<(&Gvar, &[F2Dot14])>,
+    glyph: skrifa::GlyphId,
+    depth: u8,
+    points: &mut Vec<(f32, f32)>,
+) -> Option<()> {
+    if depth > MAX_COMPOSITE_DEPTH {
+        return None;
+    }
+
+    match loca.get_glyf(glyph, glyf).ok()? {
+        None => {}
+        Some(Glyph::Simple(simple)) => {
+            let start = points.len();
+            points.extend(simple.points().map(|p| (f32::from(p.x), f32::from(p.y))));
+
+            if let Some((gvar, coords)) = variations {
+                let contour_ends: Vec<u16> = simple
+                    .end_pts_of_contours()
+                    .iter()
+                    .map(|end| end.get())
+                    .collect();
+                let deltas =
+                    ot_var::point_deltas(gvar, coords, glyph, &points[start..], &contour_ends);
+                for ((x, y), (dx, dy)) in points[start..].iter_mut().zip(deltas) {
+                    *x += dx;
+                    *y += dy;
+                }
+            }
+        }
+        Some(Glyph::Composite(composite)) => {
+            // The deltas of a composite glyph move the offsets of its components.
+            let offset_deltas = variations.map(|(gvar, coords)| {
+                let offsets = vec![(0.0, 0.0); composite.components().count()];
+                ot_var::point_deltas(gvar, coords, glyph, &offsets, &[])
+            });
+
+            // Point numbers of anchors are relative to this composite, which
+            // may itself be a component of another one.
+            let composite_start = points.len();
+            for (i, component) in composite.components().enumerate() {
+                let start = points.len();
+                glyf_points(
+                    loca,
+                    glyf,
+                    variations,
+                    component.glyph.into(),
+                    depth + 1,
+                    points,
+                )?;
+
+                let t = component.transform;
+                let (xx, yx, xy, yy) = (t.xx.to_f32(), t.yx.to_f32(), t.xy.to_f32(), t.yy.to_f32());
+                for (x, y) in &mut points[start..] {
+                    (*x, *y) = (*x * xx + *y * xy, *x * yx + *y * yy);
+                }
+
+                let (dx, dy) = match component.anchor {
+                    Anchor::Offset { x, y } => {
+                        let delta = offset_deltas
+                            .as_ref()
+                            .and_then(|deltas| deltas.get(i).copied())
+                            .unwrap_or_default();
+                        (f32::from(x) + delta.0, f32::from(y) + delta.1)
+                    }
+                    // Like in HarfBuzz, the offsets of components aligned by
+                    // their points don't vary.
+                    Anchor::Point { base, component } => {
+                        let base = *points[composite_start..start].get(usize::from(base))?;
+                        let component = *points[start..].get(usize::from(component))?;
+                        (base.0 - component.0, base.1 - component.1)
+                    }
+                };
+                for (x, y) in &mut points[start..] {
+                    *x += dx;
+                    *y += dy;
+                }
+            }
+        }
+    }
+
+    Some(())
+}
+
+/// Adds a variation delta to a metric, rounding like ttf-parser.
+///
+/// `None` when the result doesn't fit.
+fn vary_metric<T>(value: T, delta: Option<f32>) -> Option<T>
+where
+    T: Into<f32> + TryFrom<i32>,
+{
+    let mut value: f32 = value.into();
+    if let Some(delta) = delta {
+        // Matches the `+ 0.5` and truncation of ttf-parser.
+        value += delta + 0.5;
+    }
+
+    if value > i32::MIN as f32 && value < i32::MAX as f32 {
+        T::try_from(value as i32).ok()
+    } else {
+        None
+    }
+}
+
+struct NoopOutline;
+
+impl OutlineBuilder for NoopOutline {
+    fn move_to(&mut self, _: f32, _: f32) {}
+    fn line_to(&mut self, _: f32, _: f32) {}
+    fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {}
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+    fn close(&mut self) {}
+}
+
+/// The contour points of the glyphs whose contour point anchors were last resolved.
+///
+/// Mark attachment alternates between the anchors of marks and bases, so a few
+/// outlines are kept.
+#[derive(Default)]
+pub(crate) struct ContourPointCache {
+    glyphs: Vec<(GlyphId, Vec<(f32, f32)>)>,
+}
+
+impl ContourPointCache {
+    const CAPACITY: usize = 4;
+
+    /// Returns the contour points of a glyph, which are empty when it has no outline.
+    fn get(&mut self, face: &hb_font_t, glyph: GlyphId) -> &[(f32, f32)] {
+        let index = match self.glyphs.iter().position(|(g, _)| *g == glyph) {
+            Some(index) => index,
+            None => {
+                if self.glyphs.len() == Self::CAPACITY {
+                    self.glyphs.remove(0);
+                }
+
+                let points = face.glyph_contour_points(glyph).unwrap_or_default();
+                self.glyphs.push((glyph, points));
+                self.glyphs.len() - 1
+            }
+        };
+
+        &self.glyphs[index].1
+    }
+}
+
+/// Collects the points of an outline.
+#[derive(Default)]
+struct ContourPoints {
+    points: Vec<(f32, f32)>,
+    contour_start: usize,
+}
+
+impl ContourPoints {
+    /// Returns the extents of all points, including the control points of curves,
+    /// like HarfBuzz does for CFF outlines.
+    fn extents(&self) -> hb_glyph_extents_t {
+        let Some(&(x, y)) = self.points.first() else {
+            return hb_glyph_extents_t::default();
+        };
+
+        let (mut x_min, mut y_min, mut x_max, mut y_max) = (x, y, x, y);
+        for &(x, y) in &self.points[1..] {
+            x_min = x_min.min(x);
+            y_min = y_min.min(y);
+            x_max = x_max.max(x);
+            y_max = y_max.max(y);
+        }
+
+        let x_bearing = x_min.round() as i32;
+        let y_bearing = y_max.round() as i32;
+        hb_glyph_extents_t {
+            x_bearing,
+            y_bearing,
+            width: x_max.round() as i32 - x_bearing,
+            height: y_min.round() as i32 - y_bearing,
+        }
+    }
+}
+
+impl skrifa::outline::OutlinePen for ContourPoints {
+    fn move_to(&mut self, x: f32, y: f32) {
+        OutlineBuilder::move_to(self, x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        OutlineBuilder::line_to(self, x, y);
+    }
+
+    fn quad_to(&mut self, cx0: f32, cy0: f32, x: f32, y: f32) {
+        OutlineBuilder::quad_to(self, cx0, cy0, x, y);
+    }
+
+    fn curve_to(&mut self, cx0: f32, cy0: f32, cx1: f32, cy1: f32, x: f32, y: f32) {
+        OutlineBuilder::curve_to(self, cx0, cy0, cx1, cy1, x, y);
+    }
+
+    fn close(&mut self) {
+        OutlineBuilder::close(self);
+    }
+}
+
+impl OutlineBuilder for ContourPoints {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.contour_start = self.points.len();
+        self.points.push((x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.points.push((x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.points.extend([(x1, y1), (x, y)]);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.points.extend([(x1, y1), (x2, y2), (x, y)]);
+    }
+
+    fn close(&mut self) {
+        // Relative coordinates of blended CFF2 outlines accumulate rounding errors.
+        let coincides = |(x1, y1): (f32, f32), (x2, y2): (f32, f32)| {
+            (x1 - x2).abs() < 1.0 / 64.0 && (y1 - y2).abs() < 1.0 / 64.0
+        };
+
+        if self.points.len() > self.contour_start + 1
+            && coincides(
+                self.points[self.contour_start],
+                self.points[self.points.len() - 1],
+            )
+        {
+            self.points.pop();
+        }
+
+        self.contour_start = self.points.len();
+    }
+}
+
+/// Checks the parameters of a `size` feature like HarfBuzz, which follows the
+/// recommendations of the feature registry.
+fn is_valid_size_params(params: &SizeParams) -> bool {
+    let no_range = params.subfamily_id == 0
+        && params.subfamily_name_id.is_none()
+        && params.range_start == 0
+        && params.range_end == 0;
+    let valid_range = params.range_start <= params.design_size
+        && params.design_size <= params.range_end
+        && params
+            .subfamily_name_id
+            .is_some_and(|id| (256..=32767).contains(&id));
+    params.design_size != 0 && (no_range || valid_range)
+}
+
+/// Converts normalized coordinates for skrifa.
+fn to_skrifa_coords(coords: &[NormalizedCoordinate]) -> Vec<F2Dot14> {
+    coords
+        .iter()
+        .map(|coord| F2Dot14::from_bits(coord.get()))
+        .collect()
+}
+
+/// Parses a decimal number without a sign.
+fn parse_decimal<T: core::str::FromStr>(s: &str) -> Option<T> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+// `ttf_parser::Face` doesn't remember its index in a collection, so find the
+// collection entry with the same table directory. Faces can share tables, but
+// entries with equal directories are the same face.
+fn collection_index(face: &ttf_parser::Face) -> u32 {
+    fn records(raw: ttf_parser::RawFace<'_>) -> impl Iterator<Item = (Tag, u32, u32)> + '_ {
+        raw.table_records
+            .into_iter()
+            .map(|record| (record.tag, record.offset, record.length))
+    }
+
+    let data = face.raw_face().data;
+    let target = *face.raw_face();
+    (0..hb_font_t::face_count(data))
+        .find(|&index| {
+            ttf_parser::RawFace::parse(data, index)
+                .is_ok_and(|raw| records(raw).eq(records(target)))
+        })
+        .unwrap_or(0)
+}
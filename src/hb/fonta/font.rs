@@ -1,4 +1,5 @@
 use super::ot;
+use crate::hb::set_digest::hb_set_digest_t;
 use alloc::vec::Vec;
 use skrifa::{
     charmap::MapVariant,
@@ -7,8 +8,10 @@ use skrifa::{
         tables::{
             cmap::{Cmap, Cmap14, CmapSubtable, PlatformId},
             gpos::{AnchorTable, DeviceOrVariationIndex},
-            variations::{DeltaSetIndex, ItemVariationStore},
+            layout::{DeltaFormat, Device},
+            variations::{DeltaSetIndex, FloatItemDeltaTarget, ItemVariationStore},
         },
+        types::FWord,
         ReadError, TableProvider,
     },
     GlyphId,
@@ -32,22 +35,52 @@ const UNICODE_FULL_ENCODING: u16 = 6;
 
 #[derive(Clone)]
 pub struct Font<'a> {
+    pub font: skrifa::FontRef<'a>,
     pub charmap: Charmap<'a>,
     pub ot: ot::LayoutTables<'a>,
     pub coords: Vec<NormalizedCoord>,
     pub ivs: Option<ItemVariationStore<'a>>,
+    pub units_per_em: u16,
+    pub ppem: Option<(u16, u16)>,
 }
 
 impl<'a> Font<'a> {
     pub fn new(data: &'a [u8], font_index: u32) -> Option<Self> {
+        Self::with_layout_tables(data, font_index, |font| Some(ot::LayoutTables::new(font)))
+    }
+
+    /// Creates the font, building only the lookups that may apply to `glyphs`.
+    pub fn with_glyphs(data: &'a [u8], font_index: u32, glyphs: &hb_set_digest_t) -> Option<Self> {
+        Self::with_layout_tables(data, font_index, |font| {
+            Some(ot::LayoutTables::for_glyphs(font, glyphs))
+        })
+    }
+
+    /// Creates the font using lookup caches written by
+    /// [`ot::LayoutTables::write_caches`].
+    pub fn with_lookup_caches(data: &'a [u8], font_index: u32, caches: &[u8]) -> Option<Self> {
+        Self::with_layout_tables(data, font_index, |font| {
+            ot::LayoutTables::with_caches(font, caches)
+        })
+    }
+
+    fn with_layout_tables(
+        data: &'a [u8],
+        font_index: u32,
+        layout_tables: impl FnOnce(&skrifa::FontRef<'a>) -> Option<ot::LayoutTables<'a>>,
+    ) -> Option<Self> {
         let font = skrifa::FontRef::from_index(data, font_index).ok()?;
         let charmap = Charmap::new(&font);
-        let ot = ot::LayoutTables::new(&font);
+        let ot = layout_tables(&font)?;
+        let units_per_em = font.head().ok()?.units_per_em();
         Some(Self {
+            font,
             charmap,
             ot,
             coords: Vec::new(),
             ivs: None,
+            units_per_em,
+            ppem: None,
         })
     }
 
@@ -68,28 +101,71 @@ impl<'a> Font<'a> {
         }
     }
 
-    pub(super) fn resolve_anchor(&self, anchor: &AnchorTable) -> (i32, i32) {
-        let mut x = anchor.x_coordinate() as i32;
-        let mut y = anchor.y_coordinate() as i32;
-        if let Some(ivs) = self.ivs.as_ref() {
-            let delta = |val: Option<Result<DeviceOrVariationIndex<'_>, ReadError>>| match val {
-                Some(Ok(DeviceOrVariationIndex::VariationIndex(varix))) => ivs
-                    .compute_delta(
-                        DeltaSetIndex {
-                            outer: varix.delta_set_outer_index(),
-                            inner: varix.delta_set_inner_index(),
-                        },
-                        &self.coords,
-                    )
-                    .unwrap_or_default(),
-                _ => 0,
-            };
-            x += delta(anchor.x_device());
-            y += delta(anchor.y_device());
+    /// Resolves an anchor, in font units multiplied by `scale`.
+    pub(crate) fn resolve_anchor(&self, anchor: &AnchorTable, scale: i32) -> (i32, i32) {
+        let mut x = anchor.x_coordinate() as i32 * scale;
+        let mut y = anchor.y_coordinate() as i32 * scale;
+        if let Some(device) = anchor.x_device() {
+            x += self.x_delta(device, scale);
+        }
+        if let Some(device) = anchor.y_device() {
+            y += self.y_delta(device, scale);
         }
         (x, y)
     }
 
+    /// Returns the X-axis delta of a device or variation index table,
+    /// in font units multiplied by `scale`.
+    pub(super) fn x_delta(
+        &self,
+        device: Result<DeviceOrVariationIndex<'_>, ReadError>,
+        scale: i32,
+    ) -> i32 {
+        self.delta(device, self.ppem.map(|(x, _)| x), scale)
+    }
+
+    /// Returns the Y-axis delta of a device or variation index table,
+    /// in font units multiplied by `scale`.
+    pub(super) fn y_delta(
+        &self,
+        device: Result<DeviceOrVariationIndex<'_>, ReadError>,
+        scale: i32,
+    ) -> i32 {
+        self.delta(device, self.ppem.map(|(_, y)| y), scale)
+    }
+
+    fn delta(
+        &self,
+        device: Result<DeviceOrVariationIndex<'_>, ReadError>,
+        ppem: Option<u16>,
+        scale: i32,
+    ) -> i32 {
+        match device {
+            Ok(DeviceOrVariationIndex::Device(device)) => ppem
+                .and_then(|ppem| device_delta(&device, ppem, self.units_per_em, scale))
+                .unwrap_or_default(),
+            Ok(DeviceOrVariationIndex::VariationIndex(varix)) => self
+                .ivs
+                .as_ref()
+                .and_then(|ivs| {
+                    let index = DeltaSetIndex {
+                        outer: varix.delta_set_outer_index(),
+                        inner: varix.delta_set_inner_index(),
+                    };
+                    if scale == 1 {
+                        ivs.compute_delta(index, &self.coords).ok()
+                    } else {
+                        // Keep the fraction of the delta that rounding would drop.
+                        let delta = ivs.compute_float_delta(index, &self.coords).ok()?;
+                        let delta = FWord::new(0).apply_float_delta(delta);
+                        Some((delta * scale as f32).round() as i32)
+                    }
+                })
+                .unwrap_or_default(),
+            Err(_) => 0,
+        }
+    }
+
     pub fn nominal_glyph(&self, mut c: u32) -> Option<GlyphId> {
         let subtable = self.charmap.subtable.as_ref()?;
         if subtable.0 == PlatformId::Macintosh && c > 0x7F {
@@ -149,6 +225,22 @@ pub struct Charmap<'a> {
 }
 
 impl<'a> Charmap<'a> {
+    /// Returns the platform ID, encoding ID and format of the subtable
+    /// used for mapping characters.
+    pub fn subtable_info(&self) -> Option<(u16, u16, u16)> {
+        let (platform_id, encoding_id, subtable) = self.subtable.as_ref()?;
+        let format = match subtable {
+            CmapSubtable::Format0(table) => table.format(),
+            CmapSubtable::Format4(table) => table.format(),
+            CmapSubtable::Format6(table) => table.format(),
+            CmapSubtable::Format10(table) => table.format(),
+            CmapSubtable::Format12(table) => table.format(),
+            _ => return None,
+        };
+
+        Some((*platform_id as u16, *encoding_id, format))
+    }
+
     fn new(font: &impl TableProvider<'a>) -> Self {
         if let Ok(cmap) = font.cmap() {
             let subtable = find_best_cmap_subtable(&cmap);
@@ -241,3 +333,30 @@ fn unicode_to_macroman(c: u32) -> u32 {
     };
     (0x7F + index) as u32
 }
+
+/// Returns the hinting delta of a device table at the given ppem, scaled to
+/// font units.
+fn device_delta(device: &Device, ppem: u16, units_per_em: u16, scale: i32) -> Option<i32> {
+    let f = match device.delta_format() {
+        DeltaFormat::Local2BitDeltas => 1,
+        DeltaFormat::Local4BitDeltas => 2,
+        DeltaFormat::Local8BitDeltas => 3,
+        _ => return None,
+    };
+
+    if ppem == 0 || ppem < device.start_size() || ppem > device.end_size() {
+        return None;
+    }
+
+    let s = ppem - device.start_size();
+    let word = device.delta_value().get(usize::from(s >> (4 - f)))?.get();
+    let bits = word >> (16 - (((s & ((1 << (4 - f)) - 1)) + 1) << f));
+    let mask = 0xFFFF >> (16 - (1 << f));
+
+    let mut delta = i64::from(bits & mask);
+    if delta >= i64::from((mask + 1) >> 1) {
+        delta -= i64::from(mask + 1);
+    }
+
+    i32::try_from(delta * i64::from(units_per_em) * i64::from(scale) / i64::from(ppem)).ok()
+}
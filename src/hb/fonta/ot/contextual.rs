@@ -4,11 +4,149 @@ use crate::hb::ot_layout_gsubgpos::{
     WouldApply, WouldApplyContext,
 };
 use skrifa::raw::tables::layout::{
-    ChainedSequenceContextFormat1, ChainedSequenceContextFormat2, ChainedSequenceContextFormat3,
+    self, ChainedSequenceContextFormat1, ChainedSequenceContextFormat2,
+    ChainedSequenceContextFormat3, SequenceContextFormat1, SequenceContextFormat2,
+    SequenceContextFormat3,
 };
 use skrifa::raw::types::BigEndian;
 use ttf_parser::{opentype_layout::SequenceLookupRecord, GlyphId};
 
+impl WouldApply for SequenceContextFormat1<'_> {
+    fn would_apply(&self, ctx: &WouldApplyContext) -> bool {
+        let glyph = skrifa::GlyphId::from(ctx.glyphs[0].0);
+        self.coverage()
+            .ok()
+            .and_then(|coverage| coverage.get(glyph))
+            .and_then(|index| self.seq_rule_sets().get(index as usize)?.ok())
+            .is_some_and(|set| {
+                set.seq_rules()
+                    .iter()
+                    .filter_map(|rule| rule.ok())
+                    .any(|rule| would_apply_context(ctx, rule.input_sequence(), &match_glyph))
+            })
+    }
+}
+
+impl Apply for SequenceContextFormat1<'_> {
+    fn apply(&self, ctx: &mut hb_ot_apply_context_t) -> Option<()> {
+        let glyph = skrifa::GlyphId::from(ctx.buffer.cur(0).as_glyph().0);
+        let index = self.coverage().ok()?.get(glyph)? as usize;
+        let set = self.seq_rule_sets().get(index)?.ok()?;
+        for rule in set.seq_rules().iter().filter_map(|rule| rule.ok()) {
+            if apply_context(
+                ctx,
+                rule.input_sequence(),
+                &match_glyph,
+                rule.seq_lookup_records(),
+            )
+            .is_some()
+            {
+                return Some(());
+            }
+        }
+        None
+    }
+}
+
+impl WouldApply for SequenceContextFormat2<'_> {
+    fn would_apply(&self, ctx: &WouldApplyContext) -> bool {
+        let classes = self.class_def().ok();
+        let class = classes.as_ref().map_or(0, |classes| {
+            classes.get(skrifa::GlyphId16::new(ctx.glyphs[0].0))
+        });
+        self.class_seq_rule_sets()
+            .get(class as usize)
+            .and_then(|set| set.ok())
+            .is_some_and(|set| {
+                set.class_seq_rules()
+                    .iter()
+                    .filter_map(|rule| rule.ok())
+                    .any(|rule| {
+                        would_apply_context(ctx, rule.input_sequence(), &match_class(&classes))
+                    })
+            })
+    }
+}
+
+impl Apply for SequenceContextFormat2<'_> {
+    fn apply(&self, ctx: &mut hb_ot_apply_context_t) -> Option<()> {
+        let classes = self.class_def().ok();
+        let glyph = ctx.buffer.cur(0).as_skrifa_glyph16();
+        self.coverage().ok()?.get(glyph)?;
+        // A missing ClassDef puts every glyph in class 0.
+        let index = classes.as_ref().map_or(0, |classes| classes.get(glyph)) as usize;
+        let set = self.class_seq_rule_sets().get(index)?.ok()?;
+        for rule in set.class_seq_rules().iter().filter_map(|rule| rule.ok()) {
+            if apply_context(
+                ctx,
+                rule.input_sequence(),
+                &match_class(&classes),
+                rule.seq_lookup_records(),
+            )
+            .is_some()
+            {
+                return Some(());
+            }
+        }
+        None
+    }
+}
+
+impl WouldApply for SequenceContextFormat3<'_> {
+    fn would_apply(&self, ctx: &WouldApplyContext) -> bool {
+        let coverages = self.coverages();
+        ctx.glyphs.len() == coverages.len()
+            && coverages.iter().enumerate().skip(1).all(|(i, coverage)| {
+                coverage
+                    .map(|cov| cov.get(skrifa::GlyphId::from(ctx.glyphs[i].0)).is_some())
+                    .unwrap_or(false)
+            })
+    }
+}
+
+impl Apply for SequenceContextFormat3<'_> {
+    fn apply(&self, ctx: &mut hb_ot_apply_context_t) -> Option<()> {
+        let glyph = skrifa::GlyphId::from(ctx.buffer.cur(0).as_glyph().0);
+
+        let coverages = self.coverages();
+        coverages.get(0).ok()?.get(glyph)?;
+
+        let input = |glyph: GlyphId, index: u16| {
+            coverages
+                .get(index as usize + 1)
+                .map(|cov| cov.get(skrifa::GlyphId::from(glyph.0)).is_some())
+                .unwrap_or_default()
+        };
+
+        let mut match_end = 0;
+        let mut match_positions = smallvec::SmallVec::from_elem(0, 4);
+
+        if match_input(
+            ctx,
+            coverages.len() as u16 - 1,
+            &input,
+            &mut match_end,
+            &mut match_positions,
+            None,
+        ) {
+            ctx.buffer
+                .unsafe_to_break(Some(ctx.buffer.idx), Some(match_end));
+            apply_lookup(
+                ctx,
+                coverages.len() - 1,
+                &mut match_positions,
+                match_end,
+                lookup_records(self.seq_lookup_records()),
+            );
+            Some(())
+        } else {
+            ctx.buffer
+                .unsafe_to_concat(Some(ctx.buffer.idx), Some(match_end));
+            None
+        }
+    }
+}
+
 impl WouldApply for ChainedSequenceContextFormat1<'_> {
     fn would_apply(&self, _ctx: &WouldApplyContext) -> bool {
         false
@@ -30,12 +168,7 @@ impl Apply for ChainedSequenceContextFormat1<'_> {
                 input,
                 lookahead,
                 [&match_glyph; 3],
-                rule.seq_lookup_records()
-                    .iter()
-                    .map(|rec| SequenceLookupRecord {
-                        sequence_index: rec.sequence_index(),
-                        lookup_list_index: rec.lookup_list_index(),
-                    }),
+                lookup_records(rule.seq_lookup_records()),
             )
             .is_some()
             {
@@ -52,15 +185,14 @@ impl WouldApply for ChainedSequenceContextFormat2<'_> {
     }
 }
 
-/// Value represents glyph class.
+/// Value represents glyph class. Without a ClassDef, all glyphs are in class 0.
 fn match_class<'a>(
     class_def: &'a Option<skrifa::raw::tables::layout::ClassDef<'a>>,
 ) -> impl Fn(GlyphId, u16) -> bool + 'a {
     |glyph, value| {
-        class_def
-            .as_ref()
-            .map(|class_def| class_def.get(skrifa::GlyphId16::new(glyph.0)) == value)
-            .unwrap_or(false)
+        class_def.as_ref().map_or(0, |class_def| {
+            class_def.get(skrifa::GlyphId16::new(glyph.0))
+        }) == value
     }
 }
 
@@ -71,7 +203,9 @@ impl Apply for ChainedSequenceContextFormat2<'_> {
         let lookahead_classes = self.lookahead_class_def().ok();
         let glyph = ctx.buffer.cur(0).as_skrifa_glyph16();
         self.coverage().ok()?.get(glyph)?;
-        let index = input_classes.as_ref()?.get(glyph) as usize;
+        let index = input_classes
+            .as_ref()
+            .map_or(0, |classes| classes.get(glyph)) as usize;
         let set = self.chained_class_seq_rule_sets().get(index)?.ok()?;
         for rule in set
             .chained_class_seq_rules()
@@ -91,12 +225,7 @@ impl Apply for ChainedSequenceContextFormat2<'_> {
                     &match_class(&input_classes),
                     &match_class(&lookahead_classes),
                 ],
-                rule.seq_lookup_records()
-                    .iter()
-                    .map(|rec| SequenceLookupRecord {
-                        sequence_index: rec.sequence_index(),
-                        lookup_list_index: rec.lookup_list_index(),
-                    }),
+                lookup_records(rule.seq_lookup_records()),
             )
             .is_some()
             {
@@ -207,12 +336,7 @@ impl Apply for ChainedSequenceContextFormat3<'_> {
             input_coverages.len() - 1,
             &mut match_positions,
             match_end,
-            self.seq_lookup_records()
-                .iter()
-                .map(|rec| SequenceLookupRecord {
-                    sequence_index: rec.sequence_index(),
-                    lookup_list_index: rec.lookup_list_index(),
-                }),
+            lookup_records(self.seq_lookup_records()),
         );
 
         Some(())
@@ -235,6 +359,69 @@ impl ToU16 for BigEndian<u16> {
     }
 }
 
+/// Converts lookup records for [`apply_lookup`].
+fn lookup_records(
+    records: &[layout::SequenceLookupRecord],
+) -> impl Iterator<Item = SequenceLookupRecord> + '_ {
+    records.iter().map(|rec| SequenceLookupRecord {
+        sequence_index: rec.sequence_index(),
+        lookup_list_index: rec.lookup_list_index(),
+    })
+}
+
+fn would_apply_context<T: ToU16>(
+    ctx: &WouldApplyContext,
+    input: &[T],
+    match_func: &match_func_t,
+) -> bool {
+    ctx.glyphs.len() == input.len() + 1
+        && input
+            .iter()
+            .enumerate()
+            .all(|(i, value)| match_func(ctx.glyphs[i + 1], value.to_u16()))
+}
+
+fn apply_context<T: ToU16>(
+    ctx: &mut hb_ot_apply_context_t,
+    input: &[T],
+    match_func: &match_func_t,
+    lookups: &[layout::SequenceLookupRecord],
+) -> Option<()> {
+    // NOTE: Whenever something in this method changes, we also need to
+    // change it in the `apply` implementation for ContextLookup.
+    let match_func = |glyph, index| {
+        let value = (*input.get(index as usize).unwrap()).to_u16();
+        match_func(glyph, value)
+    };
+
+    let mut match_end = 0;
+    let mut match_positions = smallvec::SmallVec::from_elem(0, 4);
+
+    if match_input(
+        ctx,
+        input.len() as u16,
+        &match_func,
+        &mut match_end,
+        &mut match_positions,
+        None,
+    ) {
+        ctx.buffer
+            .unsafe_to_break(Some(ctx.buffer.idx), Some(match_end));
+        apply_lookup(
+            ctx,
+            input.len(),
+            &mut match_positions,
+            match_end,
+            lookup_records(lookups),
+        );
+        Some(())
+    } else {
+        ctx.buffer
+            .unsafe_to_concat(Some(ctx.buffer.idx), Some(match_end));
+        None
+    }
+}
+
 fn apply_chain_context<T: ToU16>(
     ctx: &mut hb_ot_apply_context_t,
     backtrack: &[T],
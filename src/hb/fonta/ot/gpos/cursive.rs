@@ -37,8 +37,19 @@ impl Apply for CursivePosFormat1<'_> {
             return None;
         };
 
-        let (exit_x, exit_y) = ctx.face.font.resolve_anchor(&exit_prev);
-        let (entry_x, entry_y) = ctx.face.font.resolve_anchor(&entry_this);
+        let scale = ctx.buffer.position_scale();
+        let (exit_x, exit_y) = ctx.face.resolve_anchor(
+            &exit_prev,
+            ctx.buffer.info[i].as_glyph(),
+            scale,
+            &mut ctx.contour_points,
+        );
+        let (entry_x, entry_y) = ctx.face.resolve_anchor(
+            &entry_this,
+            ctx.buffer.cur(0).as_glyph(),
+            scale,
+            &mut ctx.contour_points,
+        );
 
         let direction = ctx.buffer.direction;
         let j = ctx.buffer.idx;
@@ -32,8 +32,15 @@ impl MarkArrayExt for MarkArray<'_> {
         // If this subtable doesn't have an anchor for this base and this class
         // return `None` such that the subsequent subtables have a chance at it.
 
-        let (base_x, base_y) = ctx.face.font.resolve_anchor(base_anchor);
-        let (mark_x, mark_y) = ctx.face.font.resolve_anchor(mark_anchor);
+        let base_glyph = ctx.buffer.info[glyph_pos].as_glyph();
+        let mark_glyph = ctx.buffer.cur(0).as_glyph();
+        let scale = ctx.buffer.position_scale();
+        let (base_x, base_y) =
+            ctx.face
+                .resolve_anchor(base_anchor, base_glyph, scale, &mut ctx.contour_points);
+        let (mark_x, mark_y) =
+            ctx.face
+                .resolve_anchor(mark_anchor, mark_glyph, scale, &mut ctx.contour_points);
 
         ctx.buffer
             .unsafe_to_break(Some(glyph_pos), Some(ctx.buffer.idx + 1));
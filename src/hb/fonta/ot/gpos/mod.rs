@@ -6,11 +6,8 @@ use crate::hb::{
     ot_layout_gsubgpos::OT::hb_ot_apply_context_t,
 };
 use skrifa::raw::{
-    tables::{
-        gpos::{DeviceOrVariationIndex, Gpos, ValueRecord},
-        variations::DeltaSetIndex,
-    },
-    FontData, ReadError, TableProvider,
+    tables::gpos::{Gpos, ValueRecord},
+    FontData, TableProvider,
 };
 
 mod cursive;
@@ -78,18 +75,19 @@ impl ValueRecordExt for Value<'_> {
         pos: &mut crate::GlyphPosition,
     ) -> bool {
         let horizontal = ctx.buffer.direction.is_horizontal();
+        let scale = ctx.buffer.position_scale();
         let mut worked = false;
 
         if let Some(value) = self.record.x_placement() {
             if value != 0 {
-                pos.x_offset += i32::from(value);
+                pos.x_offset += i32::from(value) * scale;
                 worked = true;
             }
         }
 
         if let Some(value) = self.record.y_placement() {
             if value != 0 {
-                pos.y_offset += i32::from(value);
+                pos.y_offset += i32::from(value) * scale;
                 worked = true;
             }
         }
@@ -97,7 +95,7 @@ impl ValueRecordExt for Value<'_> {
         if horizontal {
             if let Some(value) = self.record.x_advance() {
                 if value != 0 {
-                    pos.x_advance += i32::from(value);
+                    pos.x_advance += i32::from(value) * scale;
                     worked = true;
                 }
             }
@@ -105,59 +103,43 @@ impl ValueRecordExt for Value<'_> {
             if let Some(value) = self.record.y_advance() {
                 if value != 0 {
                     // y_advance values grow downward but font-space grows upward, hence negation
-                    pos.y_advance -= i32::from(value);
+                    pos.y_advance -= i32::from(value) * scale;
                     worked = true;
                 }
             }
         }
 
-        if let Some(ivs) = ctx.face.font.ivs.as_ref() {
-            let coords = &ctx.face.font.coords;
-            let delta = |val: Result<DeviceOrVariationIndex<'_>, ReadError>| match val {
-                Ok(DeviceOrVariationIndex::VariationIndex(varix)) => ivs
-                    .compute_delta(
-                        DeltaSetIndex {
-                            outer: varix.delta_set_outer_index(),
-                            inner: varix.delta_set_inner_index(),
-                        },
-                        coords,
-                    )
-                    .unwrap_or_default(),
-                _ => 0,
-            };
-
-            let (ppem_x, ppem_y) = ctx.face.pixels_per_em().unwrap_or((0, 0));
-            let coords = ctx.face.ttfp_face.variation_coordinates().len();
-            let use_x_device = ppem_x != 0 || coords != 0;
-            let use_y_device = ppem_y != 0 || coords != 0;
-
-            if use_x_device {
-                if let Some(device) = self.record.x_placement_device(self.data) {
-                    pos.x_offset += delta(device);
-                    worked = true; // TODO: even when 0?
-                }
+        let font = &ctx.face.font;
+        let (ppem_x, ppem_y) = font.ppem.unwrap_or((0, 0));
+        let use_x_device = ppem_x != 0 || !font.coords.is_empty();
+        let use_y_device = ppem_y != 0 || !font.coords.is_empty();
+
+        if use_x_device {
+            if let Some(device) = self.record.x_placement_device(self.data) {
+                pos.x_offset += font.x_delta(device, scale);
+                worked = true; // TODO: even when 0?
             }
+        }
 
-            if use_y_device {
-                if let Some(device) = self.record.y_placement_device(self.data) {
-                    pos.y_offset += delta(device);
-                    worked = true;
-                }
+        if use_y_device {
+            if let Some(device) = self.record.y_placement_device(self.data) {
+                pos.y_offset += font.y_delta(device, scale);
+                worked = true;
             }
+        }
 
-            if horizontal && use_x_device {
-                if let Some(device) = self.record.x_advance_device(self.data) {
-                    pos.x_advance += delta(device);
-                    worked = true;
-                }
+        if horizontal && use_x_device {
+            if let Some(device) = self.record.x_advance_device(self.data) {
+                pos.x_advance += font.x_delta(device, scale);
+                worked = true;
             }
+        }
 
-            if !horizontal && use_y_device {
-                if let Some(device) = self.record.y_advance_device(self.data) {
-                    // y_advance values grow downward but face-space grows upward, hence negation
-                    pos.y_advance -= delta(device);
-                    worked = true;
-                }
+        if !horizontal && use_y_device {
+            if let Some(device) = self.record.y_advance_device(self.data) {
+                // y_advance values grow downward but face-space grows upward, hence negation
+                pos.y_advance -= font.y_delta(device, scale);
+                worked = true;
             }
         }
 
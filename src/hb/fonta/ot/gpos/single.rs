@@ -1,7 +1,9 @@
 use super::Value;
+use crate::hb::fonta::ot::ApplyRun;
 use crate::hb::ot_layout_gpos_table::ValueRecordExt;
 use crate::hb::ot_layout_gsubgpos::Apply;
 use crate::hb::ot_layout_gsubgpos::OT::hb_ot_apply_context_t;
+use crate::hb::set_digest::{hb_set_digest_ext, hb_set_digest_t};
 use skrifa::raw::tables::gpos::{SinglePosFormat1, SinglePosFormat2};
 
 impl Apply for SinglePosFormat1<'_> {
@@ -19,6 +21,41 @@ impl Apply for SinglePosFormat1<'_> {
     }
 }
 
+impl ApplyRun for SinglePosFormat1<'_> {
+    fn apply_run(&self, ctx: &mut hb_ot_apply_context_t, digest: &hb_set_digest_t) -> Option<()> {
+        let coverage = self.coverage().ok()?;
+        coverage.get(ctx.buffer.cur(0).as_skrifa_glyph())?;
+        // All covered glyphs share the same value.
+        let value = Value {
+            record: self.value_record(),
+            data: self.offset_data(),
+        };
+        value.apply(ctx, ctx.buffer.idx);
+        ctx.buffer.idx += 1;
+
+        while ctx.buffer.idx < ctx.buffer.len && ctx.buffer.successful {
+            let cur = ctx.buffer.cur(0);
+            if (cur.mask & ctx.lookup_mask()) == 0
+                || !ctx.check_glyph_property(cur, ctx.lookup_props)
+                || !digest.may_have_glyph(cur.as_glyph())
+                || coverage.get(cur.as_skrifa_glyph()).is_none()
+            {
+                break;
+            }
+
+            // Like `apply_forward`, the budget is charged for each glyph.
+            if !ctx.buffer.consume_budget(1) {
+                break;
+            }
+
+            value.apply(ctx, ctx.buffer.idx);
+            ctx.buffer.idx += 1;
+        }
+
+        Some(())
+    }
+}
+
 impl Apply for SinglePosFormat2<'_> {
     fn apply(&self, ctx: &mut hb_ot_apply_context_t) -> Option<()> {
         let glyph = ctx.buffer.cur(0).as_skrifa_glyph();
@@ -32,7 +32,11 @@ impl Apply for MultipleSubstFormat1<'_> {
             // as a "multiplied" substitution.
             1 => ctx.replace_glyph(GlyphId(substs.get(0)?.get().to_u16())),
 
-            _ => {
+            n => {
+                if !ctx.buffer.can_expand(n) {
+                    return None;
+                }
+
                 let class = if _hb_glyph_info_is_ligature(ctx.buffer.cur(0)) {
                     GlyphPropsFlags::BASE_GLYPH
                 } else {
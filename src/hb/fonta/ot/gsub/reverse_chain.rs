@@ -1,4 +1,3 @@
-use crate::hb::ot_layout::MAX_NESTING_LEVEL;
 use crate::hb::ot_layout_gsubgpos::OT::hb_ot_apply_context_t;
 use crate::hb::ot_layout_gsubgpos::{
     match_backtrack, match_lookahead, Apply, WouldApply, WouldApplyContext,
@@ -20,7 +19,7 @@ impl WouldApply for ReverseChainSingleSubstFormat1<'_> {
 impl Apply for ReverseChainSingleSubstFormat1<'_> {
     fn apply(&self, ctx: &mut hb_ot_apply_context_t) -> Option<()> {
         // No chaining to this type.
-        if ctx.nesting_level_left != MAX_NESTING_LEVEL {
+        if ctx.nesting_level_left != ctx.buffer.max_nesting_level {
             return None;
         }
 
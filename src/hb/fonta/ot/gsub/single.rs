@@ -1,6 +1,9 @@
+use crate::hb::fonta::ot::ApplyRun;
 use crate::hb::ot_layout_gsubgpos::OT::hb_ot_apply_context_t;
 use crate::hb::ot_layout_gsubgpos::{Apply, WouldApply, WouldApplyContext};
+use crate::hb::set_digest::{hb_set_digest_ext, hb_set_digest_t};
 use skrifa::raw::tables::gsub::{SingleSubstFormat1, SingleSubstFormat2};
+use skrifa::raw::types::GlyphId16;
 use ttf_parser::GlyphId;
 
 impl WouldApply for SingleSubstFormat1<'_> {
@@ -16,11 +19,23 @@ impl WouldApply for SingleSubstFormat1<'_> {
 
 impl Apply for SingleSubstFormat1<'_> {
     fn apply(&self, ctx: &mut hb_ot_apply_context_t) -> Option<()> {
-        let glyph = ctx.buffer.cur(0).as_skrifa_glyph16();
-        self.coverage().ok()?.get(glyph)?;
-        let subst = (glyph.to_u16() as i32 + self.delta_glyph_id() as i32) as u16;
-        ctx.replace_glyph(GlyphId(subst));
-        Some(())
+        let coverage = self.coverage().ok()?;
+        let delta = self.delta_glyph_id();
+        substitute(ctx, None, |glyph| {
+            coverage.get(glyph)?;
+            Some(add_delta(glyph, delta))
+        })
+    }
+}
+
+impl ApplyRun for SingleSubstFormat1<'_> {
+    fn apply_run(&self, ctx: &mut hb_ot_apply_context_t, digest: &hb_set_digest_t) -> Option<()> {
+        let coverage = self.coverage().ok()?;
+        let delta = self.delta_glyph_id();
+        substitute(ctx, Some(digest), |glyph| {
+            coverage.get(glyph)?;
+            Some(add_delta(glyph, delta))
+        })
     }
 }
 
@@ -36,10 +51,65 @@ impl WouldApply for SingleSubstFormat2<'_> {
 
 impl Apply for SingleSubstFormat2<'_> {
     fn apply(&self, ctx: &mut hb_ot_apply_context_t) -> Option<()> {
-        let glyph = ctx.buffer.cur(0).as_skrifa_glyph();
-        let index = self.coverage().ok()?.get(glyph)? as usize;
-        let subst = self.substitute_glyph_ids().get(index)?.get().to_u16();
-        ctx.replace_glyph(GlyphId(subst));
-        Some(())
+        let coverage = self.coverage().ok()?;
+        let substitutes = self.substitute_glyph_ids();
+        substitute(ctx, None, |glyph| {
+            let index = coverage.get(glyph)? as usize;
+            Some(substitutes.get(index)?.get().to_u16())
+        })
     }
 }
+
+impl ApplyRun for SingleSubstFormat2<'_> {
+    fn apply_run(&self, ctx: &mut hb_ot_apply_context_t, digest: &hb_set_digest_t) -> Option<()> {
+        let coverage = self.coverage().ok()?;
+        let substitutes = self.substitute_glyph_ids();
+        substitute(ctx, Some(digest), |glyph| {
+            let index = coverage.get(glyph)? as usize;
+            Some(substitutes.get(index)?.get().to_u16())
+        })
+    }
+}
+
+/// Adds `delta` to a glyph ID, modulo 65536.
+fn add_delta(glyph: GlyphId16, delta: i16) -> u16 {
+    glyph.to_u16().wrapping_add(delta as u16)
+}
+
+/// Substitutes the current glyph and, when `digest` is set, the run of
+/// following glyphs that match the lookup and are accepted by `subst`.
+fn substitute(
+    ctx: &mut hb_ot_apply_context_t,
+    digest: Option<&hb_set_digest_t>,
+    subst: impl Fn(GlyphId16) -> Option<u16>,
+) -> Option<()> {
+    let glyph = subst(ctx.buffer.cur(0).as_skrifa_glyph16())?;
+    ctx.replace_glyph(GlyphId(glyph));
+
+    let Some(digest) = digest else {
+        return Some(());
+    };
+
+    while ctx.buffer.idx < ctx.buffer.len && ctx.buffer.successful {
+        let cur = ctx.buffer.cur(0);
+        if (cur.mask & ctx.lookup_mask()) == 0
+            || !ctx.check_glyph_property(cur, ctx.lookup_props)
+            || !digest.may_have_glyph(cur.as_glyph())
+        {
+            break;
+        }
+
+        let Some(glyph) = subst(cur.as_skrifa_glyph16()) else {
+            break;
+        };
+
+        // Like `apply_forward`, the budget is charged for each glyph.
+        if !ctx.buffer.consume_budget(1) {
+            break;
+        }
+
+        ctx.replace_glyph(GlyphId(glyph));
+    }
+
+    Some(())
+}
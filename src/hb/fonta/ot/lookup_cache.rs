@@ -2,6 +2,7 @@ use crate::hb::set_digest::{hb_set_digest_ext, hb_set_digest_t};
 
 use alloc::vec::Vec;
 use core::ops::Range;
+use core::sync::atomic::{AtomicU32, Ordering};
 use skrifa::raw::{
     tables::{
         gpos::{
@@ -23,7 +24,18 @@ use skrifa::raw::{
     FontData, FontRead, Offset, ReadError,
 };
 
-pub trait LookupHost<'a> {
+/// Lookup hosts are shared between threads when building the cache in parallel.
+#[cfg(feature = "parallel")]
+pub trait MaybeSync: Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Sync + ?Sized> MaybeSync for T {}
+
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSync for T {}
+
+pub trait LookupHost<'a>: MaybeSync {
     fn lookup_count(&self) -> u16;
     fn lookup_data(&self, index: u16) -> Result<LookupData<'a>, ReadError>;
 }
@@ -85,6 +97,14 @@ pub struct LookupData<'a> {
     table_data: FontData<'a>,
 }
 
+/// Minimum number of lookups for building the cache on multiple threads.
+///
+/// Building the 394 GSUB lookups of Noto Sans Devanagari takes about 90µs
+/// serially, which is in the range of the cost of spawning a few threads,
+/// so smaller tables are always built on the current thread.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_LOOKUPS: u16 = 256;
+
 /// Cache containing lookup and subtable information for a single GSUB or
 /// GPOS table.
 #[derive(Clone, Default)]
@@ -106,12 +126,79 @@ impl LookupCache {
     pub fn create_all<'a>(&mut self, host: &impl LookupHost<'a>) {
         self.clear();
         let count = host.lookup_count();
+        #[cfg(feature = "parallel")]
+        if count >= PARALLEL_MIN_LOOKUPS {
+            // With a single thread, spawning one only adds overhead.
+            let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            if threads > 1 {
+                self.create_all_parallel(host, count, threads);
+                return;
+            }
+        }
         self.lookups.resize(count as usize, Default::default());
         for i in 0..count {
             let _ = self.get_or_create(host, i);
         }
     }
 
+    /// Builds only the lookups that may apply to the glyphs in `glyphs`.
+    ///
+    /// Lookups whose primary coverages don't intersect `glyphs` are left
+    /// vacant and can be built later with [`get_or_create`](Self::get_or_create).
+    /// Since lookups can be applied to glyphs produced by other lookups,
+    /// `glyphs` should contain those as well.
+    pub fn create_for<'a>(&mut self, host: &impl LookupHost<'a>, glyphs: &hb_set_digest_t) {
+        let count = host.lookup_count();
+        if self.lookups.len() < count as usize {
+            self.lookups.resize(count as usize, Default::default());
+        }
+        for i in 0..count {
+            if self.lookups[i as usize].state == LookupState::Vacant
+                && may_intersect(host, i, glyphs).unwrap_or(true)
+            {
+                let _ = self.get_or_create(host, i);
+            }
+        }
+    }
+
+    /// Builds the lookups in chunks on multiple threads.
+    ///
+    /// Each chunk is built independently and chunks are appended in order,
+    /// so the result is identical to a serial build.
+    #[cfg(feature = "parallel")]
+    fn create_all_parallel<'a>(&mut self, host: &impl LookupHost<'a>, count: u16, threads: usize) {
+        let count = usize::from(count);
+        let chunk_size = count.div_ceil(threads).max(1);
+        let chunks: Vec<(Vec<LookupInfo>, Vec<SubtableInfo>)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..count)
+                .step_by(chunk_size)
+                .map(|start| {
+                    let end = (start + chunk_size).min(count);
+                    scope.spawn(move || {
+                        let mut lookups = alloc::vec![LookupInfo::default(); end - start];
+                        let mut subtables = Vec::new();
+                        for (entry, index) in lookups.iter_mut().zip(start..end) {
+                            let _ = create_lookup(host, index as u16, entry, &mut subtables);
+                        }
+                        (lookups, subtables)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        for (lookups, subtables) in chunks {
+            let start = self.subtables.len() as u32;
+            self.lookups.extend(lookups.into_iter().map(|mut entry| {
+                entry.subtables_start += start;
+                entry
+            }));
+            self.subtables.extend(subtables);
+        }
+    }
+
     pub fn get(&self, index: u16) -> Option<&LookupInfo> {
         let entry = self.lookups.get(index as usize)?;
         match entry.state {
@@ -133,102 +220,338 @@ impl LookupCache {
         if entry.state != LookupState::Vacant {
             return Ok(entry);
         }
-        entry.state = LookupState::Error;
-        let data = cx.lookup_data(index as u16)?;
-        entry.is_subst = data.is_subst;
-        let lookup_data = data
-            .table_data
-            .split_off(data.offset)
-            .ok_or(ReadError::OutOfBounds)?;
-        let lookup: Lookup<()> = Lookup::read(lookup_data)?;
-        let kind = lookup.lookup_type();
-        let lookup_flag = lookup.lookup_flag();
-        entry.props = u32::from(lookup.lookup_flag().to_bits());
-        if lookup_flag.to_bits() & LookupFlag::USE_MARK_FILTERING_SET.to_bits() != 0 {
-            entry.props |= (lookup.mark_filtering_set().unwrap_or_default() as u32) << 16;
-        }
-        entry.is_rtl = lookup_flag.to_bits() & LookupFlag::RIGHT_TO_LEFT.to_bits() != 0;
-        if data.is_subst {
-            entry.is_reversed =
-                is_reversed(data.table_data, &lookup, data.offset).unwrap_or_default();
-        }
-        entry.subtables_start = self
-            .subtables
-            .len()
-            .try_into()
-            .map_err(|_| ReadError::MalformedData("too many subtables"))?;
-        entry.state = LookupState::Ready;
-        let mut process_subtable = |mut subtable_offset: usize| {
-            let mut subtable_kind = kind;
-            match (data.is_subst, kind) {
-                (true, 7) | (false, 9) => {
-                    let subtable_data = data
-                        .table_data
-                        .split_off(subtable_offset)
-                        .ok_or(ReadError::OutOfBounds)?;
-                    let ext = ExtensionSubstFormat1::<()>::read(subtable_data)?;
-                    subtable_kind = ext.extension_lookup_type();
-                    let ext_offset = ext.extension_offset().to_usize();
-                    subtable_offset += ext_offset;
-                }
-                _ => {}
+        create_lookup(cx, index as u16, entry, &mut self.subtables)?;
+        Ok(entry)
+    }
+
+    /// Returns statistics about the cached lookups and subtables.
+    pub fn stats(&self) -> LookupCacheStats {
+        let mut stats = LookupCacheStats {
+            lookups: self.lookups.len(),
+            subtables: self.subtables.len(),
+            ..Default::default()
+        };
+        for entry in &self.lookups {
+            match entry.state {
+                LookupState::Vacant => stats.vacant_lookups += 1,
+                LookupState::Ready => stats.ready_lookups += 1,
+                LookupState::Error => stats.error_lookups += 1,
+            }
+        }
+        for subtable in &self.subtables {
+            if let Some(count) = stats
+                .subtables_per_type
+                .get_mut(subtable.lookup_type as usize)
+            {
+                *count += 1;
             }
-            let mut subtable_info = SubtableInfo {
-                offset: subtable_offset
-                    .try_into()
-                    .map_err(|_| ReadError::OutOfBounds)?,
-                coverage_offset: 0,
-                is_subst: data.is_subst,
-                lookup_type: subtable_kind as u8,
-                digest: Default::default(),
+        }
+        stats
+    }
+
+    pub fn subtables(&self, entry: &LookupInfo) -> Option<&[SubtableInfo]> {
+        self.subtables.get(entry.subtables_range())
+    }
+
+    /// Appends the cache to `out`, so it can be restored with
+    /// [`read`](Self::read) without parsing the layout table again.
+    pub fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&(self.lookups.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(self.subtables.len() as u32).to_le_bytes());
+        for entry in &self.lookups {
+            out.push(entry.state as u8);
+            out.extend_from_slice(&entry.props.to_le_bytes());
+            out.push(
+                u8::from(entry.is_subst)
+                    | u8::from(entry.is_rtl) << 1
+                    | u8::from(entry.is_reversed) << 2,
+            );
+            out.extend_from_slice(&entry.subtables_start.to_le_bytes());
+            out.extend_from_slice(&entry.subtables_count.to_le_bytes());
+            out.extend_from_slice(&entry.skipped_subtables.to_le_bytes());
+            out.extend_from_slice(&entry.unsupported_subtables.to_le_bytes());
+            entry
+                .digest
+                .write_masks(&mut |mask| out.extend_from_slice(&mask.to_le_bytes()));
+        }
+        for subtable in &self.subtables {
+            out.extend_from_slice(&subtable.offset.to_le_bytes());
+            out.extend_from_slice(&subtable.coverage_offset.to_le_bytes());
+            out.push(u8::from(subtable.is_subst));
+            out.push(subtable.lookup_type);
+            subtable
+                .digest
+                .write_masks(&mut |mask| out.extend_from_slice(&mask.to_le_bytes()));
+        }
+    }
+
+    /// Reads a cache written by [`write`](Self::write) and advances `data`
+    /// past it.
+    ///
+    /// Returns `None` if the data is truncated or inconsistent.
+    pub fn read(data: &mut &[u8]) -> Option<Self> {
+        let lookups_count = read_u32(data)? as usize;
+        let subtables_count = read_u32(data)? as usize;
+        // Every entry takes at least one byte, which bounds the allocations
+        // for malformed data.
+        if lookups_count.saturating_add(subtables_count) > data.len() {
+            return None;
+        }
+
+        let mut cache = LookupCache {
+            lookups: Vec::with_capacity(lookups_count),
+            subtables: Vec::with_capacity(subtables_count),
+        };
+        for _ in 0..lookups_count {
+            let state = match read_u8(data)? {
+                0 => LookupState::Vacant,
+                1 => LookupState::Ready,
+                2 => LookupState::Error,
+                _ => return None,
             };
-            // TODO: update as we add more subtables
-            let is_supported = match (data.is_subst, subtable_kind) {
-                // (true, 1) | (true, 2) | (true, 3) | (true, 4) => true,
-                (true, 1) | (true, 2) | (true, 3) | (true, 4) => true,
-                (false, 4) | (false, 6) => true,
-                // single pos
-                (false, 1) => true,
-                // pair pos
-                (false, 2) => true,
-                // cursive pos
-                (false, 3) => true,
-                // mark lig pos
-                (false, 5) => true,
-                // chained sequence context
-                (true, 6) => true,
-                (false, 8) => true,
-                // reverse chained context
-                (true, 8) => true,
-                _ => false,
+            let props = read_u32(data)?;
+            let flags = read_u8(data)?;
+            let entry = LookupInfo {
+                state,
+                props,
+                is_subst: flags & 1 != 0,
+                is_rtl: flags & 2 != 0,
+                is_reversed: flags & 4 != 0,
+                subtables_start: read_u32(data)?,
+                subtables_count: read_u16(data)?,
+                skipped_subtables: read_u16(data)?,
+                unsupported_subtables: read_u16(data)?,
+                digest: hb_set_digest_t::read_masks(&mut || read_u64(data))?,
             };
-            if !is_supported {
-                return Err(ReadError::MalformedData("unsupported subtable"));
+            if entry.subtables_range().end > subtables_count {
+                return None;
+            }
+            cache.lookups.push(entry);
+        }
+        for _ in 0..subtables_count {
+            cache.subtables.push(SubtableInfo {
+                offset: read_u32(data)?,
+                coverage_offset: read_u16(data)?,
+                is_subst: read_u8(data)? != 0,
+                lookup_type: read_u8(data)?,
+                digest: hb_set_digest_t::read_masks(&mut || read_u64(data))?,
+                coverage_cache: CoverageCache::default(),
+            });
+        }
+
+        Some(cache)
+    }
+}
+
+fn read_bytes<const N: usize>(data: &mut &[u8]) -> Option<[u8; N]> {
+    let bytes = data.get(..N)?.try_into().ok()?;
+    *data = &data[N..];
+    Some(bytes)
+}
+
+fn read_u8(data: &mut &[u8]) -> Option<u8> {
+    read_bytes::<1>(data).map(|bytes| bytes[0])
+}
+
+fn read_u16(data: &mut &[u8]) -> Option<u16> {
+    read_bytes(data).map(u16::from_le_bytes)
+}
+
+fn read_u32(data: &mut &[u8]) -> Option<u32> {
+    read_bytes(data).map(u32::from_le_bytes)
+}
+
+fn read_u64(data: &mut &[u8]) -> Option<u64> {
+    read_bytes(data).map(u64::from_le_bytes)
+}
+
+/// Statistics about the contents of a [`LookupCache`].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct LookupCacheStats {
+    /// Total number of lookups.
+    pub lookups: usize,
+    /// Number of lookups that are available for use.
+    pub ready_lookups: usize,
+    /// Number of lookups that couldn't be read.
+    pub error_lookups: usize,
+    /// Number of lookups that were not built yet.
+    pub vacant_lookups: usize,
+    /// Total number of subtables.
+    pub subtables: usize,
+    /// Number of subtables per lookup type, indexed by the lookup type.
+    ///
+    /// Extension subtables are counted as the type of the subtable they
+    /// point to.
+    pub subtables_per_type: [usize; 10],
+}
+
+/// Reads the lookup at `index` into `entry`, appending its subtables to
+/// `subtables`.
+fn create_lookup<'a>(
+    cx: &impl LookupHost<'a>,
+    index: u16,
+    entry: &mut LookupInfo,
+    subtables: &mut Vec<SubtableInfo>,
+) -> Result<(), ReadError> {
+    entry.state = LookupState::Error;
+    let data = cx.lookup_data(index)?;
+    entry.is_subst = data.is_subst;
+    let lookup_data = data
+        .table_data
+        .split_off(data.offset)
+        .ok_or(ReadError::OutOfBounds)?;
+    let lookup: Lookup<()> = Lookup::read(lookup_data)?;
+    let kind = lookup.lookup_type();
+    let lookup_flag = lookup.lookup_flag();
+    entry.props = u32::from(lookup.lookup_flag().to_bits());
+    if lookup_flag.to_bits() & LookupFlag::USE_MARK_FILTERING_SET.to_bits() != 0 {
+        entry.props |= (lookup.mark_filtering_set().unwrap_or_default() as u32) << 16;
+    }
+    entry.is_rtl = lookup_flag.to_bits() & LookupFlag::RIGHT_TO_LEFT.to_bits() != 0;
+    if data.is_subst {
+        entry.is_reversed = is_reversed(data.table_data, &lookup, data.offset).unwrap_or_default();
+    }
+    entry.subtables_start = subtables
+        .len()
+        .try_into()
+        .map_err(|_| ReadError::MalformedData("too many subtables"))?;
+    entry.state = LookupState::Ready;
+    let mut skipped_subtables = 0;
+    let mut unsupported_subtables = 0;
+    let mut process_subtable = |mut subtable_offset: usize| {
+        let mut subtable_kind = kind;
+        match (data.is_subst, kind) {
+            (true, 7) | (false, 9) => {
+                let subtable_data = data
+                    .table_data
+                    .split_off(subtable_offset)
+                    .ok_or(ReadError::OutOfBounds)?;
+                let ext = ExtensionSubstFormat1::<()>::read(subtable_data)?;
+                subtable_kind = ext.extension_lookup_type();
+                if is_extension(data.is_subst, subtable_kind as u8) {
+                    return Err(ReadError::MalformedData("extension of an extension"));
+                }
+                let ext_offset = ext.extension_offset().to_usize();
+                subtable_offset += ext_offset;
             }
-            let subtable = subtable_info.materialize(data.table_data.as_bytes())?;
-            let (coverage, coverage_offset) = subtable.coverage_and_offset()?;
-            add_coverage_to_digest(&coverage, &mut subtable_info.digest);
-            add_coverage_to_digest(&coverage, &mut entry.digest);
-            // subtable_info.digest.insert_coverage(&coverage);
-            // entry.digest.insert_coverage(&coverage);
-            subtable_info.coverage_offset = coverage_offset;
-            self.subtables.push(subtable_info);
-            entry.subtables_count += 1;
-            Ok::<(), ReadError>(())
+            _ => {}
+        }
+        let mut subtable_info = SubtableInfo {
+            offset: subtable_offset
+                .try_into()
+                .map_err(|_| ReadError::OutOfBounds)?,
+            coverage_offset: 0,
+            is_subst: data.is_subst,
+            lookup_type: subtable_kind as u8,
+            digest: Default::default(),
+            coverage_cache: CoverageCache::default(),
+        };
+        // TODO: update as we add more subtables
+        let is_supported = match (data.is_subst, subtable_kind) {
+            // (true, 1) | (true, 2) | (true, 3) | (true, 4) => true,
+            (true, 1) | (true, 2) | (true, 3) | (true, 4) => true,
+            (false, 4) | (false, 6) => true,
+            // single pos
+            (false, 1) => true,
+            // pair pos
+            (false, 2) => true,
+            // cursive pos
+            (false, 3) => true,
+            // mark lig pos
+            (false, 5) => true,
+            // sequence context
+            (true, 5) | (false, 7) => true,
+            // chained sequence context
+            (true, 6) => true,
+            (false, 8) => true,
+            // reverse chained context
+            (true, 8) => true,
+            _ => false,
         };
-        for subtable_offset in lookup.subtable_offsets() {
-            let subtable_offset = subtable_offset.get().to_usize() + data.offset;
-            // Just drop subtables with errors
-            let _ = process_subtable(subtable_offset);
+        if !is_supported {
+            unsupported_subtables += 1;
+            return Err(ReadError::MalformedData("unsupported subtable"));
+        }
+        let subtable = subtable_info.materialize(data.table_data.as_bytes())?;
+        let (coverage, coverage_offset) = subtable.coverage_and_offset()?;
+        subtable_info.coverage_offset = coverage_offset;
+        subtable_info.primary_coverage_table(data.table_data.as_bytes())?;
+        add_coverage_to_digest(&coverage, &mut subtable_info.digest);
+        add_coverage_to_digest(&coverage, &mut entry.digest);
+        // subtable_info.digest.insert_coverage(&coverage);
+        // entry.digest.insert_coverage(&coverage);
+        subtables.push(subtable_info);
+        entry.subtables_count += 1;
+        Ok::<(), ReadError>(())
+    };
+    for subtable_offset in lookup.subtable_offsets() {
+        let subtable_offset = subtable_offset.get().to_usize() + data.offset;
+        // Just drop subtables with errors
+        if process_subtable(subtable_offset).is_err() {
+            skipped_subtables += 1;
         }
-        Ok(entry)
     }
+    entry.unsupported_subtables = unsupported_subtables;
+    entry.skipped_subtables = skipped_subtables - unsupported_subtables;
+    Ok(())
+}
 
-    pub fn subtables(&self, entry: &LookupInfo) -> Option<&[SubtableInfo]> {
-        self.subtables.get(entry.subtables_range())
+/// Checks whether the primary coverage of any subtable of the lookup at
+/// `index` may intersect `glyphs`.
+///
+/// Unlike [`create_lookup`], this stops at the first match and doesn't build
+/// digests, so it's cheap to call for lookups that are never used.
+fn may_intersect<'a>(
+    cx: &impl LookupHost<'a>,
+    index: u16,
+    glyphs: &hb_set_digest_t,
+) -> Result<bool, ReadError> {
+    let data = cx.lookup_data(index)?;
+    let lookup_data = data
+        .table_data
+        .split_off(data.offset)
+        .ok_or(ReadError::OutOfBounds)?;
+    let lookup: Lookup<()> = Lookup::read(lookup_data)?;
+    let kind = lookup.lookup_type() as u8;
+    for subtable_offset in lookup.subtable_offsets() {
+        let subtable_offset = subtable_offset.get().to_usize() + data.offset;
+        let Some(subtable_data) = data.table_data.split_off(subtable_offset) else {
+            continue;
+        };
+        let Ok(subtable) = Subtable::read(subtable_data, data.is_subst, kind) else {
+            continue;
+        };
+        let Ok((coverage, _)) = subtable.coverage_and_offset() else {
+            continue;
+        };
+        if coverage_may_intersect(&coverage, glyphs) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn coverage_may_intersect(coverage: &CoverageTable, glyphs: &hb_set_digest_t) -> bool {
+    match coverage {
+        CoverageTable::Format1(table) => table
+            .glyph_array()
+            .iter()
+            .any(|glyph| glyphs.may_have_glyph(ttf_parser::GlyphId(glyph.get().to_u16()))),
+        CoverageTable::Format2(table) => table.range_records().iter().any(|range| {
+            let mut digest = hb_set_digest_t::new();
+            let first = ttf_parser::GlyphId(range.start_glyph_id().to_u16());
+            let last = ttf_parser::GlyphId(range.end_glyph_id().to_u16());
+            digest.add_range(first, last);
+            glyphs.may_have(&digest)
+        }),
     }
 }
 
+/// Checks whether `lookup_type` is the extension lookup type of GSUB or GPOS.
+fn is_extension(is_subst: bool, lookup_type: u8) -> bool {
+    matches!((is_subst, lookup_type), (true, 7) | (false, 9))
+}
+
 fn is_reversed(table_data: FontData, lookup: &Lookup<()>, lookup_offset: usize) -> Option<bool> {
     match lookup.lookup_type() {
         // Reverse chain context
@@ -291,6 +614,11 @@ pub struct LookupInfo {
     pub subtables_start: u32,
     /// Number of subtables in the cache subtables vector.
     pub subtables_count: u16,
+    /// Number of subtables that were dropped because they are malformed.
+    pub skipped_subtables: u16,
+    /// Number of subtables that were dropped because their format is not
+    /// supported.
+    pub unsupported_subtables: u16,
     /// Bloom filter representing the set of glyphs from the primary
     /// coverage of all subtables in the lookup.
     pub digest: hb_set_digest_t,
@@ -318,6 +646,8 @@ pub struct SubtableInfo {
     /// Original lookup type.
     pub lookup_type: u8,
     pub digest: hb_set_digest_t,
+    /// The result of the last primary coverage lookup.
+    pub coverage_cache: CoverageCache,
 }
 
 impl SubtableInfo {
@@ -325,14 +655,41 @@ impl SubtableInfo {
         &self,
         table_data: &'a [u8],
     ) -> Result<CoverageTable<'a>, ReadError> {
-        let offset = self.offset as usize + self.coverage_offset as usize;
-        let data = FontData::new(table_data.get(offset..).ok_or(ReadError::OutOfBounds)?);
+        let subtable = table_data
+            .get(self.offset as usize..)
+            .ok_or(ReadError::OutOfBounds)?;
+        // The coverage can't overlap the fields of the subtable itself,
+        // otherwise a malformed offset could point to data that happens to
+        // parse as a coverage table.
+        let header_len =
+            subtable_header_len(FontData::new(subtable), self.is_subst, self.lookup_type)?;
+        if (self.coverage_offset as usize) < header_len {
+            return Err(ReadError::OutOfBounds);
+        }
+        let data = FontData::new(
+            subtable
+                .get(self.coverage_offset as usize..)
+                .ok_or(ReadError::OutOfBounds)?,
+        );
         CoverageTable::read(data)
     }
 
+    /// Returns the coverage index of a glyph in the primary coverage.
+    ///
+    /// The result for the last glyph is cached, so that subtables that don't
+    /// cover a glyph are skipped without being read when applying a lookup.
     pub fn primary_coverage(&self, table_data: &[u8], glyph_id: GlyphId) -> Option<u16> {
+        let glyph = u16::try_from(glyph_id.to_u32()).ok()?;
+        if let Some(index) = self.coverage_cache.get(glyph) {
+            return index;
+        }
+
         let coverage = self.primary_coverage_table(table_data).ok()?;
-        coverage.get(glyph_id)
+        #[cfg(test)]
+        self.coverage_cache.searches.fetch_add(1, Ordering::Relaxed);
+        let index = coverage.get(glyph_id);
+        self.coverage_cache.set(glyph, index);
+        index
     }
 
     pub fn materialize<'a>(&self, table_data: &'a [u8]) -> Result<Subtable<'a>, ReadError> {
@@ -345,6 +702,123 @@ impl SubtableInfo {
     }
 }
 
+/// The last glyph looked up in a coverage table, together with its
+/// coverage index.
+///
+/// Lookups are applied one glyph after another, so runs of the same glyph
+/// query a subtable's coverage with the same glyph repeatedly. The entry is
+/// packed into an atomic, so that the lookup cache can be shared between
+/// threads.
+#[derive(Debug)]
+pub struct CoverageCache {
+    /// The glyph in the upper 16 bits, the coverage index in the lower ones.
+    entry: AtomicU32,
+    /// Number of times the coverage table was actually searched.
+    #[cfg(test)]
+    searches: AtomicU32,
+}
+
+impl CoverageCache {
+    const EMPTY: u32 = u32::MAX;
+    const NOT_COVERED: u16 = u16::MAX;
+
+    /// Returns the cached coverage index of a glyph, if the glyph is the
+    /// cached one.
+    fn get(&self, glyph: u16) -> Option<Option<u16>> {
+        let entry = self.entry.load(Ordering::Relaxed);
+        if entry == Self::EMPTY || (entry >> 16) as u16 != glyph {
+            return None;
+        }
+
+        let index = entry as u16;
+        Some((index != Self::NOT_COVERED).then_some(index))
+    }
+
+    fn set(&self, glyph: u16, index: Option<u16>) {
+        let entry = u32::from(glyph) << 16 | u32::from(index.unwrap_or(Self::NOT_COVERED));
+        self.entry.store(entry, Ordering::Relaxed);
+    }
+}
+
+impl Default for CoverageCache {
+    fn default() -> Self {
+        CoverageCache {
+            entry: AtomicU32::new(Self::EMPTY),
+            #[cfg(test)]
+            searches: AtomicU32::new(0),
+        }
+    }
+}
+
+impl Clone for CoverageCache {
+    fn clone(&self) -> Self {
+        CoverageCache {
+            entry: AtomicU32::new(self.entry.load(Ordering::Relaxed)),
+            #[cfg(test)]
+            searches: AtomicU32::new(0),
+        }
+    }
+}
+
+/// Returns the size of the fields of a subtable, including its arrays of
+/// offsets and records, which precede all the data it points to.
+fn subtable_header_len(
+    data: FontData,
+    is_subst: bool,
+    lookup_type: u8,
+) -> Result<usize, ReadError> {
+    let format = data.read_at::<u16>(0)?;
+    let count = |pos: usize| data.read_at::<u16>(pos).map(usize::from);
+    let len = match (is_subst, lookup_type, format) {
+        // Single, multiple, alternate and ligature substitution.
+        (true, 1, 1) => 6,
+        (true, 1, 2) | (true, 2..=4, 1) => 6 + 2 * count(4)?,
+        // Reverse chaining contextual single substitution.
+        (true, 8, 1) => {
+            let backtrack = count(4)?;
+            let lookahead = count(6 + 2 * backtrack)?;
+            let glyphs = count(8 + 2 * (backtrack + lookahead))?;
+            10 + 2 * (backtrack + lookahead + glyphs)
+        }
+        // Single positioning.
+        (false, 1, 1) => 6 + value_record_len(data.read_at(4)?),
+        (false, 1, 2) => 8 + count(6)? * value_record_len(data.read_at(4)?),
+        // Pair positioning.
+        (false, 2, 1) => 10 + 2 * count(8)?,
+        (false, 2, 2) => {
+            let record_len =
+                value_record_len(data.read_at(4)?) + value_record_len(data.read_at(6)?);
+            16 + count(12)?
+                .saturating_mul(count(14)?)
+                .saturating_mul(record_len)
+        }
+        // Cursive positioning.
+        (false, 3, 1) => 6 + 4 * count(4)?,
+        // Mark-to-base, mark-to-ligature and mark-to-mark positioning.
+        (false, 4..=6, 1) => 12,
+        // Sequence context.
+        (true, 5, 1) | (false, 7, 1) => 6 + 2 * count(4)?,
+        (true, 5, 2) | (false, 7, 2) => 8 + 2 * count(6)?,
+        (true, 5, 3) | (false, 7, 3) => 6 + 2 * count(2)? + 4 * count(4)?,
+        // Chained sequence context.
+        (true, 6, 1) | (false, 8, 1) => 6 + 2 * count(4)?,
+        (true, 6, 2) | (false, 8, 2) => 12 + 2 * count(10)?,
+        (true, 6, 3) | (false, 8, 3) => {
+            let backtrack = count(2)?;
+            let input = count(4 + 2 * backtrack)?;
+            let lookahead = count(6 + 2 * (backtrack + input))?;
+            let lookups = count(8 + 2 * (backtrack + input + lookahead))?;
+            10 + 2 * (backtrack + input + lookahead) + 4 * lookups
+        }
+        _ => return Err(ReadError::InvalidFormat(format.into())),
+    };
+    Ok(len)
+}
+
+fn value_record_len(format: u16) -> usize {
+    (format & 0xFF).count_ones() as usize * 2
+}
+
 /// All possible subtables in a lookup.
 #[derive(Clone)]
 pub enum Subtable<'a> {
@@ -405,6 +879,11 @@ impl<'a> Subtable<'a> {
             (true, 7) | (false, 9) => {
                 let ext = ExtensionSubstFormat1::<'a, ()>::read(data)?;
                 let ext_type = ext.extension_lookup_type() as u8;
+                // Extensions must not point to other extensions, which
+                // could also recurse forever.
+                if is_extension(is_sub, ext_type) {
+                    return Err(ReadError::MalformedData("extension of an extension"));
+                }
                 let offset = ext.extension_offset().to_usize();
                 let data = data.split_off(offset).ok_or(ReadError::OutOfBounds)?;
                 Self::read(data, is_sub, ext_type)
@@ -460,3 +939,352 @@ impl<'a> Subtable<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CoverageCache, LookupCache, LookupHost, SubtableInfo};
+    use crate::hb::set_digest::{hb_set_digest_ext, hb_set_digest_t};
+    use alloc::vec::Vec;
+    use core::sync::atomic::Ordering;
+    use skrifa::raw::{
+        tables::gsub::Gsub, types::GlyphId, FontData, FontRead, FontRef, ReadError, TableProvider,
+    };
+
+    #[cfg(feature = "parallel")]
+    fn serial<'a>(host: &impl LookupHost<'a>) -> std::string::String {
+        let mut cache = LookupCache::new();
+        for i in 0..host.lookup_count() {
+            let _ = cache.get_or_create(host, i);
+        }
+        std::format!("{:?} {:?}", cache.lookups, cache.subtables)
+    }
+
+    #[cfg(feature = "parallel")]
+    fn parallel<'a>(host: &impl LookupHost<'a>) -> std::string::String {
+        let mut cache = LookupCache::new();
+        cache.create_all_parallel(host, host.lookup_count(), 4);
+        std::format!("{:?} {:?}", cache.lookups, cache.subtables)
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_build_matches_serial_build() {
+        let data = std::fs::read("tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf").unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let gsub = font.gsub().unwrap();
+        let gpos = font.gpos().unwrap();
+        assert_eq!(serial(&gsub), parallel(&gsub));
+        assert_eq!(serial(&gpos), parallel(&gpos));
+    }
+
+    #[test]
+    fn create_for_skips_unused_lookups() {
+        let data = std::fs::read("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf").unwrap();
+        let face = crate::Face::from_slice(&data, 0).unwrap();
+        let mut buffer = crate::UnicodeBuffer::new();
+        buffer.push_str("abc");
+        let buffer = crate::shape(&face, &[], buffer);
+        let mut glyphs = hb_set_digest_t::new();
+        for info in buffer.glyph_infos() {
+            glyphs.add(ttf_parser::GlyphId(info.glyph_id as u16));
+        }
+
+        let font = FontRef::new(&data).unwrap();
+        let gsub = font.gsub().unwrap();
+        let mut all = LookupCache::new();
+        all.create_all(&gsub);
+        let mut lazy = LookupCache::new();
+        lazy.create_for(&gsub, &glyphs);
+        assert_eq!(all.subtables.len(), 18);
+        assert_eq!(lazy.subtables.len(), 5);
+
+        // Skipped lookups can still be built on demand.
+        for i in 0..gsub.lookup_count() {
+            let _ = lazy.get_or_create(&gsub, i);
+        }
+        assert_eq!(lazy.subtables.len(), 18);
+    }
+
+    #[test]
+    fn shaping_uses_coverage_cache() {
+        let data = std::fs::read("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf").unwrap();
+        let searches = |text: &str| {
+            let face = crate::Face::from_slice(&data, 0).unwrap();
+            let mut buffer = crate::UnicodeBuffer::new();
+            buffer.push_str(text);
+            crate::shape(&face, &[], buffer);
+            let gpos = face.font.ot.gpos.as_ref().unwrap();
+            gpos.lookups
+                .subtables
+                .iter()
+                .map(|subtable| subtable.coverage_cache.searches.load(Ordering::Relaxed))
+                .sum::<u32>()
+        };
+
+        // Repeating a glyph doesn't search the coverages again.
+        assert!(searches("A") > 0);
+        assert_eq!(searches("AAAAAAAA"), searches("A"));
+    }
+
+    #[test]
+    fn write_read_roundtrip() {
+        let data = std::fs::read("tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf").unwrap();
+        let font = FontRef::new(&data).unwrap();
+        let mut cache = LookupCache::new();
+        cache.create_all(&font.gpos().unwrap());
+
+        let mut bytes = std::vec::Vec::new();
+        cache.write(&mut bytes);
+        let mut data = &bytes[..];
+        let read = LookupCache::read(&mut data).unwrap();
+        assert!(data.is_empty());
+        assert_eq!(
+            std::format!("{:?} {:?}", cache.lookups, cache.subtables),
+            std::format!("{:?} {:?}", read.lookups, read.subtables)
+        );
+
+        // Truncated data is rejected.
+        assert!(LookupCache::read(&mut &bytes[..bytes.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn extension_of_extension() {
+        let mut data = std::vec::Vec::new();
+        let mut push = |values: &[u16]| {
+            for value in values {
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+        };
+        // Header, empty script and feature lists, and a lookup list with
+        // two extension lookups.
+        push(&[1, 0, 10, 12, 14, 0, 0, 2, 6, 22]);
+        // An extension pointing to itself.
+        push(&[7, 0, 1, 8, 1, 7, 0, 0]);
+        // An extension pointing to another extension of a single substitution.
+        push(&[7, 0, 1, 8, 1, 7, 0, 8, 1, 1, 0, 8, 1, 6, 1, 1, 1, 1]);
+
+        let gsub = Gsub::read(FontData::new(&data)).unwrap();
+        let mut cache = LookupCache::new();
+        cache.create_for(&gsub, &hb_set_digest_t::full());
+        assert_eq!(cache.stats().vacant_lookups, 2);
+        cache.create_all(&gsub);
+        for i in 0..2 {
+            let entry = cache.get(i).unwrap();
+            assert_eq!(entry.subtables_count, 0);
+            assert_eq!(entry.skipped_subtables, 1);
+            assert_eq!(entry.unsupported_subtables, 0);
+        }
+    }
+
+    #[test]
+    fn corrupted_coverage_offset() {
+        // A single substitution with a coverage of glyph 5. The delta and the
+        // coverage fields starting at byte 4 parse as a coverage of glyph 1.
+        let data: std::vec::Vec<u8> = [1u16, 6, 1, 1, 1, 5]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let mut subtable = SubtableInfo {
+            offset: 0,
+            coverage_offset: 6,
+            is_subst: true,
+            lookup_type: 1,
+            digest: hb_set_digest_t::new(),
+            coverage_cache: CoverageCache::default(),
+        };
+        assert_eq!(subtable.primary_coverage(&data, GlyphId::new(5)), Some(0));
+
+        subtable.coverage_offset = 4;
+        assert!(matches!(
+            subtable.primary_coverage_table(&data),
+            Err(ReadError::OutOfBounds)
+        ));
+        assert_eq!(subtable.primary_coverage(&data, GlyphId::new(1)), None);
+
+        subtable.coverage_offset = 100;
+        assert!(matches!(
+            subtable.primary_coverage_table(&data),
+            Err(ReadError::OutOfBounds)
+        ));
+
+        // A context format 3 with a coverage of glyph 5 after its sequence
+        // lookup record, which parses as a coverage of glyph 1.
+        let data: std::vec::Vec<u8> = [3u16, 1, 1, 12, 1, 1, 1, 1, 5]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let mut subtable = SubtableInfo {
+            offset: 0,
+            coverage_offset: 12,
+            is_subst: true,
+            lookup_type: 5,
+            digest: hb_set_digest_t::new(),
+            coverage_cache: CoverageCache::default(),
+        };
+        assert_eq!(subtable.primary_coverage(&data, GlyphId::new(5)), Some(0));
+
+        subtable.coverage_offset = 8;
+        assert!(matches!(
+            subtable.primary_coverage_table(&data),
+            Err(ReadError::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn coverage_cache() {
+        // A single substitution with a coverage of glyphs 5 and 7.
+        let data: std::vec::Vec<u8> = [1u16, 6, 1, 1, 2, 5, 7]
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect();
+        let subtable = SubtableInfo {
+            offset: 0,
+            coverage_offset: 6,
+            is_subst: true,
+            lookup_type: 1,
+            digest: hb_set_digest_t::new(),
+            coverage_cache: CoverageCache::default(),
+        };
+        let searches = || subtable.coverage_cache.searches.load(Ordering::Relaxed);
+
+        // Repeated queries for a glyph, covered or not, search only once.
+        for (glyph, index, count) in [
+            (5, Some(0), 1),
+            (5, Some(0), 1),
+            (7, Some(1), 2),
+            (7, Some(1), 2),
+            (6, None, 3),
+            (6, None, 3),
+            (5, Some(0), 4),
+        ] {
+            assert_eq!(subtable.primary_coverage(&data, GlyphId::new(glyph)), index);
+            assert_eq!(searches(), count);
+        }
+
+        // Clones keep the cached entry.
+        let clone = subtable.clone();
+        assert_eq!(clone.primary_coverage(&data, GlyphId::new(5)), Some(0));
+        assert_eq!(clone.coverage_cache.searches.load(Ordering::Relaxed), 0);
+    }
+
+    /// Returns the output of shaping `text` as a comparable string.
+    fn shape_to_string(
+        face: &crate::Face,
+        features: &[crate::Feature],
+        text: &str,
+    ) -> std::string::String {
+        let mut buffer = crate::UnicodeBuffer::new();
+        buffer.push_str(text);
+        let glyph_buffer = crate::shape(face, features, buffer);
+        let mut out = std::string::String::new();
+        for (info, pos) in glyph_buffer
+            .glyph_infos()
+            .iter()
+            .zip(glyph_buffer.glyph_positions())
+        {
+            out += &std::format!(
+                "{}={}@{},{}+{},{}|",
+                info.glyph_id,
+                info.cluster,
+                pos.x_offset,
+                pos.y_offset,
+                pos.x_advance,
+                pos.y_advance
+            );
+        }
+        out
+    }
+
+    fn font_paths(dir: &std::path::Path, paths: &mut Vec<std::path::PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                font_paths(&path, paths);
+            } else if path.extension().is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("ttf") || ext.eq_ignore_ascii_case("otf")
+            }) {
+                paths.push(path);
+            }
+        }
+    }
+
+    // Shapes runs of the characters mapped by each test font, with the default
+    // features and with all features of the font enabled, once using the lookup
+    // caches and once using the lookups read directly by ttf-parser.
+    #[test]
+    fn cached_lookups_match_direct_lookups() {
+        const MAX_CHARS: usize = 256;
+        const RUN_LEN: usize = 8;
+
+        let mut paths = Vec::new();
+        font_paths(std::path::Path::new("tests/fonts"), &mut paths);
+        paths.sort();
+        assert!(!paths.is_empty());
+
+        let mut mismatches = Vec::new();
+        for path in &paths {
+            let data = std::fs::read(path).unwrap();
+            let Some(cached) = crate::Face::from_slice(&data, 0) else {
+                continue;
+            };
+            if cached.font.ot.gsub.is_none() && cached.font.ot.gpos.is_none() {
+                continue;
+            }
+            let mut direct = cached.clone();
+            direct.font.ot.gsub = None;
+            direct.font.ot.gpos = None;
+
+            let mut chars = Vec::new();
+            if let Some(cmap) = cached.tables().cmap {
+                for subtable in cmap.subtables {
+                    // Codepoints are listed in increasing order.
+                    let mut count = 0;
+                    if subtable.is_unicode() {
+                        subtable.codepoints(|c| {
+                            if count < MAX_CHARS {
+                                chars.extend(char::from_u32(c));
+                                count += 1;
+                            }
+                        });
+                    }
+                }
+            }
+            chars.sort_unstable();
+            chars.dedup();
+            chars.truncate(MAX_CHARS);
+
+            let mut all_features = Vec::new();
+            for table in [
+                cached.gsub.as_ref().map(|t| &t.inner),
+                cached.gpos.as_ref().map(|t| &t.inner),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                for feature in table.features {
+                    let feature = crate::Feature::new(feature.tag, 1, ..);
+                    if !all_features.contains(&feature) {
+                        all_features.push(feature);
+                    }
+                }
+            }
+
+            for run in chars.chunks(RUN_LEN) {
+                let text: std::string::String = run.iter().collect();
+                for features in [&[][..], &all_features] {
+                    let expected = shape_to_string(&direct, features, &text);
+                    if shape_to_string(&cached, features, &text) != expected {
+                        mismatches.push(std::format!(
+                            "{} {:?} ({} features)",
+                            path.display(),
+                            text,
+                            features.len()
+                        ));
+                    }
+                }
+            }
+        }
+
+        assert!(mismatches.is_empty(), "{:#?}", mismatches);
+    }
+}
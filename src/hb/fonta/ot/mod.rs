@@ -1,11 +1,13 @@
 use crate::hb::{
+    buffer::ShapeDiagnostics,
     ot_layout::LayoutLookup,
     ot_layout_gsubgpos::{Apply, OT::hb_ot_apply_context_t},
-    set_digest::hb_set_digest_ext,
+    set_digest::{hb_set_digest_ext, hb_set_digest_t},
 };
+use alloc::vec::Vec;
 use skrifa::raw::{
-    tables::{gdef::Gdef, variations::ItemVariationStore},
-    TableProvider,
+    tables::{gdef::Gdef, gpos::Gpos, gsub::Gsub, variations::ItemVariationStore},
+    TableProvider, TopLevelTable,
 };
 
 mod contextual;
@@ -15,7 +17,22 @@ mod lookup_cache;
 
 pub use gpos::GposTable;
 pub use gsub::GsubTable;
-pub use lookup_cache::{LookupCache, LookupInfo, Subtable};
+pub use lookup_cache::{LookupCache, LookupCacheStats, LookupHost, LookupInfo, Subtable};
+
+/// Applies a single substitution or positioning to a run of glyphs.
+///
+/// Applying glyph by glyph requires materializing the subtable again
+/// for each of them, while `locl` or `calt` lookups often cover most glyphs
+/// of a run.
+pub trait ApplyRun {
+    /// Applies the subtable to the current glyph and all following glyphs
+    /// that are covered by the subtable and match the lookup.
+    ///
+    /// Must only be used for the first subtable of a lookup that is not
+    /// applied recursively, since the following glyphs could be matched by
+    /// a different subtable otherwise.
+    fn apply_run(&self, ctx: &mut hb_ot_apply_context_t, digest: &hb_set_digest_t) -> Option<()>;
+}
 
 #[derive(Clone)]
 pub struct LayoutTables<'a> {
@@ -33,6 +50,88 @@ impl<'a> LayoutTables<'a> {
         }
     }
 
+    /// Creates the layout tables, building only the lookups that may apply
+    /// to `glyphs`.
+    ///
+    /// The other lookups are left vacant, see [`LookupCache::create_for`].
+    pub fn for_glyphs(font: &impl TableProvider<'a>, glyphs: &hb_set_digest_t) -> Self {
+        let gsub = font.gsub().ok().map(|table| {
+            let mut lookups = LookupCache::new();
+            lookups.create_for(&table, glyphs);
+            GsubTable { table, lookups }
+        });
+        let gpos = font.gpos().ok().map(|table| {
+            let mut lookups = LookupCache::new();
+            lookups.create_for(&table, glyphs);
+            GposTable { table, lookups }
+        });
+
+        Self {
+            gsub,
+            gpos,
+            gdef: font.gdef().ok(),
+        }
+    }
+
+    /// Creates the layout tables using lookup caches written by
+    /// [`write_caches`](Self::write_caches), instead of building them.
+    ///
+    /// Returns `None` if the caches are malformed or were written for
+    /// different GSUB or GPOS tables.
+    pub fn with_caches(font: &impl TableProvider<'a>, caches: &[u8]) -> Option<Self> {
+        let gsub = font.gsub().ok();
+        let gpos = font.gpos().ok();
+
+        let header = caches.get(..LOOKUP_CACHES_HEADER_LEN)?;
+        let mut caches = &caches[LOOKUP_CACHES_HEADER_LEN..];
+        let hash = tables_hash(font);
+        if header[..4] != LOOKUP_CACHES_MAGIC
+            || header[4] != LOOKUP_CACHES_VERSION
+            || header[5..] != hash.to_le_bytes()
+        {
+            return None;
+        }
+
+        let mut read_cache = |host: &dyn LookupHost<'a>| {
+            let lookups = LookupCache::read(&mut caches)?;
+            (lookups.lookups.len() == usize::from(host.lookup_count())).then_some(lookups)
+        };
+        let gsub = match gsub {
+            Some(table) => Some(GsubTable {
+                lookups: read_cache(&table)?,
+                table,
+            }),
+            None => None,
+        };
+        let gpos = match gpos {
+            Some(table) => Some(GposTable {
+                lookups: read_cache(&table)?,
+                table,
+            }),
+            None => None,
+        };
+
+        Some(Self {
+            gsub,
+            gpos,
+            gdef: font.gdef().ok(),
+        })
+    }
+
+    /// Appends the lookup caches of GSUB and GPOS to `out`, together with
+    /// a hash of both tables.
+    pub fn write_caches(&self, font: &impl TableProvider<'a>, out: &mut Vec<u8>) {
+        out.extend_from_slice(&LOOKUP_CACHES_MAGIC);
+        out.push(LOOKUP_CACHES_VERSION);
+        out.extend_from_slice(&tables_hash(font).to_le_bytes());
+        if let Some(gsub) = &self.gsub {
+            gsub.lookups.write(out);
+        }
+        if let Some(gpos) = &self.gpos {
+            gpos.lookups.write(out);
+        }
+    }
+
     pub fn item_variation_store(&self) -> Option<ItemVariationStore<'a>> {
         self.gdef
             .as_ref()
@@ -40,6 +139,31 @@ impl<'a> LayoutTables<'a> {
     }
 }
 
+const LOOKUP_CACHES_MAGIC: [u8; 4] = *b"HZLC";
+const LOOKUP_CACHES_VERSION: u8 = 1;
+const LOOKUP_CACHES_HEADER_LEN: usize = 4 + 1 + 8;
+
+/// Returns the 64-bit FNV-1a hash of the GSUB and GPOS table data.
+fn tables_hash<'a>(font: &impl TableProvider<'a>) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut add = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    for tag in [Gsub::TAG, Gpos::TAG] {
+        match font.data_for_tag(tag) {
+            Some(data) => {
+                add(&(data.len() as u32 + 1).to_le_bytes());
+                add(data.as_bytes());
+            }
+            None => add(&0u32.to_le_bytes()),
+        }
+    }
+    hash
+}
+
 impl LayoutLookup for LookupInfo {
     fn props(&self) -> u32 {
         self.props
@@ -52,6 +176,11 @@ impl LayoutLookup for LookupInfo {
     fn digest(&self) -> &crate::hb::set_digest::hb_set_digest_t {
         &self.digest
     }
+
+    fn diagnose(&self, diagnostics: &mut ShapeDiagnostics) {
+        diagnostics.skipped_subtables += usize::from(self.skipped_subtables);
+        diagnostics.unsupported_subtables += usize::from(self.unsupported_subtables);
+    }
 }
 
 impl Apply for LookupInfo {
@@ -68,26 +197,38 @@ impl Apply for LookupInfo {
             (table.table.offset_data().as_bytes(), &table.lookups)
         };
         let subtables = lookups.subtables(self)?;
-        for subtable_info in subtables {
+        // Glyphs following the current one can only be handled in the same
+        // pass if no other subtable could match them first.
+        let can_apply_run = ctx.nesting_level_left == ctx.buffer.max_nesting_level;
+        for (i, subtable_info) in subtables.iter().enumerate() {
             if !subtable_info.digest.may_have_glyph(glyph) {
                 continue;
             }
-            // if subtable_info
-            //     .primary_coverage(table_data, skrifa::GlyphId::from(glyph.0))
-            //     .is_none()
-            // {
-            //     continue;
-            // }
+            if subtable_info
+                .primary_coverage(table_data, skrifa::GlyphId::from(glyph.0))
+                .is_none()
+            {
+                continue;
+            }
             let Ok(subtable) = subtable_info.materialize(table_data) else {
                 continue;
             };
             let result = match subtable {
+                Subtable::SingleSubst1(subtable) if can_apply_run && i == 0 => {
+                    subtable.apply_run(ctx, &subtable_info.digest)
+                }
+                Subtable::SingleSubst2(subtable) if can_apply_run && i == 0 => {
+                    subtable.apply_run(ctx, &subtable_info.digest)
+                }
                 Subtable::SingleSubst1(subtable) => subtable.apply(ctx),
                 Subtable::SingleSubst2(subtable) => subtable.apply(ctx),
                 Subtable::MultipleSubst1(subtable) => subtable.apply(ctx),
                 Subtable::AlternateSubst1(subtable) => subtable.apply(ctx),
                 Subtable::LigatureSubst1(subtable) => subtable.apply(ctx),
                 Subtable::ReverseChainContext(subtable) => subtable.apply(ctx),
+                Subtable::SinglePos1(subtable) if can_apply_run && i == 0 => {
+                    subtable.apply_run(ctx, &subtable_info.digest)
+                }
                 Subtable::SinglePos1(subtable) => subtable.apply(ctx),
                 Subtable::SinglePos2(subtable) => subtable.apply(ctx),
                 Subtable::PairPos1(subtable) => subtable.apply(ctx),
@@ -96,10 +237,12 @@ impl Apply for LookupInfo {
                 Subtable::MarkBasePos1(subtable) => subtable.apply(ctx),
                 Subtable::MarkLigPos1(subtable) => subtable.apply(ctx),
                 Subtable::MarkMarkPos1(subtable) => subtable.apply(ctx),
+                Subtable::ContextFormat1(subtable) => subtable.apply(ctx),
+                Subtable::ContextFormat2(subtable) => subtable.apply(ctx),
+                Subtable::ContextFormat3(subtable) => subtable.apply(ctx),
                 Subtable::ChainedContextFormat1(subtable) => subtable.apply(ctx),
                 Subtable::ChainedContextFormat2(subtable) => subtable.apply(ctx),
                 Subtable::ChainedContextFormat3(subtable) => subtable.apply(ctx),
-                _ => None,
             };
             if result.is_some() {
                 return Some(());
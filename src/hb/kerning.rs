@@ -92,7 +92,7 @@ fn machine_kern(
         let j = iter.index();
 
         let info = &ctx.buffer.info;
-        let kern = get_kerning(info[i].glyph_id, info[j].glyph_id);
+        let kern = get_kerning(info[i].glyph_id, info[j].glyph_id) * ctx.buffer.position_scale();
 
         let pos = &mut ctx.buffer.pos;
         if kern != 0 {
@@ -284,6 +284,7 @@ fn state_machine_transition(
             // NOT seem to accumulate as otherwise implied by specs.
 
             let mut has_gpos_attachment = false;
+            let scale = buffer.position_scale();
             let glyph_mask = buffer.info[idx].mask;
             let pos = &mut buffer.pos[idx];
 
@@ -296,12 +297,12 @@ fn state_machine_transition(
                         pos.set_attach_chain(0);
                         pos.y_offset = 0;
                     } else if pos.attach_type() != 0 {
-                        pos.y_offset += v;
+                        pos.y_offset += v * scale;
                         has_gpos_attachment = true;
                     }
                 } else if glyph_mask & kern_mask != 0 {
-                    pos.x_advance += v;
-                    pos.x_offset += v;
+                    pos.x_advance += v * scale;
+                    pos.x_offset += v * scale;
                 }
             } else {
                 if has_cross_stream {
@@ -311,13 +312,13 @@ fn state_machine_transition(
                         pos.set_attach_chain(0);
                         pos.x_offset = 0;
                     } else if pos.attach_type() != 0 {
-                        pos.x_offset += v;
+                        pos.x_offset += v * scale;
                         has_gpos_attachment = true;
                     }
                 } else if glyph_mask & kern_mask != 0 {
                     if pos.y_offset == 0 {
-                        pos.y_advance += v;
-                        pos.y_offset += v;
+                        pos.y_advance += v * scale;
+                        pos.y_offset += v * scale;
                     }
                 }
             }
@@ -12,17 +12,20 @@ mod aat_layout_kerx_table;
 mod aat_layout_morx_table;
 mod aat_layout_trak_table;
 mod aat_map;
+pub mod buffer_diff;
+pub mod color;
 pub mod common;
 pub mod face;
 mod kerning;
 mod machine_cursor;
 mod ot;
-mod ot_layout;
+pub mod ot_layout;
 mod ot_layout_common;
 mod ot_layout_gpos_table;
 mod ot_layout_gsub_table;
 mod ot_layout_gsubgpos;
 mod ot_map;
+pub mod ot_math;
 mod ot_shape;
 mod ot_shape_fallback;
 mod ot_shape_normalize;
@@ -30,6 +33,7 @@ pub mod ot_shape_plan;
 mod ot_shaper;
 mod ot_shaper_arabic;
 mod ot_shaper_arabic_table;
+pub mod ot_shaper_custom;
 mod ot_shaper_hangul;
 mod ot_shaper_hebrew;
 mod ot_shaper_indic;
@@ -48,15 +52,17 @@ mod ot_shaper_use_machine;
 mod ot_shaper_use_table;
 mod aat_layout_common;
 mod ot_shaper_vowel_constraints;
+mod ot_var;
 mod paint_extents;
 mod set_digest;
 pub mod shape;
+pub mod shape_simple;
 #[cfg(feature = "wasm-shaper")]
 mod shape_wasm;
 mod tag;
 mod tag_table;
 mod text_parser;
-mod unicode;
+pub mod unicode;
 mod unicode_norm;
 
 use ttf_parser::Tag as hb_tag_t;
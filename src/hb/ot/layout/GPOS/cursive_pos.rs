@@ -1,7 +1,7 @@
 use crate::hb::buffer::HB_BUFFER_SCRATCH_FLAG_HAS_GPOS_ATTACHMENT;
 use crate::hb::ot_layout_common::lookup_flags;
 use crate::hb::ot_layout_gpos_table::attach_type;
-use crate::hb::ot_layout_gpos_table::AnchorExt;
+use crate::hb::ot_layout_gpos_table::{AnchorExt, AnchorRecord};
 use crate::hb::ot_layout_gsubgpos::OT::hb_ot_apply_context_t;
 use crate::hb::ot_layout_gsubgpos::{skipping_iterator_t, Apply};
 use crate::{Direction, GlyphPosition};
@@ -32,8 +32,9 @@ impl Apply for CursiveAdjustment<'_> {
             return None;
         };
 
-        let (exit_x, exit_y) = exit_prev.get(ctx.face);
-        let (entry_x, entry_y) = entry_this.get(ctx.face);
+        let scale = ctx.buffer.position_scale();
+        let (exit_x, exit_y) = exit_prev.get(ctx, prev, AnchorRecord::Exit(index_prev), scale);
+        let (entry_x, entry_y) = entry_this.get(ctx, this, AnchorRecord::Entry(index_this), scale);
 
         let direction = ctx.buffer.direction;
         let j = ctx.buffer.idx;
@@ -1,15 +1,17 @@
 use crate::hb::buffer::HB_BUFFER_SCRATCH_FLAG_HAS_GPOS_ATTACHMENT;
-use crate::hb::ot_layout_gpos_table::{attach_type, AnchorExt};
+use crate::hb::ot_layout_gpos_table::{attach_type, AnchorExt, AnchorRecord};
 use crate::hb::ot_layout_gsubgpos::OT::hb_ot_apply_context_t;
 use ttf_parser::gpos::{AnchorMatrix, MarkArray};
 
 pub(crate) trait MarkArrayExt {
+    /// `glyph_record` locates the anchor of the glyph at `glyph_pos` for a mark class.
     fn apply(
         &self,
         ctx: &mut hb_ot_apply_context_t,
         anchors: AnchorMatrix,
         mark_index: u16,
         glyph_index: u16,
+        glyph_record: impl FnOnce(u16) -> AnchorRecord,
         glyph_pos: usize,
     ) -> Option<()>;
 }
@@ -21,6 +23,7 @@ impl MarkArrayExt for MarkArray<'_> {
         anchors: AnchorMatrix,
         mark_index: u16,
         glyph_index: u16,
+        glyph_record: impl FnOnce(u16) -> AnchorRecord,
         glyph_pos: usize,
     ) -> Option<()> {
         // If this subtable doesn't have an anchor for this base and this class
@@ -28,8 +31,13 @@ impl MarkArrayExt for MarkArray<'_> {
         let (mark_class, mark_anchor) = self.get(mark_index)?;
         let base_anchor = anchors.get(glyph_index, mark_class)?;
 
-        let (mark_x, mark_y) = mark_anchor.get(ctx.face);
-        let (base_x, base_y) = base_anchor.get(ctx.face);
+        let scale = ctx.buffer.position_scale();
+        let mark_glyph = ctx.buffer.cur(0).as_glyph();
+        let base_glyph = ctx.buffer.info[glyph_pos].as_glyph();
+        let mark_record = AnchorRecord::Mark(mark_index);
+        let (mark_x, mark_y) = mark_anchor.get(ctx, mark_glyph, mark_record, scale);
+        let base_record = glyph_record(mark_class);
+        let (base_x, base_y) = base_anchor.get(ctx, base_glyph, base_record, scale);
 
         ctx.buffer
             .unsafe_to_break(Some(glyph_pos), Some(ctx.buffer.idx + 1));
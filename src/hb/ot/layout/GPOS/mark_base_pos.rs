@@ -5,6 +5,7 @@ use crate::hb::ot_layout::{
     _hb_glyph_info_multiplied,
 };
 use crate::hb::ot_layout_common::lookup_flags;
+use crate::hb::ot_layout_gpos_table::AnchorRecord;
 use crate::hb::ot_layout_gsubgpos::OT::hb_ot_apply_context_t;
 use crate::hb::ot_layout_gsubgpos::{match_t, skipping_iterator_t, Apply};
 use ttf_parser::gpos::MarkToBaseAdjustment;
@@ -66,8 +67,18 @@ impl Apply for MarkToBaseAdjustment<'_> {
             return None;
         };
 
-        self.marks
-            .apply(ctx, self.anchors, mark_index, base_index, idx as usize)
+        let base_record = |class| AnchorRecord::Base {
+            base: base_index,
+            class,
+        };
+        self.marks.apply(
+            ctx,
+            self.anchors,
+            mark_index,
+            base_index,
+            base_record,
+            idx as usize,
+        )
     }
 }
 
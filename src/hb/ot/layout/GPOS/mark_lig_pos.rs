@@ -1,6 +1,7 @@
 use crate::hb::ot::layout::GPOS::mark_array::MarkArrayExt;
 use crate::hb::ot_layout::{_hb_glyph_info_get_lig_comp, _hb_glyph_info_get_lig_id};
 use crate::hb::ot_layout_common::lookup_flags;
+use crate::hb::ot_layout_gpos_table::AnchorRecord;
 use crate::hb::ot_layout_gsubgpos::OT::hb_ot_apply_context_t;
 use crate::hb::ot_layout_gsubgpos::{match_t, skipping_iterator_t, Apply};
 use ttf_parser::gpos::MarkToLigatureAdjustment;
@@ -74,7 +75,18 @@ impl Apply for MarkToLigatureAdjustment<'_> {
             comp_count
         } - 1;
 
-        self.marks
-            .apply(ctx, lig_attach, mark_index, comp_index, idx)
+        let component_record = |class| AnchorRecord::Ligature {
+            ligature: lig_index,
+            component: comp_index,
+            class,
+        };
+        self.marks.apply(
+            ctx,
+            lig_attach,
+            mark_index,
+            comp_index,
+            component_record,
+            idx,
+        )
     }
 }
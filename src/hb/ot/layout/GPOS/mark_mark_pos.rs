@@ -3,6 +3,7 @@ use crate::hb::ot_layout::{
     _hb_glyph_info_get_lig_comp, _hb_glyph_info_get_lig_id, _hb_glyph_info_is_mark,
 };
 use crate::hb::ot_layout_common::lookup_flags;
+use crate::hb::ot_layout_gpos_table::AnchorRecord;
 use crate::hb::ot_layout_gsubgpos::OT::hb_ot_apply_context_t;
 use crate::hb::ot_layout_gsubgpos::{skipping_iterator_t, Apply};
 use ttf_parser::gpos::MarkToMarkAdjustment;
@@ -55,7 +56,17 @@ impl Apply for MarkToMarkAdjustment<'_> {
         let mark2_glyph = buffer.info[iter_idx].as_glyph();
         let mark2_index = self.mark2_coverage.get(mark2_glyph)?;
 
-        self.marks
-            .apply(ctx, self.mark2_matrix, mark1_index, mark2_index, iter_idx)
+        let mark2_record = |class| AnchorRecord::Mark2 {
+            mark2: mark2_index,
+            class,
+        };
+        self.marks.apply(
+            ctx,
+            self.mark2_matrix,
+            mark1_index,
+            mark2_index,
+            mark2_record,
+            iter_idx,
+        )
     }
 }
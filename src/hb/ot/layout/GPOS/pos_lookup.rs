@@ -21,7 +21,8 @@ impl LayoutLookup for PositioningLookup<'_> {
 impl Apply for PositioningLookup<'_> {
     fn apply(&self, ctx: &mut hb_ot_apply_context_t) -> Option<()> {
         if self.digest().may_have_glyph(ctx.buffer.cur(0).as_glyph()) {
-            for subtable in &self.subtables {
+            for (index, subtable) in self.subtables.iter().enumerate() {
+                ctx.subtable_index = index as u16;
                 if subtable.apply(ctx).is_some() {
                     return Some(());
                 }
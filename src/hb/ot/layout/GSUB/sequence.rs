@@ -18,7 +18,11 @@ impl Apply for Sequence<'_> {
             // as a "multiplied" substitution.
             1 => ctx.replace_glyph(self.substitutes.get(0)?),
 
-            _ => {
+            n => {
+                if !ctx.buffer.can_expand(usize::from(n)) {
+                    return None;
+                }
+
                 let class = if _hb_glyph_info_is_ligature(ctx.buffer.cur(0)) {
                     GlyphPropsFlags::BASE_GLYPH
                 } else {
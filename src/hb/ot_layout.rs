@@ -52,14 +52,17 @@ pub fn hb_ot_layout_has_glyph_classes(face: &hb_font_t) -> bool {
 
 // get_gsubgpos_table
 
+/// An OpenType layout table.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TableIndex {
+    /// The glyph substitution table.
     GSUB = 0,
+    /// The glyph positioning table.
     GPOS = 1,
 }
 
 impl TableIndex {
-    pub fn iter() -> impl Iterator<Item = TableIndex> {
+    pub(crate) fn iter() -> impl Iterator<Item = TableIndex> {
         [Self::GSUB, Self::GPOS].iter().copied()
     }
 }
@@ -103,6 +106,9 @@ pub trait LayoutLookup: Apply {
 
     /// The digest of the lookup.
     fn digest(&self) -> &hb_set_digest_t;
+
+    /// Records the subtables of the lookup that can't be applied.
+    fn diagnose(&self, _diagnostics: &mut ShapeDiagnostics) {}
 }
 
 pub trait LayoutTableExt {
@@ -276,8 +282,24 @@ pub fn apply_layout_table2<T: LayoutTable, T2: LayoutTable>(
 ) {
     let mut ctx = OT::hb_ot_apply_context_t::new(T::INDEX, face, buffer);
 
+    // Lookups never add feature bits to glyph masks, only pause functions
+    // do, so the union only has to be refreshed between stages.
+    let mut masks_union = ctx.buffer.masks_union();
+
     for (stage_index, stage) in plan.ot_map.stages(T::INDEX).iter().enumerate() {
         for lookup in plan.ot_map.stage_lookups(T::INDEX, stage_index) {
+            // No glyph has the feature of this lookup enabled.
+            if masks_union & lookup.mask == 0 {
+                ctx.buffer.diagnostics.skipped_lookups += 1;
+                continue;
+            }
+
+            // Skip the remaining lookups, but still run the pause functions.
+            if ctx.buffer.is_budget_exhausted() {
+                ctx.buffer.shaping_failed = true;
+                break;
+            }
+
             ctx.lookup_index = lookup.index;
             ctx.set_lookup_mask(lookup.mask);
             ctx.auto_zwj = lookup.auto_zwj;
@@ -288,12 +310,14 @@ pub fn apply_layout_table2<T: LayoutTable, T2: LayoutTable>(
 
             if let Some(table) = &table2 {
                 if let Some(lookup) = table.get_lookup(lookup.index) {
+                    lookup.diagnose(&mut ctx.buffer.diagnostics);
                     apply_string::<T2>(&mut ctx, lookup);
                     continue;
                 }
             }
             if let Some(table) = &table {
                 if let Some(lookup) = table.get_lookup(lookup.index) {
+                    ctx.buffer.diagnostics.fallback_lookups += 1;
                     apply_string::<T>(&mut ctx, lookup);
                 }
             }
@@ -301,6 +325,7 @@ pub fn apply_layout_table2<T: LayoutTable, T2: LayoutTable>(
 
         if let Some(func) = stage.pause_func {
             func(plan, face, ctx.buffer);
+            masks_union = ctx.buffer.masks_union();
         }
     }
 }
@@ -335,6 +360,11 @@ fn apply_string<T: LayoutTable>(ctx: &mut OT::hb_ot_apply_context_t, lookup: &T:
 fn apply_forward(ctx: &mut OT::hb_ot_apply_context_t, lookup: &impl Apply) -> bool {
     let mut ret = false;
     while ctx.buffer.idx < ctx.buffer.len && ctx.buffer.successful {
+        // The rest of the buffer is copied to the output by `sync`.
+        if !ctx.buffer.consume_budget(1) {
+            break;
+        }
+
         let cur = ctx.buffer.cur(0);
         if (cur.mask & ctx.lookup_mask()) != 0
             && ctx.check_glyph_property(cur, ctx.lookup_props)
@@ -351,6 +381,10 @@ fn apply_forward(ctx: &mut OT::hb_ot_apply_context_t, lookup: &impl Apply) -> bo
 fn apply_backward(ctx: &mut OT::hb_ot_apply_context_t, lookup: &impl Apply) -> bool {
     let mut ret = false;
     loop {
+        if !ctx.buffer.consume_budget(1) {
+            break;
+        }
+
         let cur = ctx.buffer.cur(0);
         ret |= (cur.mask & ctx.lookup_mask()) != 0
             && ctx.check_glyph_property(cur, ctx.lookup_props)
@@ -8,8 +8,10 @@ use super::ot_layout_common::{PositioningLookup, PositioningTable};
 use super::ot_layout_gsubgpos::{Apply, OT::hb_ot_apply_context_t};
 use super::ot_shape_plan::hb_ot_shape_plan_t;
 use crate::Direction;
+use skrifa::raw::tables::gpos::{AnchorTable, PositionSubtables};
 use ttf_parser::gpos::*;
 use ttf_parser::opentype_layout::LookupIndex;
+use ttf_parser::GlyphId;
 
 pub fn position(plan: &hb_ot_shape_plan_t, face: &hb_font_t, buffer: &mut hb_buffer_t) {
     //apply_layout_table(plan, face, buffer, face.gpos.as_ref());
@@ -49,26 +51,27 @@ impl ValueRecordExt for ValueRecord<'_> {
 
     fn apply_to_pos(&self, ctx: &mut hb_ot_apply_context_t, pos: &mut GlyphPosition) -> bool {
         let horizontal = ctx.buffer.direction.is_horizontal();
+        let scale = ctx.buffer.position_scale();
         let mut worked = false;
 
         if self.x_placement != 0 {
-            pos.x_offset += i32::from(self.x_placement);
+            pos.x_offset += i32::from(self.x_placement) * scale;
             worked = true;
         }
 
         if self.y_placement != 0 {
-            pos.y_offset += i32::from(self.y_placement);
+            pos.y_offset += i32::from(self.y_placement) * scale;
             worked = true;
         }
 
         if self.x_advance != 0 && horizontal {
-            pos.x_advance += i32::from(self.x_advance);
+            pos.x_advance += i32::from(self.x_advance) * scale;
             worked = true;
         }
 
         if self.y_advance != 0 && !horizontal {
             // y_advance values grow downward but font-space grows upward, hence negation
-            pos.y_advance -= i32::from(self.y_advance);
+            pos.y_advance -= i32::from(self.y_advance) * scale;
             worked = true;
         }
 
@@ -80,21 +83,21 @@ impl ValueRecordExt for ValueRecord<'_> {
 
             if use_x_device {
                 if let Some(device) = self.x_placement_device {
-                    pos.x_offset += device.get_x_delta(ctx.face).unwrap_or(0);
+                    pos.x_offset += device.get_x_delta(ctx.face, scale).unwrap_or(0);
                     worked = true; // TODO: even when 0?
                 }
             }
 
             if use_y_device {
                 if let Some(device) = self.y_placement_device {
-                    pos.y_offset += device.get_y_delta(ctx.face).unwrap_or(0);
+                    pos.y_offset += device.get_y_delta(ctx.face, scale).unwrap_or(0);
                     worked = true;
                 }
             }
 
             if horizontal && use_x_device {
                 if let Some(device) = self.x_advance_device {
-                    pos.x_advance += device.get_x_delta(ctx.face).unwrap_or(0);
+                    pos.x_advance += device.get_x_delta(ctx.face, scale).unwrap_or(0);
                     worked = true;
                 }
             }
@@ -102,7 +105,7 @@ impl ValueRecordExt for ValueRecord<'_> {
             if !horizontal && use_y_device {
                 if let Some(device) = self.y_advance_device {
                     // y_advance values grow downward but face-space grows upward, hence negation
-                    pos.y_advance -= device.get_y_delta(ctx.face).unwrap_or(0);
+                    pos.y_advance -= device.get_y_delta(ctx.face, scale).unwrap_or(0);
                     worked = true;
                 }
             }
@@ -112,14 +115,137 @@ impl ValueRecordExt for ValueRecord<'_> {
     }
 }
 
+/// Locates an anchor in the subtable of `GPOS` being applied.
+#[derive(Clone, Copy)]
+pub(crate) enum AnchorRecord {
+    /// The anchor of a mark.
+    Mark(u16),
+    /// The anchor of a base for a mark class.
+    Base { base: u16, class: u16 },
+    /// The anchor of a ligature component for a mark class.
+    Ligature {
+        ligature: u16,
+        component: u16,
+        class: u16,
+    },
+    /// The anchor of a base mark for a mark class.
+    Mark2 { mark2: u16, class: u16 },
+    /// The entry anchor of a cursive attachment.
+    Entry(u16),
+    /// The exit anchor of a cursive attachment.
+    Exit(u16),
+}
+
+impl AnchorRecord {
+    /// Reads the anchor with skrifa, which, unlike ttf-parser, keeps the contour
+    /// point of format 2 anchors.
+    fn read<'b>(self, ctx: &hb_ot_apply_context_t<'_, 'b>) -> Option<AnchorTable<'b>> {
+        let gpos = &ctx.face.font.ot.gpos.as_ref()?.table;
+        let lookup = gpos
+            .lookup_list()
+            .ok()?
+            .lookups()
+            .get(usize::from(ctx.lookup_index))
+            .ok()?;
+        let subtable = usize::from(ctx.subtable_index);
+
+        let anchor = match (lookup.subtables().ok()?, self) {
+            (PositionSubtables::Cursive(subtables), Self::Entry(index)) => {
+                let subtable = subtables.get(subtable).ok()?;
+                let record = subtable.entry_exit_record().get(usize::from(index))?;
+                record.entry_anchor(subtable.offset_data())?
+            }
+            (PositionSubtables::Cursive(subtables), Self::Exit(index)) => {
+                let subtable = subtables.get(subtable).ok()?;
+                let record = subtable.entry_exit_record().get(usize::from(index))?;
+                record.exit_anchor(subtable.offset_data())?
+            }
+            (PositionSubtables::MarkToBase(subtables), Self::Mark(index)) => {
+                mark_anchor(subtables.get(subtable).ok()?.mark_array().ok()?, index)?
+            }
+            (PositionSubtables::MarkToBase(subtables), Self::Base { base, class }) => {
+                let array = subtables.get(subtable).ok()?.base_array().ok()?;
+                let record = array.base_records().get(usize::from(base)).ok()?;
+                record
+                    .base_anchors(array.offset_data())
+                    .get(usize::from(class))?
+            }
+            (PositionSubtables::MarkToLig(subtables), Self::Mark(index)) => {
+                mark_anchor(subtables.get(subtable).ok()?.mark_array().ok()?, index)?
+            }
+            (
+                PositionSubtables::MarkToLig(subtables),
+                Self::Ligature {
+                    ligature,
+                    component,
+                    class,
+                },
+            ) => {
+                let array = subtables.get(subtable).ok()?.ligature_array().ok()?;
+                let attach = array.ligature_attaches().get(usize::from(ligature)).ok()?;
+                let record = attach
+                    .component_records()
+                    .get(usize::from(component))
+                    .ok()?;
+                record
+                    .ligature_anchors(attach.offset_data())
+                    .get(usize::from(class))?
+            }
+            (PositionSubtables::MarkToMark(subtables), Self::Mark(index)) => {
+                mark_anchor(subtables.get(subtable).ok()?.mark1_array().ok()?, index)?
+            }
+            (PositionSubtables::MarkToMark(subtables), Self::Mark2 { mark2, class }) => {
+                let array = subtables.get(subtable).ok()?.mark2_array().ok()?;
+                let record = array.mark2_records().get(usize::from(mark2)).ok()?;
+                record
+                    .mark2_anchors(array.offset_data())
+                    .get(usize::from(class))?
+            }
+            _ => return None,
+        };
+
+        anchor.ok()
+    }
+}
+
+fn mark_anchor(
+    array: skrifa::raw::tables::gpos::MarkArray,
+    index: u16,
+) -> Option<Result<AnchorTable, skrifa::raw::ReadError>> {
+    let record = array.mark_records().get(usize::from(index))?;
+    Some(record.mark_anchor(array.offset_data()))
+}
+
 pub(crate) trait AnchorExt {
-    fn get(&self, face: &hb_font_t) -> (i32, i32);
+    /// Returns the anchor of a glyph, in font units multiplied by `scale`.
+    fn get(
+        &self,
+        ctx: &mut hb_ot_apply_context_t,
+        glyph: GlyphId,
+        record: AnchorRecord,
+        scale: i32,
+    ) -> (i32, i32);
 }
 
 impl AnchorExt for Anchor<'_> {
-    fn get(&self, face: &hb_font_t) -> (i32, i32) {
-        let mut x = i32::from(self.x);
-        let mut y = i32::from(self.y);
+    fn get(
+        &self,
+        ctx: &mut hb_ot_apply_context_t,
+        glyph: GlyphId,
+        record: AnchorRecord,
+        scale: i32,
+    ) -> (i32, i32) {
+        let face = ctx.face;
+
+        // Contour points are only used with a ppem set.
+        if face.pixels_per_em().is_some() {
+            if let Some(anchor @ AnchorTable::Format2(_)) = record.read(ctx) {
+                return face.resolve_anchor(&anchor, glyph, scale, &mut ctx.contour_points);
+            }
+        }
+
+        let mut x = i32::from(self.x) * scale;
+        let mut y = i32::from(self.y) * scale;
 
         if self.x_device.is_some() || self.y_device.is_some() {
             let (ppem_x, ppem_y) = face.pixels_per_em().unwrap_or((0, 0));
@@ -127,13 +253,13 @@ impl AnchorExt for Anchor<'_> {
 
             if let Some(device) = self.x_device {
                 if ppem_x != 0 || coords != 0 {
-                    x += device.get_x_delta(face).unwrap_or(0);
+                    x += device.get_x_delta(face, scale).unwrap_or(0);
                 }
             }
 
             if let Some(device) = self.y_device {
                 if ppem_y != 0 || coords != 0 {
-                    y += device.get_y_delta(face).unwrap_or(0);
+                    y += device.get_y_delta(face, scale).unwrap_or(0);
                 }
             }
         }
@@ -187,39 +313,41 @@ impl TryNumFrom<f32> for i32 {
 }
 
 pub(crate) trait DeviceExt {
-    fn get_x_delta(&self, face: &hb_font_t) -> Option<i32>;
-    fn get_y_delta(&self, face: &hb_font_t) -> Option<i32>;
+    /// Returns the X-axis delta, in font units multiplied by `scale`.
+    fn get_x_delta(&self, face: &hb_font_t, scale: i32) -> Option<i32>;
+    /// Returns the Y-axis delta, in font units multiplied by `scale`.
+    fn get_y_delta(&self, face: &hb_font_t, scale: i32) -> Option<i32>;
 }
 
 impl DeviceExt for Device<'_> {
-    fn get_x_delta(&self, face: &hb_font_t) -> Option<i32> {
-        match self {
-            Device::Hinting(hinting) => hinting.x_delta(face.units_per_em, face.pixels_per_em()),
-            Device::Variation(variation) => face
-                .tables()
-                .gdef?
-                .glyph_variation_delta(
-                    variation.outer_index,
-                    variation.inner_index,
-                    face.variation_coordinates(),
-                )
-                .and_then(|float| i32::try_num_from(float.round())),
-        }
+    fn get_x_delta(&self, face: &hb_font_t, scale: i32) -> Option<i32> {
+        device_delta(self, face, face.pixels_per_em().map(|(x, _)| x), scale)
     }
 
-    fn get_y_delta(&self, face: &hb_font_t) -> Option<i32> {
-        match self {
-            Device::Hinting(hinting) => hinting.y_delta(face.units_per_em, face.pixels_per_em()),
-            Device::Variation(variation) => face
-                .tables()
-                .gdef?
-                .glyph_variation_delta(
-                    variation.outer_index,
-                    variation.inner_index,
-                    face.variation_coordinates(),
-                )
-                .and_then(|float| i32::try_num_from(float.round())),
+    fn get_y_delta(&self, face: &hb_font_t, scale: i32) -> Option<i32> {
+        device_delta(self, face, face.pixels_per_em().map(|(_, y)| y), scale)
+    }
+}
+
+fn device_delta(device: &Device, face: &hb_font_t, ppem: Option<u16>, scale: i32) -> Option<i32> {
+    match device {
+        Device::Hinting(hinting) => {
+            let ppem = ppem?;
+            // A delta scaled to the ppem itself is the unscaled number of pixels,
+            // which we scale to the font units ourselves to keep the fraction.
+            let pixels = hinting.x_delta(ppem, Some((ppem, ppem)))?;
+            let delta = i64::from(pixels) * i64::from(face.units_per_em) * i64::from(scale);
+            i32::try_from(delta / i64::from(ppem)).ok()
         }
+        Device::Variation(variation) => face
+            .tables()
+            .gdef?
+            .glyph_variation_delta(
+                variation.outer_index,
+                variation.inner_index,
+                face.variation_coordinates(),
+            )
+            .and_then(|float| i32::try_num_from((float * scale as f32).round())),
     }
 }
 
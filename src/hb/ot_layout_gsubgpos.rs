@@ -5,6 +5,7 @@ use ttf_parser::{GlyphId, LazyArray16};
 
 use super::buffer::hb_glyph_info_t;
 use super::buffer::{hb_buffer_t, GlyphPropsFlags};
+use super::face::ContourPointCache;
 use super::hb_font_t;
 use super::hb_mask_t;
 use super::ot_layout::LayoutTable;
@@ -889,6 +890,7 @@ pub(super) fn apply_lookup(
         }
 
         if ctx.buffer.max_ops <= 0 {
+            ctx.buffer.shaping_failed = true;
             break;
         }
 
@@ -1010,6 +1012,8 @@ pub mod OT {
         lookup_mask: hb_mask_t,
         pub per_syllable: bool,
         pub lookup_index: LookupIndex,
+        /// The subtable of the current lookup being applied by ttf-parser.
+        pub subtable_index: u16,
         pub lookup_props: u32,
         pub nesting_level_left: usize,
         pub auto_zwnj: bool,
@@ -1019,6 +1023,7 @@ pub mod OT {
         pub last_base: i32,
         pub last_base_until: u32,
         pub digest: hb_set_digest_t,
+        pub contour_points: ContourPointCache,
     }
 
     impl<'a, 'b> hb_ot_apply_context_t<'a, 'b> {
@@ -1028,6 +1033,7 @@ pub mod OT {
             buffer: &'a mut hb_buffer_t,
         ) -> Self {
             let buffer_digest = buffer.digest();
+            let nesting_level_left = buffer.max_nesting_level;
             Self {
                 table_index,
                 face,
@@ -1035,8 +1041,9 @@ pub mod OT {
                 lookup_mask: 1,
                 per_syllable: false,
                 lookup_index: u16::MAX,
+                subtable_index: 0,
                 lookup_props: 0,
-                nesting_level_left: MAX_NESTING_LEVEL,
+                nesting_level_left,
                 auto_zwnj: true,
                 auto_zwj: true,
                 random: false,
@@ -1044,6 +1051,7 @@ pub mod OT {
                 last_base: -1,
                 last_base_until: 0,
                 digest: buffer_digest,
+                contour_points: ContourPointCache::default(),
             }
         }
 
@@ -1078,6 +1086,7 @@ pub mod OT {
             self.nesting_level_left -= 1;
             let saved_props = self.lookup_props;
             let saved_index = self.lookup_index;
+            let saved_subtable_index = self.subtable_index;
 
             self.lookup_index = sub_lookup_index;
             let applied = match self.table_index {
@@ -1129,6 +1138,7 @@ pub mod OT {
 
             self.lookup_props = saved_props;
             self.lookup_index = saved_index;
+            self.subtable_index = saved_subtable_index;
             self.nesting_level_left += 1;
             applied
         }
@@ -1206,7 +1216,7 @@ pub mod OT {
                 .gdef
                 .map_or(false, |table| table.has_glyph_classes());
 
-            if has_glyph_classes {
+            if has_glyph_classes || self.face.glyph_class_override(glyph_id).is_some() {
                 props &= GlyphPropsFlags::PRESERVE.bits();
                 cur.set_glyph_props(props | self.face.glyph_props(glyph_id));
             } else if !class_guess.is_empty() {
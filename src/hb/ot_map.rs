@@ -135,6 +135,11 @@ impl hb_ot_map_t {
         &self.stages[table_index]
     }
 
+    #[inline]
+    pub fn lookups(&self, table_index: TableIndex) -> &[lookup_map_t] {
+        &self.lookups[table_index]
+    }
+
     #[inline]
     pub fn lookup(&self, table_index: TableIndex, index: usize) -> &lookup_map_t {
         &self.lookups[table_index][index]
@@ -311,6 +316,28 @@ impl<'a> hb_ot_map_builder_t<'a> {
         self.current_stage[table_index] += 1;
     }
 
+    /// Returns the tags of the features enabled for at least some glyphs, in the
+    /// order they were first added.
+    ///
+    /// Features added more than once are merged like in `compile`.
+    #[cfg(test)]
+    pub(crate) fn enabled_features(&self) -> Vec<hb_tag_t> {
+        let mut features: Vec<(hb_tag_t, u32)> = Vec::new();
+        for info in &self.feature_infos {
+            match features.iter_mut().find(|(tag, _)| *tag == info.tag) {
+                Some((_, max_value)) if info.flags & F_GLOBAL != 0 => *max_value = info.max_value,
+                Some((_, max_value)) => *max_value = (*max_value).max(info.max_value),
+                None => features.push((info.tag, info.max_value)),
+            }
+        }
+
+        features
+            .into_iter()
+            .filter(|&(_, max_value)| max_value != 0)
+            .map(|(tag, _)| tag)
+            .collect()
+    }
+
     pub fn compile(&mut self) -> hb_ot_map_t {
         // We default to applying required feature in stage 0.  If the required
         // feature has a tag that is known to the shaper, we apply required feature
@@ -491,7 +518,7 @@ impl<'a> hb_ot_map_builder_t<'a> {
             let mut stage_index = 0;
             let mut last_lookup = 0;
 
-            let coords = self.face.ttfp_face.variation_coordinates();
+            let coords = self.face.variation_coordinates();
             let variation_index = self
                 .face
                 .layout_table(table_index)
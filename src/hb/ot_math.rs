@@ -0,0 +1,289 @@
+//! Access to the OpenType MATH table.
+
+use alloc::vec::Vec;
+
+use ttf_parser::math::{GlyphConstruction, MathValue};
+use ttf_parser::GlyphId;
+
+use super::hb_font_t;
+use super::ot_layout_gpos_table::DeviceExt;
+use crate::Direction;
+
+/// A math layout constant of the MATH table.
+///
+/// The variants follow the order of the `MathConstants` table, like
+/// `hb_ot_math_constant_t` in HarfBuzz.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MathConstant {
+    ScriptPercentScaleDown,
+    ScriptScriptPercentScaleDown,
+    DelimitedSubFormulaMinHeight,
+    DisplayOperatorMinHeight,
+    MathLeading,
+    AxisHeight,
+    AccentBaseHeight,
+    FlattenedAccentBaseHeight,
+    SubscriptShiftDown,
+    SubscriptTopMax,
+    SubscriptBaselineDropMin,
+    SuperscriptShiftUp,
+    SuperscriptShiftUpCramped,
+    SuperscriptBottomMin,
+    SuperscriptBaselineDropMax,
+    SubSuperscriptGapMin,
+    SuperscriptBottomMaxWithSubscript,
+    SpaceAfterScript,
+    UpperLimitGapMin,
+    UpperLimitBaselineRiseMin,
+    LowerLimitGapMin,
+    LowerLimitBaselineDropMin,
+    StackTopShiftUp,
+    StackTopDisplayStyleShiftUp,
+    StackBottomShiftDown,
+    StackBottomDisplayStyleShiftDown,
+    StackGapMin,
+    StackDisplayStyleGapMin,
+    StretchStackTopShiftUp,
+    StretchStackBottomShiftDown,
+    StretchStackGapAboveMin,
+    StretchStackGapBelowMin,
+    FractionNumeratorShiftUp,
+    FractionNumeratorDisplayStyleShiftUp,
+    FractionDenominatorShiftDown,
+    FractionDenominatorDisplayStyleShiftDown,
+    FractionNumeratorGapMin,
+    FractionNumDisplayStyleGapMin,
+    FractionRuleThickness,
+    FractionDenominatorGapMin,
+    FractionDenomDisplayStyleGapMin,
+    SkewedFractionHorizontalGap,
+    SkewedFractionVerticalGap,
+    OverbarVerticalGap,
+    OverbarRuleThickness,
+    OverbarExtraAscender,
+    UnderbarVerticalGap,
+    UnderbarRuleThickness,
+    UnderbarExtraDescender,
+    RadicalVerticalGap,
+    RadicalDisplayStyleVerticalGap,
+    RadicalRuleThickness,
+    RadicalExtraAscender,
+    RadicalKernBeforeDegree,
+    RadicalKernAfterDegree,
+    RadicalDegreeBottomRaisePercent,
+}
+
+/// A size variant of a glyph.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MathGlyphVariant {
+    /// The variant glyph.
+    pub glyph: GlyphId,
+    /// The advance of the variant in the direction of stretching, in font units.
+    pub advance: u16,
+}
+
+/// A part of a glyph assembly.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MathGlyphPart {
+    /// The glyph of the part.
+    pub glyph: GlyphId,
+    /// The length of the connector at the start of the part, in font units.
+    pub start_connector_length: u16,
+    /// The length of the connector at the end of the part, in font units.
+    pub end_connector_length: u16,
+    /// The advance of the part in the direction of stretching, in font units.
+    pub full_advance: u16,
+    /// Whether the part can be skipped or repeated.
+    pub extender: bool,
+}
+
+/// A recipe for building a glyph of arbitrary size from parts.
+///
+/// Parts are listed from bottom to top or from left to right. Adjacent parts
+/// overlap by at least [`Face::math_min_connector_overlap`](crate::Face::math_min_connector_overlap)
+/// and by at most the shorter of their connectors.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MathGlyphAssembly {
+    /// The parts of the assembly.
+    pub parts: Vec<MathGlyphPart>,
+    /// The italic correction of the assembled glyph, in font units.
+    pub italics_correction: i32,
+}
+
+pub(crate) fn hb_ot_math_get_constant(face: &hb_font_t, constant: MathConstant) -> Option<i32> {
+    use MathConstant::*;
+
+    let constants = face.tables().math?.constants?;
+    // Percentages and minimum heights have no device table, the other values are
+    // horizontal or vertical depending on the constant.
+    let (value, horizontal) = match constant {
+        ScriptPercentScaleDown => return Some(constants.script_percent_scale_down().into()),
+        ScriptScriptPercentScaleDown => {
+            return Some(constants.script_script_percent_scale_down().into())
+        }
+        DelimitedSubFormulaMinHeight => {
+            return Some(constants.delimited_sub_formula_min_height().into())
+        }
+        DisplayOperatorMinHeight => return Some(constants.display_operator_min_height().into()),
+        RadicalDegreeBottomRaisePercent => {
+            return Some(constants.radical_degree_bottom_raise_percent().into())
+        }
+        MathLeading => (constants.math_leading(), false),
+        AxisHeight => (constants.axis_height(), false),
+        AccentBaseHeight => (constants.accent_base_height(), false),
+        FlattenedAccentBaseHeight => (constants.flattened_accent_base_height(), false),
+        SubscriptShiftDown => (constants.subscript_shift_down(), false),
+        SubscriptTopMax => (constants.subscript_top_max(), false),
+        SubscriptBaselineDropMin => (constants.subscript_baseline_drop_min(), false),
+        SuperscriptShiftUp => (constants.superscript_shift_up(), false),
+        SuperscriptShiftUpCramped => (constants.superscript_shift_up_cramped(), false),
+        SuperscriptBottomMin => (constants.superscript_bottom_min(), false),
+        SuperscriptBaselineDropMax => (constants.superscript_baseline_drop_max(), false),
+        SubSuperscriptGapMin => (constants.sub_superscript_gap_min(), false),
+        SuperscriptBottomMaxWithSubscript => {
+            (constants.superscript_bottom_max_with_subscript(), false)
+        }
+        SpaceAfterScript => (constants.space_after_script(), true),
+        UpperLimitGapMin => (constants.upper_limit_gap_min(), false),
+        UpperLimitBaselineRiseMin => (constants.upper_limit_baseline_rise_min(), false),
+        LowerLimitGapMin => (constants.lower_limit_gap_min(), false),
+        LowerLimitBaselineDropMin => (constants.lower_limit_baseline_drop_min(), false),
+        StackTopShiftUp => (constants.stack_top_shift_up(), false),
+        StackTopDisplayStyleShiftUp => (constants.stack_top_display_style_shift_up(), false),
+        StackBottomShiftDown => (constants.stack_bottom_shift_down(), false),
+        StackBottomDisplayStyleShiftDown => {
+            (constants.stack_bottom_display_style_shift_down(), false)
+        }
+        StackGapMin => (constants.stack_gap_min(), false),
+        StackDisplayStyleGapMin => (constants.stack_display_style_gap_min(), false),
+        StretchStackTopShiftUp => (constants.stretch_stack_top_shift_up(), false),
+        StretchStackBottomShiftDown => (constants.stretch_stack_bottom_shift_down(), false),
+        StretchStackGapAboveMin => (constants.stretch_stack_gap_above_min(), false),
+        StretchStackGapBelowMin => (constants.stretch_stack_gap_below_min(), false),
+        FractionNumeratorShiftUp => (constants.fraction_numerator_shift_up(), false),
+        FractionNumeratorDisplayStyleShiftUp => {
+            (constants.fraction_numerator_display_style_shift_up(), false)
+        }
+        FractionDenominatorShiftDown => (constants.fraction_denominator_shift_down(), false),
+        FractionDenominatorDisplayStyleShiftDown => (
+            constants.fraction_denominator_display_style_shift_down(),
+            false,
+        ),
+        FractionNumeratorGapMin => (constants.fraction_numerator_gap_min(), false),
+        FractionNumDisplayStyleGapMin => (constants.fraction_num_display_style_gap_min(), false),
+        FractionRuleThickness => (constants.fraction_rule_thickness(), false),
+        FractionDenominatorGapMin => (constants.fraction_denominator_gap_min(), false),
+        FractionDenomDisplayStyleGapMin => {
+            (constants.fraction_denom_display_style_gap_min(), false)
+        }
+        SkewedFractionHorizontalGap => (constants.skewed_fraction_horizontal_gap(), true),
+        SkewedFractionVerticalGap => (constants.skewed_fraction_vertical_gap(), false),
+        OverbarVerticalGap => (constants.overbar_vertical_gap(), false),
+        OverbarRuleThickness => (constants.overbar_rule_thickness(), false),
+        OverbarExtraAscender => (constants.overbar_extra_ascender(), false),
+        UnderbarVerticalGap => (constants.underbar_vertical_gap(), false),
+        UnderbarRuleThickness => (constants.underbar_rule_thickness(), false),
+        UnderbarExtraDescender => (constants.underbar_extra_descender(), false),
+        RadicalVerticalGap => (constants.radical_vertical_gap(), false),
+        RadicalDisplayStyleVerticalGap => (constants.radical_display_style_vertical_gap(), false),
+        RadicalRuleThickness => (constants.radical_rule_thickness(), false),
+        RadicalExtraAscender => (constants.radical_extra_ascender(), false),
+        RadicalKernBeforeDegree => (constants.radical_kern_before_degree(), true),
+        RadicalKernAfterDegree => (constants.radical_kern_after_degree(), true),
+    };
+
+    Some(resolve_value(face, value, horizontal))
+}
+
+pub(crate) fn hb_ot_math_get_glyph_italics_correction(
+    face: &hb_font_t,
+    glyph: GlyphId,
+) -> Option<i32> {
+    let value = face
+        .tables()
+        .math?
+        .glyph_info?
+        .italic_corrections?
+        .get(glyph)?;
+    Some(resolve_value(face, value, true))
+}
+
+pub(crate) fn hb_ot_math_get_glyph_top_accent_attachment(
+    face: &hb_font_t,
+    glyph: GlyphId,
+) -> Option<i32> {
+    let value = face
+        .tables()
+        .math?
+        .glyph_info?
+        .top_accent_attachments?
+        .get(glyph)?;
+    Some(resolve_value(face, value, true))
+}
+
+/// Adds the device delta to a math value, horizontal values using the X-axis delta.
+fn resolve_value(face: &hb_font_t, value: MathValue, horizontal: bool) -> i32 {
+    let delta = value.device.and_then(|device| match horizontal {
+        true => device.get_x_delta(face, 1),
+        false => device.get_y_delta(face, 1),
+    });
+    i32::from(value.value) + delta.unwrap_or(0)
+}
+
+pub(crate) fn hb_ot_math_get_min_connector_overlap(face: &hb_font_t) -> Option<u16> {
+    Some(face.tables().math?.variants?.min_connector_overlap)
+}
+
+pub(crate) fn hb_ot_math_get_glyph_variants<'a>(
+    face: &'a hb_font_t,
+    glyph: GlyphId,
+    direction: Direction,
+) -> impl Iterator<Item = MathGlyphVariant> + 'a {
+    glyph_construction(face, glyph, direction)
+        .into_iter()
+        .flat_map(|construction| construction.variants)
+        .map(|variant| MathGlyphVariant {
+            glyph: variant.variant_glyph,
+            advance: variant.advance_measurement,
+        })
+}
+
+pub(crate) fn hb_ot_math_get_glyph_assembly(
+    face: &hb_font_t,
+    glyph: GlyphId,
+    direction: Direction,
+) -> Option<MathGlyphAssembly> {
+    let assembly = glyph_construction(face, glyph, direction)?.assembly?;
+    let parts = assembly
+        .parts
+        .into_iter()
+        .map(|part| MathGlyphPart {
+            glyph: part.glyph_id,
+            start_connector_length: part.start_connector_length,
+            end_connector_length: part.end_connector_length,
+            full_advance: part.full_advance,
+            extender: part.part_flags.extender(),
+        })
+        .collect();
+
+    Some(MathGlyphAssembly {
+        parts,
+        italics_correction: resolve_value(face, assembly.italics_correction, true),
+    })
+}
+
+/// Returns the construction of a glyph growing in the given direction.
+///
+/// Like in HarfBuzz, invalid directions are treated as vertical.
+fn glyph_construction<'a>(
+    face: &hb_font_t<'a>,
+    glyph: GlyphId,
+    direction: Direction,
+) -> Option<GlyphConstruction<'a>> {
+    let variants = face.tables().math?.variants?;
+    match direction.is_horizontal() {
+        true => variants.horizontal_constructions.get(glyph),
+        false => variants.vertical_constructions.get(glyph),
+    }
+}
@@ -4,7 +4,12 @@ use super::ot_layout_gpos_table::GPOS;
 use super::ot_map::*;
 use super::ot_shape_plan::hb_ot_shape_plan_t;
 use super::ot_shaper::*;
-use super::unicode::{hb_unicode_general_category_t, CharExt, GeneralCategoryExt};
+use super::ot_shaper_custom::{
+    with_glyph_buffer, with_unicode_buffer, ComplexShaper, ShapePlanner, ShaperBuffer,
+};
+use super::unicode::{
+    hb_unicode_funcs_get, hb_unicode_general_category_t, CharExt, GeneralCategoryExt,
+};
 use super::*;
 use super::{hb_font_t, hb_tag_t};
 use crate::hb::aat_layout::hb_aat_layout_remove_deleted_glyphs;
@@ -17,16 +22,19 @@ use crate::hb::unicode::hb_gc::{
 };
 use crate::BufferFlags;
 use crate::{Direction, Feature, Language, Script};
+use alloc::sync::Arc;
 
 pub struct hb_ot_shape_planner_t<'a> {
     pub face: &'a hb_font_t<'a>,
     pub direction: Direction,
     pub script: Option<Script>,
+    pub language: Option<Language>,
     pub ot_map: hb_ot_map_builder_t<'a>,
     pub apply_morx: bool,
     pub script_zero_marks: bool,
     pub script_fallback_mark_positioning: bool,
     pub shaper: &'static hb_ot_shaper_t,
+    pub custom_shaper: Option<Arc<dyn ComplexShaper>>,
 }
 
 impl<'a> hb_ot_shape_planner_t<'a> {
@@ -38,7 +46,12 @@ impl<'a> hb_ot_shape_planner_t<'a> {
     ) -> Self {
         let ot_map = hb_ot_map_builder_t::new(face, script, language);
 
+        // A shaper registered by the user takes precedence over the built-in one.
+        // The default shaper still provides normalization and mark handling.
+        let custom_shaper = script.and_then(|script| face.custom_shaper(script).cloned());
+
         let mut shaper = match script {
+            _ if custom_shaper.is_some() => &DEFAULT_SHAPER,
             Some(script) => hb_ot_shape_complex_categorize(
                 script,
                 direction,
@@ -55,7 +68,7 @@ impl<'a> hb_ot_shape_planner_t<'a> {
             face.tables().morx.is_some() && (direction.is_horizontal() || face.gsub.is_none());
 
         // https://github.com/harfbuzz/harfbuzz/issues/1528
-        if apply_morx && shaper as *const _ != &DEFAULT_SHAPER as *const _ {
+        if apply_morx && !core::ptr::eq(shaper, &DEFAULT_SHAPER) {
             shaper = &DUMBER_SHAPER;
         }
 
@@ -63,11 +76,13 @@ impl<'a> hb_ot_shape_planner_t<'a> {
             face,
             direction,
             script,
+            language: language.cloned(),
             ot_map,
             apply_morx,
             script_zero_marks,
             script_fallback_mark_positioning,
             shaper,
+            custom_shaper,
         }
     }
 
@@ -147,6 +162,11 @@ impl<'a> hb_ot_shape_planner_t<'a> {
             func(self);
         }
 
+        if let Some(shaper) = self.custom_shaper.clone() {
+            self.ot_map.is_simple = false;
+            shaper.collect_features(&mut ShapePlanner::new(self));
+        }
+
         self.ot_map
             .enable_feature(hb_tag_t::from_bytes(b"Buzz"), empty, 1); // Considered required.
         self.ot_map
@@ -185,6 +205,10 @@ impl<'a> hb_ot_shape_planner_t<'a> {
         if let Some(func) = self.shaper.override_features {
             func(self);
         }
+
+        if let Some(shaper) = self.custom_shaper.clone() {
+            shaper.override_features(&mut ShapePlanner::new(self));
+        }
     }
 
     pub fn compile(mut self, user_features: &[Feature]) -> hb_ot_shape_plan_t {
@@ -273,7 +297,10 @@ impl<'a> hb_ot_shape_planner_t<'a> {
         let mut plan = hb_ot_shape_plan_t {
             direction: self.direction,
             script: self.script,
+            language: self.language,
+            coords: self.face.variation_coordinates().to_vec(),
             shaper: self.shaper,
+            custom_shaper: self.custom_shaper,
             ot_map,
             data: None,
             frac_mask,
@@ -313,6 +340,8 @@ pub struct hb_ot_shape_context_t<'a> {
     pub buffer: &'a mut hb_buffer_t,
     // Transient stuff
     pub target_direction: Direction,
+    /// Stop after substitution, leaving all positions zeroed.
+    pub substitute_only: bool,
 }
 
 // Pull it all together!
@@ -322,6 +351,7 @@ pub fn shape_internal(ctx: &mut hb_ot_shape_context_t) {
     initialize_masks(ctx);
     set_unicode_props(ctx.buffer);
     insert_dotted_circle(ctx.buffer, ctx.face);
+    record_grapheme_starts(ctx.buffer);
 
     form_clusters(ctx.buffer);
 
@@ -331,9 +361,27 @@ pub fn shape_internal(ctx: &mut hb_ot_shape_context_t) {
         func(ctx.plan, ctx.face, ctx.buffer);
     }
 
+    if let Some(shaper) = &ctx.plan.custom_shaper {
+        with_unicode_buffer(ctx.buffer, |buffer| {
+            shaper.preprocess_text(ctx.plan, ctx.face, buffer)
+        });
+    }
+
     substitute_pre(ctx);
-    position(ctx);
-    substitute_post(ctx);
+
+    if ctx.substitute_only {
+        ctx.buffer.clear_positions();
+
+        if ctx.buffer.direction.is_backward() {
+            ctx.buffer.reverse();
+        }
+
+        // The shaper post-processing may depend on positions, so it's skipped too.
+        remove_hidden_glyphs(ctx);
+    } else {
+        position(ctx);
+        substitute_post(ctx);
+    }
 
     propagate_flags(ctx.buffer);
 
@@ -351,15 +399,28 @@ fn substitute_pre(ctx: &mut hb_ot_shape_context_t) {
 }
 
 fn substitute_post(ctx: &mut hb_ot_shape_context_t) {
+    remove_hidden_glyphs(ctx);
+
+    if let Some(func) = ctx.plan.shaper.postprocess_glyphs {
+        func(ctx.plan, ctx.face, ctx.buffer);
+    }
+
+    if let Some(shaper) = &ctx.plan.custom_shaper {
+        with_glyph_buffer(ctx.buffer, |buffer| {
+            shaper.postprocess_glyphs(ctx.plan, ctx.face, buffer)
+        });
+    }
+}
+
+/// Removes the glyphs deleted by `morx` and hides or removes default ignorables
+/// and `.notdef` glyphs.
+fn remove_hidden_glyphs(ctx: &mut hb_ot_shape_context_t) {
     if ctx.plan.apply_morx && !ctx.plan.apply_gpos {
         aat_layout::hb_aat_layout_remove_deleted_glyphs(ctx.buffer);
     }
 
     hide_default_ignorables(ctx.buffer, ctx.face);
-
-    if let Some(func) = ctx.plan.shaper.postprocess_glyphs {
-        func(ctx.plan, ctx.face, ctx.buffer);
-    }
+    remove_notdef_glyphs(ctx.buffer);
 }
 
 fn hb_ot_substitute_default(ctx: &mut hb_ot_shape_context_t) {
@@ -383,7 +444,7 @@ fn hb_ot_substitute_plan(ctx: &mut hb_ot_shape_context_t) {
     hb_ot_layout_substitute_start(ctx.face, ctx.buffer);
 
     if ctx.plan.fallback_glyph_classes {
-        hb_synthesize_glyph_classes(ctx.buffer);
+        hb_synthesize_glyph_classes(ctx.face, ctx.buffer);
     }
 
     if ctx.plan.apply_morx {
@@ -407,13 +468,14 @@ fn position(ctx: &mut hb_ot_shape_context_t) {
 
 fn position_default(ctx: &mut hb_ot_shape_context_t) {
     let len = ctx.buffer.len;
+    let scale = ctx.buffer.position_scale();
 
     if ctx.buffer.direction.is_horizontal() {
         for (info, pos) in ctx.buffer.info[..len]
             .iter()
             .zip(&mut ctx.buffer.pos[..len])
         {
-            pos.x_advance = ctx.face.glyph_h_advance(info.as_glyph());
+            pos.x_advance = ctx.face.glyph_h_advance(info.as_glyph()) * scale;
         }
     } else {
         for (info, pos) in ctx.buffer.info[..len]
@@ -421,9 +483,10 @@ fn position_default(ctx: &mut hb_ot_shape_context_t) {
             .zip(&mut ctx.buffer.pos[..len])
         {
             let glyph = info.as_glyph();
-            pos.y_advance = ctx.face.glyph_v_advance(glyph);
-            pos.x_offset -= ctx.face.glyph_h_origin(glyph);
-            pos.y_offset -= ctx.face.glyph_v_origin(glyph);
+            pos.y_advance = ctx.face.glyph_v_advance(glyph) * scale;
+            let (x, y) = ctx.face.glyph_v_origin(glyph);
+            pos.x_offset -= x * scale;
+            pos.y_offset -= y * scale;
         }
     }
 
@@ -454,8 +517,24 @@ fn position_complex(ctx: &mut hb_ot_shape_context_t) {
         zero_mark_widths_by_gdef(ctx.buffer, adjust_offsets_when_zeroing);
     }
 
+    let produce_positioned_flags = ctx
+        .buffer
+        .flags
+        .contains(BufferFlags::PRODUCE_POSITIONED_FLAGS);
+    if produce_positioned_flags {
+        let len = ctx.buffer.len;
+        ctx.buffer.layout_positions.clear();
+        ctx.buffer
+            .layout_positions
+            .extend_from_slice(&ctx.buffer.pos[..len]);
+    }
+
     position_by_plan(ctx.plan, ctx.face, ctx.buffer);
 
+    if produce_positioned_flags {
+        mark_positioned_glyphs(ctx.buffer);
+    }
+
     if ctx.plan.zero_marks
         && ctx.plan.shaper.zero_width_marks == HB_OT_SHAPE_ZERO_WIDTH_MARKS_BY_GDEF_LATE
     {
@@ -473,6 +552,7 @@ fn position_complex(ctx: &mut hb_ot_shape_context_t) {
     GPOS::position_finish_offsets(ctx.face, ctx.buffer);
 
     if ctx.plan.fallback_mark_positioning {
+        ctx.buffer.diagnostics.fallback_mark_positioning = true;
         ot_shape_fallback::position_marks(
             ctx.plan,
             ctx.face,
@@ -480,6 +560,34 @@ fn position_complex(ctx: &mut hb_ot_shape_context_t) {
             adjust_offsets_when_zeroing,
         );
     }
+
+    apply_synthetic_slant(ctx.face, ctx.buffer);
+}
+
+fn apply_synthetic_slant(face: &hb_font_t, buffer: &mut hb_buffer_t) {
+    let slant = face.slant;
+    if slant == 0.0 {
+        return;
+    }
+
+    for pos in &mut buffer.pos[..buffer.len] {
+        if pos.y_offset != 0 {
+            pos.x_offset += (slant * pos.y_offset as f32).round() as i32;
+        }
+    }
+}
+
+fn mark_positioned_glyphs(buffer: &mut hb_buffer_t) {
+    let len = buffer.len;
+    for ((info, pos), old_pos) in buffer.info[..len]
+        .iter_mut()
+        .zip(&buffer.pos[..len])
+        .zip(&buffer.layout_positions)
+    {
+        if bytemuck::bytes_of(pos) != bytemuck::bytes_of(old_pos) {
+            info.set_glyph_props(info.glyph_props() | GlyphPropsFlags::POSITIONED.bits());
+        }
+    }
 }
 
 fn position_by_plan(plan: &hb_ot_shape_plan_t, face: &hb_font_t, buffer: &mut hb_buffer_t) {
@@ -491,6 +599,7 @@ fn position_by_plan(plan: &hb_ot_shape_plan_t, face: &hb_font_t, buffer: &mut hb
     if plan.apply_kern {
         super::kerning::hb_ot_layout_kern(plan, face, buffer);
     } else if plan.apply_fallback_kern {
+        buffer.diagnostics.fallback_kerning = true;
         ot_shape_fallback::_hb_ot_shape_fallback_kern(plan, face, buffer);
     }
 
@@ -511,6 +620,10 @@ fn setup_masks(ctx: &mut hb_ot_shape_context_t) {
         func(ctx.plan, ctx.face, ctx.buffer);
     }
 
+    if let Some(shaper) = &ctx.plan.custom_shaper {
+        shaper.setup_masks(ctx.plan, ctx.face, &mut ShaperBuffer::new(ctx.buffer));
+    }
+
     for feature in &ctx.plan.user_features {
         if !feature.is_global() {
             let (mask, shift) = ctx.plan.ot_map.get_mask(feature.tag);
@@ -601,6 +714,7 @@ fn set_unicode_props(buffer: &mut hb_buffer_t) {
     // https://www.unicode.org/reports/tr29/#Regex_Definitions
 
     let len = buffer.len;
+    let unicode = hb_unicode_funcs_get(&buffer.unicode);
 
     let mut i = 0;
     while i < len {
@@ -609,7 +723,7 @@ fn set_unicode_props(buffer: &mut hb_buffer_t) {
         // checker can understand.
         let (prior, later) = buffer.info.split_at_mut(i);
         let info = &mut later[0];
-        info.init_unicode_props(&mut buffer.scratch_flags);
+        info.init_unicode_props(unicode, &mut buffer.scratch_flags);
 
         let gen_cat = _hb_glyph_info_get_general_category(&info);
 
@@ -642,7 +756,7 @@ fn set_unicode_props(buffer: &mut hb_buffer_t) {
             _hb_glyph_info_set_continuation(info);
             if let Some(next) = buffer.info[..len].get_mut(i + 1) {
                 if next.as_char().is_emoji_extended_pictographic() {
-                    next.init_unicode_props(&mut buffer.scratch_flags);
+                    next.init_unicode_props(unicode, &mut buffer.scratch_flags);
                     _hb_glyph_info_set_continuation(next);
                     i += 1;
                 }
@@ -696,13 +810,29 @@ fn insert_dotted_circle(buffer: &mut hb_buffer_t, face: &hb_font_t) {
             ..hb_glyph_info_t::default()
         };
 
-        info.init_unicode_props(&mut buffer.scratch_flags);
+        info.init_unicode_props(
+            hb_unicode_funcs_get(&buffer.unicode),
+            &mut buffer.scratch_flags,
+        );
         buffer.clear_output();
         buffer.output_info(info);
         buffer.sync();
     }
 }
 
+fn record_grapheme_starts(buffer: &mut hb_buffer_t) {
+    buffer.grapheme_starts.clear();
+
+    if !buffer.flags.contains(BufferFlags::PRODUCE_GRAPHEME_INDICES) {
+        return;
+    }
+
+    foreach_grapheme!(buffer, start, _end, {
+        let cluster = buffer.info[start].cluster;
+        buffer.grapheme_starts.push(cluster);
+    });
+}
+
 fn form_clusters(buffer: &mut hb_buffer_t) {
     if buffer.scratch_flags & HB_BUFFER_SCRATCH_FLAG_HAS_NON_ASCII != 0 {
         if buffer.cluster_level == HB_BUFFER_CLUSTER_LEVEL_MONOTONE_GRAPHEMES {
@@ -777,8 +907,13 @@ fn rotate_chars(ctx: &mut hb_ot_shape_context_t) {
     if ctx.target_direction.is_backward() {
         let rtlm_mask = ctx.plan.rtlm_mask;
 
+        // Characters whose bidi mirror is in the font use that glyph, `rtlm`
+        // is only enabled for the rest, as recommended by the OpenType spec.
+        // `rtlm` deliberately doesn't take precedence, since HarfBuzz
+        // and other shapers don't do that either.
+        let unicode = hb_unicode_funcs_get(&ctx.buffer.unicode);
         for info in &mut ctx.buffer.info[..len] {
-            if let Some(c) = info.as_char().mirrored().map(u32::from) {
+            if let Some(c) = unicode.mirroring(info.as_char()).map(u32::from) {
                 if ctx.face.has_glyph(c) {
                     info.glyph_id = c;
                     continue;
@@ -811,7 +946,7 @@ fn map_glyphs_fast(buffer: &mut hb_buffer_t) {
     }
 }
 
-fn hb_synthesize_glyph_classes(buffer: &mut hb_buffer_t) {
+fn hb_synthesize_glyph_classes(face: &hb_font_t, buffer: &mut hb_buffer_t) {
     let len = buffer.len;
     for info in &mut buffer.info[..len] {
         // Never mark default-ignorables as marks.
@@ -831,7 +966,11 @@ fn hb_synthesize_glyph_classes(buffer: &mut hb_buffer_t) {
             GlyphPropsFlags::MARK
         };
 
-        info.set_glyph_props(class.bits());
+        if face.glyph_class_override(info.as_glyph()).is_some() {
+            info.set_glyph_props(face.glyph_props(info.as_glyph()));
+        } else {
+            info.set_glyph_props(class.bits());
+        }
     }
 }
 
@@ -899,6 +1038,12 @@ fn hide_default_ignorables(buffer: &mut hb_buffer_t, face: &hb_font_t) {
     }
 }
 
+fn remove_notdef_glyphs(buffer: &mut hb_buffer_t) {
+    if buffer.flags.contains(BufferFlags::REMOVE_NOTDEF) {
+        buffer.delete_glyphs_inplace(|info| info.glyph_id == 0);
+    }
+}
+
 fn propagate_flags(buffer: &mut hb_buffer_t) {
     // Propagate cluster-level glyph flags to be the same on all cluster glyphs.
     // Simplifies using them.
@@ -946,3 +1091,67 @@ fn propagate_flags(buffer: &mut hb_buffer_t) {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::hb_ot_shape_planner_t;
+    use crate::hb::hb_font_t;
+    use crate::{script, Direction};
+    use alloc::vec::Vec;
+
+    // Returns the features enabled by default, which don't depend on the font
+    // as long as it has no `GSUB` table.
+    fn default_features(direction: Direction, script: crate::Script) -> Vec<[u8; 4]> {
+        let data = include_bytes!("../../tests/fonts/rb_custom/GposDevice.ttf");
+        let face = hb_font_t::from_slice(data, 0).unwrap();
+        assert!(face.gsub.is_none());
+
+        let mut planner = hb_ot_shape_planner_t::new(&face, direction, Some(script), None);
+        planner.collect_features(&[]);
+        planner
+            .ot_map
+            .enabled_features()
+            .into_iter()
+            .map(|tag| tag.to_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn default_shaper_features() {
+        assert_eq!(
+            default_features(Direction::LeftToRight, script::LATIN),
+            [
+                b"rvrn", b"ltra", b"ltrm", b"frac", b"numr", b"dnom", b"rand", b"trak", b"Harf",
+                b"HARF", b"Buzz", b"BUZZ", b"abvm", b"blwm", b"ccmp", b"locl", b"mark", b"mkmk",
+                b"rlig", b"calt", b"clig", b"curs", b"dist", b"kern", b"liga", b"rclt",
+            ]
+            .map(|tag| *tag)
+        );
+    }
+
+    #[test]
+    fn vertical_features() {
+        assert_eq!(
+            default_features(Direction::TopToBottom, script::LATIN),
+            [
+                b"rvrn", b"frac", b"numr", b"dnom", b"rand", b"trak", b"Harf", b"HARF", b"Buzz",
+                b"BUZZ", b"abvm", b"blwm", b"ccmp", b"locl", b"mark", b"mkmk", b"rlig", b"vert",
+            ]
+            .map(|tag| *tag)
+        );
+    }
+
+    #[test]
+    fn arabic_shaper_features() {
+        assert_eq!(
+            default_features(Direction::RightToLeft, script::ARABIC),
+            [
+                b"rvrn", b"rtla", b"rtlm", b"frac", b"numr", b"dnom", b"rand", b"trak", b"Harf",
+                b"HARF", b"stch", b"ccmp", b"locl", b"isol", b"fina", b"fin2", b"fin3", b"medi",
+                b"med2", b"init", b"rlig", b"calt", b"rclt", b"liga", b"clig", b"mset", b"Buzz",
+                b"BUZZ", b"abvm", b"blwm", b"mark", b"mkmk", b"curs", b"dist", b"kern",
+            ]
+            .map(|tag| *tag)
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use ttf_parser::GlyphId;
 
-use super::buffer::{hb_buffer_t, GlyphPosition};
+use super::buffer::{hb_buffer_t, hb_glyph_info_t, GlyphPosition};
 use super::face::hb_glyph_extents_t;
 use super::ot_layout::*;
 use super::ot_shape_plan::hb_ot_shape_plan_t;
@@ -129,9 +129,9 @@ fn zero_mark_advances(
 }
 
 fn position_mark(
-    _: &hb_ot_shape_plan_t,
     face: &hb_font_t,
     direction: Direction,
+    scale: i32,
     glyph: GlyphId,
     pos: &mut GlyphPosition,
     base_extents: &mut hb_glyph_extents_t,
@@ -139,12 +139,11 @@ fn position_mark(
 ) {
     use CanonicalCombiningClass as Class;
 
-    let mut mark_extents = hb_glyph_extents_t::default();
-    if !face.glyph_extents(glyph, &mut mark_extents) {
+    let Some(mark_extents) = scaled_glyph_extents(face, glyph, scale) else {
         return;
     };
 
-    let y_gap = face.units_per_em as i32 / 16;
+    let y_gap = face.units_per_em as i32 * scale / 16;
     pos.x_offset = 0;
     pos.y_offset = 0;
 
@@ -244,6 +243,21 @@ fn position_mark(
     }
 }
 
+/// Returns the extents of a glyph, in font units multiplied by `scale`.
+fn scaled_glyph_extents(
+    face: &hb_font_t,
+    glyph: GlyphId,
+    scale: i32,
+) -> Option<hb_glyph_extents_t> {
+    let extents = face.glyph_extents(glyph)?;
+    Some(hb_glyph_extents_t {
+        x_bearing: extents.x_bearing * scale,
+        y_bearing: extents.y_bearing * scale,
+        width: extents.width * scale,
+        height: extents.height * scale,
+    })
+}
+
 fn position_around_base(
     plan: &hb_ot_shape_plan_t,
     face: &hb_font_t,
@@ -255,12 +269,12 @@ fn position_around_base(
     let mut horizontal_dir = Direction::Invalid;
     buffer.unsafe_to_break(Some(base), Some(end));
 
+    let scale = buffer.position_scale();
     let base_info = &buffer.info[base];
     let base_pos = &buffer.pos[base];
     let base_glyph = base_info.as_glyph();
 
-    let mut base_extents = hb_glyph_extents_t::default();
-    if !face.glyph_extents(base_glyph, &mut base_extents) {
+    let Some(mut base_extents) = scaled_glyph_extents(face, base_glyph, scale) else {
         zero_mark_advances(buffer, base + 1, end, adjust_offsets_when_zeroing);
         return;
     };
@@ -271,7 +285,7 @@ fn position_around_base(
     // Use horizontal advance for horizontal positioning.
     // Generally a better idea. Also works for zero-ink glyphs. See:
     // https://github.com/harfbuzz/harfbuzz/issues/1532
-    base_extents.width = face.glyph_h_advance(base_glyph) as i32;
+    base_extents.width = face.glyph_h_advance(base_glyph) * scale;
 
     let lig_id = _hb_glyph_info_get_lig_id(base_info) as u32;
     let num_lig_components = _hb_glyph_info_get_lig_num_comps(base_info) as i32;
@@ -294,14 +308,7 @@ fn position_around_base(
     {
         if _hb_glyph_info_get_modified_combining_class(info) != 0 {
             if num_lig_components > 1 {
-                let this_lig_id = _hb_glyph_info_get_lig_id(info) as u32;
-                let mut this_lig_component = _hb_glyph_info_get_lig_comp(info) as i32 - 1;
-
-                // Conditions for attaching to the last component.
-                if lig_id == 0 || lig_id != this_lig_id || this_lig_component >= num_lig_components
-                {
-                    this_lig_component = num_lig_components - 1;
-                }
+                let this_lig_component = mark_lig_component(info, lig_id, num_lig_components);
 
                 if last_lig_component != this_lig_component {
                     last_lig_component = this_lig_component;
@@ -336,9 +343,9 @@ fn position_around_base(
             }
 
             position_mark(
-                plan,
                 face,
                 buffer.direction,
+                scale,
                 info.as_glyph(),
                 pos,
                 &mut cluster_extents,
@@ -359,6 +366,138 @@ fn position_around_base(
             }
         }
     }
+
+    if let Some(band) = buffer.fallback_mark_band {
+        if buffer.direction.is_horizontal() {
+            for component in 0..num_lig_components.max(1) {
+                for stack in [MarkStack::Above, MarkStack::Below] {
+                    clamp_mark_stack(face, buffer, base, end, component, stack, band);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the ligature component a mark is positioned on.
+fn mark_lig_component(info: &hb_glyph_info_t, lig_id: u32, num_lig_components: i32) -> i32 {
+    let this_lig_id = _hb_glyph_info_get_lig_id(info) as u32;
+    let this_lig_component = _hb_glyph_info_get_lig_comp(info) as i32 - 1;
+
+    // Conditions for attaching to the last component.
+    if lig_id == 0 || lig_id != this_lig_id || this_lig_component >= num_lig_components {
+        num_lig_components - 1
+    } else {
+        this_lig_component
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MarkStack {
+    Above,
+    Below,
+}
+
+impl MarkStack {
+    fn of(combining_class: CanonicalCombiningClass) -> Option<Self> {
+        use CanonicalCombiningClass as Class;
+
+        match combining_class {
+            Class::DoubleBelow
+            | Class::BelowLeft
+            | Class::Below
+            | Class::BelowRight
+            | Class::AttachedBelowLeft
+            | Class::AttachedBelow => Some(Self::Below),
+
+            Class::DoubleAbove
+            | Class::AboveLeft
+            | Class::Above
+            | Class::AboveRight
+            | Class::AttachedAbove
+            | Class::AttachedAboveRight => Some(Self::Above),
+
+            _ => None,
+        }
+    }
+}
+
+/// Compresses a stack of positioned marks that reaches beyond the band.
+///
+/// Works in a space where the stack grows upwards, from its inner edge next to
+/// the base to its outer edge. Marks are moved towards the base proportionally
+/// to the distance of their outer edge from the inner edge of the stack, so
+/// that the outer edge of the stack ends up on the band edge. When even the
+/// inner edge is beyond the band, all marks end up on the band edge.
+fn clamp_mark_stack(
+    face: &hb_font_t,
+    buffer: &mut hb_buffer_t,
+    base: usize,
+    end: usize,
+    component: i32,
+    stack: MarkStack,
+    (min_y, max_y): (i32, i32),
+) {
+    let scale = buffer.position_scale();
+    let (sign, limit) = match stack {
+        MarkStack::Above => (1, max_y.saturating_mul(scale)),
+        MarkStack::Below => (-1, min_y.saturating_mul(scale).saturating_neg()),
+    };
+
+    let base_info = &buffer.info[base];
+    let lig_id = _hb_glyph_info_get_lig_id(base_info) as u32;
+    let num_lig_components = _hb_glyph_info_get_lig_num_comps(base_info) as i32;
+
+    // Returns the inner and outer edges of a mark of the stack.
+    let mark_edges = |buffer: &hb_buffer_t, i: usize| {
+        let info = &buffer.info[i];
+        let combining_class = _hb_glyph_info_get_modified_combining_class(info);
+        if combining_class == 0
+            || MarkStack::of(conv_combining_class(combining_class)) != Some(stack)
+            || (num_lig_components > 1
+                && mark_lig_component(info, lig_id, num_lig_components) != component)
+        {
+            return None;
+        }
+
+        let extents = scaled_glyph_extents(face, info.as_glyph(), scale)?;
+        let top = buffer.pos[i].y_offset + extents.y_bearing;
+        let bottom = top + extents.height;
+        match stack {
+            MarkStack::Above => Some((bottom, top)),
+            MarkStack::Below => Some((-top, -bottom)),
+        }
+    };
+
+    let mut stack_inner = i32::MAX;
+    let mut stack_outer = i32::MIN;
+    for i in base + 1..end {
+        if let Some((inner, outer)) = mark_edges(buffer, i) {
+            stack_inner = stack_inner.min(inner);
+            stack_outer = stack_outer.max(outer);
+        }
+    }
+
+    if stack_outer <= limit {
+        return;
+    }
+
+    let new_inner = stack_inner.min(limit);
+    for i in base + 1..end {
+        let Some((_, outer)) = mark_edges(buffer, i) else {
+            continue;
+        };
+
+        // The limit can be far from the marks, so compute the shift in 64 bits.
+        let new_outer = if stack_outer > stack_inner {
+            i64::from(new_inner)
+                + i64::from(outer - stack_inner) * (i64::from(limit) - i64::from(new_inner))
+                    / i64::from(stack_outer - stack_inner)
+        } else {
+            i64::from(limit)
+        };
+        let y_offset = i64::from(buffer.pos[i].y_offset) + sign * (new_outer - i64::from(outer));
+        buffer.pos[i].y_offset = y_offset.clamp(i32::MIN.into(), i32::MAX.into()) as i32;
+    }
 }
 
 fn position_cluster(
@@ -421,6 +560,8 @@ pub fn _hb_ot_shape_fallback_spaces(
 
     let len = buffer.len;
     let horizontal = buffer.direction.is_horizontal();
+    let scale = buffer.position_scale();
+    let upem = face.units_per_em as i32 * scale;
     for (info, pos) in buffer.info[..len].iter().zip(&mut buffer.pos[..len]) {
         if _hb_glyph_info_is_unicode_space(&info) && !_hb_glyph_info_ligated(info) {
             let space_type = _hb_glyph_info_get_unicode_space_fallback_type(info);
@@ -432,8 +573,7 @@ pub fn _hb_ot_shape_fallback_spaces(
                 | t::SPACE_EM_5
                 | t::SPACE_EM_6
                 | t::SPACE_EM_16 => {
-                    let length =
-                        (face.units_per_em as i32 + (space_type as i32) / 2) / space_type as i32;
+                    let length = (upem + (space_type as i32) / 2) / space_type as i32;
                     if horizontal {
                         pos.x_advance = length;
                     } else {
@@ -442,7 +582,7 @@ pub fn _hb_ot_shape_fallback_spaces(
                 }
 
                 t::SPACE_4_EM_18 => {
-                    let length = ((face.units_per_em as i64) * 4 / 18) as i32;
+                    let length = ((upem as i64) * 4 / 18) as i32;
                     if horizontal {
                         pos.x_advance = length
                     } else {
@@ -454,9 +594,9 @@ pub fn _hb_ot_shape_fallback_spaces(
                     for u in '0'..='9' {
                         if let Some(glyph) = face.get_nominal_glyph(u as u32) {
                             if horizontal {
-                                pos.x_advance = face.glyph_h_advance(glyph) as i32;
+                                pos.x_advance = face.glyph_h_advance(glyph) * scale;
                             } else {
-                                pos.y_advance = face.glyph_v_advance(glyph);
+                                pos.y_advance = face.glyph_v_advance(glyph) * scale;
                             }
                             break;
                         }
@@ -470,9 +610,9 @@ pub fn _hb_ot_shape_fallback_spaces(
 
                     if let Some(glyph) = punct {
                         if horizontal {
-                            pos.x_advance = face.glyph_h_advance(glyph) as i32;
+                            pos.x_advance = face.glyph_h_advance(glyph) * scale;
                         } else {
-                            pos.y_advance = face.glyph_v_advance(glyph);
+                            pos.y_advance = face.glyph_v_advance(glyph) * scale;
                         }
                     }
                 }
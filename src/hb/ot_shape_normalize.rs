@@ -4,7 +4,9 @@ use super::hb_font_t;
 use super::ot_layout::*;
 use super::ot_shape_plan::hb_ot_shape_plan_t;
 use super::ot_shaper::{ComposeFn, DecomposeFn, MAX_COMBINING_MARKS};
-use super::unicode::{hb_unicode_funcs_t, CharExt};
+use super::ot_shaper_custom::{NormalizeContext, ShaperBuffer};
+use super::unicode::{hb_unicode_funcs_get, hb_unicode_funcs_t, CharExt, UnicodeFuncs};
+use crate::BufferFlags;
 
 pub struct hb_ot_shape_normalize_context_t<'a> {
     pub plan: &'a hb_ot_shape_plan_t,
@@ -15,6 +17,11 @@ pub struct hb_ot_shape_normalize_context_t<'a> {
 }
 
 impl hb_ot_shape_normalize_context_t<'_> {
+    #[inline]
+    pub(crate) fn unicode(&self) -> &dyn UnicodeFuncs {
+        hb_unicode_funcs_get(&self.buffer.unicode)
+    }
+
     pub(crate) fn override_decompose_and_compose(
         &mut self,
         decompose: Option<DecomposeFn>,
@@ -79,18 +86,39 @@ pub const HB_OT_SHAPE_NORMALIZATION_MODE_DEFAULT: i32 = HB_OT_SHAPE_NORMALIZATIO
 //     Indic shaper may want to disallow recomposing of two matras.
 
 fn decompose_unicode(
-    _: &hb_ot_shape_normalize_context_t,
+    ctx: &hb_ot_shape_normalize_context_t,
     ab: hb_codepoint_t,
 ) -> Option<(hb_codepoint_t, hb_codepoint_t)> {
-    super::unicode::decompose(ab)
+    ctx.unicode().decompose(ab)
 }
 
 fn compose_unicode(
-    _: &hb_ot_shape_normalize_context_t,
+    ctx: &hb_ot_shape_normalize_context_t,
+    a: hb_codepoint_t,
+    b: hb_codepoint_t,
+) -> Option<hb_codepoint_t> {
+    ctx.unicode().compose(a, b)
+}
+
+fn decompose_custom(
+    ctx: &hb_ot_shape_normalize_context_t,
+    ab: hb_codepoint_t,
+) -> Option<(hb_codepoint_t, hb_codepoint_t)> {
+    match &ctx.plan.custom_shaper {
+        Some(shaper) => shaper.decompose(&NormalizeContext::new(ctx), ab),
+        None => decompose_unicode(ctx, ab),
+    }
+}
+
+fn compose_custom(
+    ctx: &hb_ot_shape_normalize_context_t,
     a: hb_codepoint_t,
     b: hb_codepoint_t,
 ) -> Option<hb_codepoint_t> {
-    super::unicode::compose(a, b)
+    match &ctx.plan.custom_shaper {
+        Some(shaper) => shaper.compose(&NormalizeContext::new(ctx), a, b),
+        None => compose_unicode(ctx, a, b),
+    }
 }
 
 fn set_glyph(info: &mut hb_glyph_info_t, font: &hb_font_t) {
@@ -105,7 +133,10 @@ fn output_char(buffer: &mut hb_buffer_t, unichar: u32, glyph: u32) {
     buffer.output_glyph(unichar);
     // TODO: should be _hb_glyph_info_set_unicode_props (&buffer->prev(), buffer);
     let mut flags = buffer.scratch_flags;
-    buffer.prev_mut().init_unicode_props(&mut flags);
+    let unicode = buffer.unicode.clone();
+    buffer
+        .prev_mut()
+        .init_unicode_props(hb_unicode_funcs_get(&unicode), &mut flags);
     buffer.scratch_flags = flags;
 }
 
@@ -201,7 +232,7 @@ fn decompose_current_character(ctx: &mut hb_ot_shape_normalize_context_t, shorte
     }
 
     // Insert a .notdef glyph if decomposition failed.
-    next_char(ctx.buffer, 0);
+    next_char(ctx.buffer, u32::from(ctx.buffer.not_found.0));
 }
 
 fn handle_variation_selector_cluster(
@@ -299,6 +330,9 @@ pub fn _hb_ot_shape_normalize(
         compose: compose_unicode,
     };
     ctx.override_decompose_and_compose(plan.shaper.decompose, plan.shaper.compose);
+    if plan.custom_shaper.is_some() {
+        ctx.override_decompose_and_compose(Some(decompose_custom), Some(compose_custom));
+    }
 
     let mut buffer = &mut ctx.buffer;
 
@@ -375,7 +409,7 @@ pub fn _hb_ot_shape_normalize(
     }
 
     // Second round, reorder (inplace)
-    if !all_simple {
+    if !all_simple && !buffer.flags.contains(BufferFlags::PRESERVE_MARK_ORDER) {
         let count = buffer.len;
         let mut i = 0;
         while i < count {
@@ -397,6 +431,10 @@ pub fn _hb_ot_shape_normalize(
                 if let Some(reorder_marks) = ctx.plan.shaper.reorder_marks {
                     reorder_marks(ctx.plan, buffer, i, end);
                 }
+
+                if let Some(shaper) = &ctx.plan.custom_shaper {
+                    shaper.reorder_marks(ctx.plan, &mut ShaperBuffer::new(buffer), i, end);
+                }
             }
 
             i = end + 1;
@@ -461,10 +499,11 @@ pub fn _hb_ot_shape_normalize(
 
                         // Modify starter and carry on.
                         let mut flags = buffer.scratch_flags;
+                        let unicode = buffer.unicode.clone();
                         let info = &mut buffer.out_info_mut()[starter];
                         info.glyph_id = u32::from(composed);
                         info.set_glyph_index(u32::from(glyph_id.0));
-                        info.init_unicode_props(&mut flags);
+                        info.init_unicode_props(hb_unicode_funcs_get(&unicode), &mut flags);
                         buffer.scratch_flags = flags;
 
                         continue;
@@ -1,17 +1,25 @@
 use alloc::boxed::Box;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::any::Any;
 
+use super::buffer::UnicodeBuffer;
+use super::ot_layout::TableIndex;
 use super::ot_map::*;
 use super::ot_shape::*;
 use super::ot_shaper::*;
-use super::{hb_font_t, hb_mask_t, Direction, Feature, Language, Script};
+use super::ot_shaper_custom::ComplexShaper;
+use super::{hb_font_t, hb_mask_t, script, Direction, Feature, Language, Script};
+use ttf_parser::{NormalizedCoordinate, Tag};
 
 /// A reusable plan for shaping a text buffer.
 pub struct hb_ot_shape_plan_t {
     pub(crate) direction: Direction,
     pub(crate) script: Option<Script>,
+    pub(crate) language: Option<Language>,
+    pub(crate) coords: Vec<NormalizedCoordinate>,
     pub(crate) shaper: &'static hb_ot_shaper_t,
+    pub(crate) custom_shaper: Option<Arc<dyn ComplexShaper>>,
     pub(crate) ot_map: hb_ot_map_t,
     pub(crate) data: Option<Box<dyn Any + Send + Sync>>,
 
@@ -57,6 +65,73 @@ impl hb_ot_shape_plan_t {
         planner.compile(user_features)
     }
 
+    /// Returns the direction the plan was built for.
+    #[inline]
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the script the plan was built for.
+    #[inline]
+    pub fn script(&self) -> Option<Script> {
+        self.script
+    }
+
+    /// Returns the language the plan was built for.
+    #[inline]
+    pub fn language(&self) -> Option<&Language> {
+        self.language.as_ref()
+    }
+
+    /// Returns the user features the plan was built with.
+    #[inline]
+    pub fn user_features(&self) -> &[Feature] {
+        &self.user_features
+    }
+
+    /// Returns the OpenType script tag the plan selected from a table.
+    ///
+    /// This is the first tag of [`Script::to_ot_tags`] that the font has, or
+    /// a default script like `DFLT` as a fallback. Returns `None` when the
+    /// table has neither.
+    #[inline]
+    pub fn chosen_script(&self, table_index: TableIndex) -> Option<Tag> {
+        self.ot_map.chosen_script(table_index)
+    }
+
+    /// Returns the indices of the lookups the plan applies from a table,
+    /// in the order they are applied.
+    pub fn lookups(&self, table_index: TableIndex) -> impl Iterator<Item = u16> + '_ {
+        self.ot_map
+            .lookups(table_index)
+            .iter()
+            .map(|lookup| lookup.index)
+    }
+
+    /// Returns the mask that enables a feature with the value 1.
+    ///
+    /// Returns 0 when the font has no lookups for the feature.
+    /// See [`ComplexShaper::setup_masks`](crate::ComplexShaper::setup_masks).
+    #[inline]
+    pub fn feature_mask(&self, tag: Tag) -> u32 {
+        self.ot_map.get_1_mask(tag)
+    }
+
+    /// Checks whether the plan can be used for shaping `buffer` with `face`.
+    ///
+    /// The direction, script and language of the buffer and the variation
+    /// coordinates of the face must be the ones the plan was built for.
+    /// Unset buffer properties are not guessed, so
+    /// [`UnicodeBuffer::guess_segment_properties`] should be called first.
+    /// Features are not compared, see [`user_features`](Self::user_features).
+    pub fn matches(&self, face: &hb_font_t, buffer: &UnicodeBuffer) -> bool {
+        let buffer = &buffer.0;
+        self.direction == buffer.direction
+            && self.script.unwrap_or(script::UNKNOWN) == buffer.script.unwrap_or(script::UNKNOWN)
+            && self.language == buffer.language
+            && self.coords == face.variation_coordinates()
+    }
+
     pub(crate) fn data<T: 'static>(&self) -> &T {
         self.data.as_ref().unwrap().downcast_ref().unwrap()
     }
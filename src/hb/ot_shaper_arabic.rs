@@ -304,7 +304,8 @@ fn arabic_joining(buffer: &mut hb_buffer_t) {
     // Check pre-context.
     for i in 0..buffer.context_len[0] {
         let c = buffer.context[0][i];
-        let this_type = get_joining_type(c, c.general_category());
+        let gc = hb_unicode_funcs_get(&buffer.unicode).general_category(c);
+        let this_type = get_joining_type(c, gc);
         if this_type == hb_arabic_joining_type_t::T {
             continue;
         }
@@ -351,7 +352,8 @@ fn arabic_joining(buffer: &mut hb_buffer_t) {
 
     for i in 0..buffer.context_len[1] {
         let c = buffer.context[1][i];
-        let this_type = get_joining_type(c, c.general_category());
+        let gc = hb_unicode_funcs_get(&buffer.unicode).general_category(c);
+        let this_type = get_joining_type(c, gc);
         if this_type == hb_arabic_joining_type_t::T {
             continue;
         }
@@ -455,6 +457,7 @@ fn apply_stch(face: &hb_font_t, buffer: &mut hb_buffer_t) {
     }
 
     let rtl = buffer.direction == Direction::RightToLeft;
+    let scale = buffer.position_scale();
 
     if !rtl {
         buffer.reverse();
@@ -495,7 +498,7 @@ fn apply_stch(face: &hb_font_t, buffer: &mut hb_buffer_t) {
             let end = i;
             while i != 0 && arabic_action_t::is_stch(buffer.info[i - 1].arabic_shaping_action()) {
                 i -= 1;
-                let width = face.glyph_h_advance(buffer.info[i].as_glyph()) as i32;
+                let width = face.glyph_h_advance(buffer.info[i].as_glyph()) * scale;
 
                 if buffer.info[i].arabic_shaping_action() == arabic_action_t::STRETCHING_FIXED {
                     w_fixed += width;
@@ -546,7 +549,7 @@ fn apply_stch(face: &hb_font_t, buffer: &mut hb_buffer_t) {
                 buffer.unsafe_to_break(Some(context), Some(end));
                 let mut x_offset = w_remaining / 2;
                 for k in (start + 1..=end).rev() {
-                    let width = face.glyph_h_advance(buffer.info[k - 1].as_glyph()) as i32;
+                    let width = face.glyph_h_advance(buffer.info[k - 1].as_glyph()) * scale;
 
                     let mut repeat = 1;
                     if buffer.info[k - 1].arabic_shaping_action()
@@ -693,7 +696,7 @@ fn reorder_marks_arabic(
     }
 }
 
-pub const ARABIC_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
+pub static ARABIC_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
     collect_features: Some(collect_features),
     override_features: None,
     create_data: Some(|plan| Box::new(data_create_arabic(plan))),
@@ -0,0 +1,334 @@
+use super::buffer::{hb_buffer_t, hb_glyph_info_t as GlyphInfo, GlyphBuffer, UnicodeBuffer};
+use super::ot_map::F_NONE;
+use super::ot_shape::hb_ot_shape_planner_t;
+use super::ot_shape_normalize::hb_ot_shape_normalize_context_t;
+use super::ot_shape_plan::hb_ot_shape_plan_t;
+use super::ot_shaper::hb_ot_shaper_t;
+use super::{hb_font_t, hb_tag_t, Direction, Script};
+
+/// A complex shaper that can be registered for a script.
+///
+/// See [`Face::register_shaper`](crate::Face::register_shaper).
+///
+/// All hooks are optional and are called at the same points of the shaping
+/// pipeline as the ones of the built-in shapers.
+pub trait ComplexShaper: Send + Sync {
+    /// Called during shape plan creation.
+    ///
+    /// Shapers should use it to add their features.
+    fn collect_features(&self, _planner: &mut ShapePlanner) {}
+
+    /// Called during shape plan creation, after common features were added.
+    ///
+    /// Shapers should use it to override features.
+    fn override_features(&self, _planner: &mut ShapePlanner) {}
+
+    /// Called before shaping starts.
+    ///
+    /// Shapers can use it to modify text. Glyph infos contain codepoints at this point.
+    ///
+    /// Codepoints that are not valid characters after the hook are replaced
+    /// with U+FFFD and the shaping is marked as failed in
+    /// [`ShapeDiagnostics`](crate::ShapeDiagnostics).
+    fn preprocess_text(
+        &self,
+        _plan: &hb_ot_shape_plan_t,
+        _face: &hb_font_t,
+        _buffer: &mut UnicodeBuffer,
+    ) {
+    }
+
+    /// Called during normalization to decompose a character.
+    ///
+    /// The decomposition is only used when the font supports it.
+    /// By default, the canonical decomposition of the buffer's Unicode functions is used.
+    fn decompose(&self, ctx: &NormalizeContext, ab: char) -> Option<(char, char)> {
+        ctx.decompose(ab)
+    }
+
+    /// Called during normalization to compose a mark with its preceding base.
+    ///
+    /// The composition is only used when the font has a glyph for it.
+    /// By default, the canonical composition of the buffer's Unicode functions is used.
+    fn compose(&self, ctx: &NormalizeContext, a: char, b: char) -> Option<char> {
+        ctx.compose(a, b)
+    }
+
+    /// Called during normalization, after a sequence of combining marks in
+    /// `start..end` was sorted by combining class.
+    ///
+    /// Shapers can use it to modify the ordering of combining marks.
+    fn reorder_marks(
+        &self,
+        _plan: &hb_ot_shape_plan_t,
+        _buffer: &mut ShaperBuffer,
+        _start: usize,
+        _end: usize,
+    ) {
+    }
+
+    /// Called after normalization, before substitution.
+    ///
+    /// Shapers can use it to enable features for a part of the buffer using
+    /// [`ShapePlan::feature_mask`](crate::ShapePlan::feature_mask) and
+    /// [`ShaperBuffer::set_feature_mask`].
+    /// Glyph infos contain codepoints at this point.
+    fn setup_masks(
+        &self,
+        _plan: &hb_ot_shape_plan_t,
+        _face: &hb_font_t,
+        _buffer: &mut ShaperBuffer,
+    ) {
+    }
+
+    /// Called after substitution.
+    ///
+    /// Shapers can use it to modify glyphs before positioning.
+    fn postprocess_glyphs(
+        &self,
+        _plan: &hb_ot_shape_plan_t,
+        _face: &hb_font_t,
+        _buffer: &mut GlyphBuffer,
+    ) {
+    }
+}
+
+/// A shape plan builder that is passed to the [`ComplexShaper`] feature hooks.
+pub struct ShapePlanner<'p, 'a>(&'p mut hb_ot_shape_planner_t<'a>);
+
+impl<'p, 'a> ShapePlanner<'p, 'a> {
+    pub(crate) fn new(planner: &'p mut hb_ot_shape_planner_t<'a>) -> Self {
+        ShapePlanner(planner)
+    }
+
+    /// Returns the plan direction.
+    #[inline]
+    pub fn direction(&self) -> Direction {
+        self.0.direction
+    }
+
+    /// Returns the plan script.
+    #[inline]
+    pub fn script(&self) -> Option<Script> {
+        self.0.script
+    }
+
+    /// Enables a feature for the whole buffer.
+    #[inline]
+    pub fn enable_feature(&mut self, tag: hb_tag_t, value: u32) {
+        self.0.ot_map.enable_feature(tag, F_NONE, value);
+    }
+
+    /// Adds a feature that is disabled by default.
+    ///
+    /// It can be enabled for some glyphs in [`ComplexShaper::setup_masks`].
+    #[inline]
+    pub fn add_feature(&mut self, tag: hb_tag_t, value: u32) {
+        self.0.ot_map.add_feature(tag, F_NONE, value);
+    }
+
+    /// Disables a feature for the whole buffer.
+    #[inline]
+    pub fn disable_feature(&mut self, tag: hb_tag_t) {
+        self.0.ot_map.disable_feature(tag);
+    }
+
+    /// Starts a new GSUB stage.
+    ///
+    /// Features added after the pause are applied after all the previous ones.
+    #[inline]
+    pub fn add_gsub_pause(&mut self) {
+        self.0.ot_map.add_gsub_pause(None);
+    }
+
+    /// Starts a new GPOS stage.
+    ///
+    /// Features added after the pause are applied after all the previous ones.
+    #[inline]
+    pub fn add_gpos_pause(&mut self) {
+        self.0.ot_map.add_gpos_pause(None);
+    }
+}
+
+/// A view of the buffer that is passed to the [`ComplexShaper`] hooks
+/// that must not change the buffer length.
+///
+/// Glyph infos contain codepoints while it is used.
+pub struct ShaperBuffer<'b>(&'b mut hb_buffer_t);
+
+impl<'b> ShaperBuffer<'b> {
+    pub(crate) fn new(buffer: &'b mut hb_buffer_t) -> Self {
+        ShaperBuffer(buffer)
+    }
+
+    /// Returns the number of glyph infos.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+
+    /// Checks that the buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+
+    /// Returns the glyph infos.
+    #[inline]
+    pub fn glyph_infos(&self) -> &[GlyphInfo] {
+        &self.0.info[..self.0.len]
+    }
+
+    /// Replaces the character of a glyph info.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn set_char(&mut self, index: usize, c: char) {
+        self.0.info[..self.0.len][index].glyph_id = u32::from(c);
+    }
+
+    /// Enables or disables the features of a mask for a glyph info.
+    ///
+    /// See [`ShapePlan::feature_mask`](crate::ShapePlan::feature_mask).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn set_feature_mask(&mut self, index: usize, mask: u32, enabled: bool) {
+        self.0.info[..self.0.len][index].set_feature_mask(mask, enabled);
+    }
+
+    /// Swaps two glyph infos.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` are out of bounds.
+    #[inline]
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.0.info[..self.0.len].swap(a, b);
+    }
+}
+
+/// The normalization state that is passed to the [`ComplexShaper`]
+/// composition hooks.
+pub struct NormalizeContext<'c, 'a>(&'c hb_ot_shape_normalize_context_t<'a>);
+
+impl<'c, 'a> NormalizeContext<'c, 'a> {
+    pub(crate) fn new(ctx: &'c hb_ot_shape_normalize_context_t<'a>) -> Self {
+        NormalizeContext(ctx)
+    }
+
+    /// Returns the plan that is used for shaping.
+    #[inline]
+    pub fn plan(&self) -> &hb_ot_shape_plan_t {
+        self.0.plan
+    }
+
+    /// Returns the face that is used for shaping.
+    #[inline]
+    pub fn face(&self) -> &hb_font_t<'a> {
+        self.0.face
+    }
+
+    /// Returns the canonical decomposition of a character.
+    #[inline]
+    pub fn decompose(&self, ab: char) -> Option<(char, char)> {
+        self.0.unicode().decompose(ab)
+    }
+
+    /// Returns the canonical composition of two characters.
+    #[inline]
+    pub fn compose(&self, a: char, b: char) -> Option<char> {
+        self.0.unicode().compose(a, b)
+    }
+}
+
+impl ComplexShaper for hb_ot_shaper_t {
+    fn collect_features(&self, planner: &mut ShapePlanner) {
+        if let Some(func) = self.collect_features {
+            func(planner.0);
+        }
+    }
+
+    fn override_features(&self, planner: &mut ShapePlanner) {
+        if let Some(func) = self.override_features {
+            func(planner.0);
+        }
+    }
+
+    fn preprocess_text(
+        &self,
+        plan: &hb_ot_shape_plan_t,
+        face: &hb_font_t,
+        buffer: &mut UnicodeBuffer,
+    ) {
+        if let Some(func) = self.preprocess_text {
+            func(plan, face, &mut buffer.0);
+        }
+    }
+
+    fn decompose(&self, ctx: &NormalizeContext, ab: char) -> Option<(char, char)> {
+        match self.decompose {
+            Some(func) => func(ctx.0, ab),
+            None => ctx.decompose(ab),
+        }
+    }
+
+    fn compose(&self, ctx: &NormalizeContext, a: char, b: char) -> Option<char> {
+        match self.compose {
+            Some(func) => func(ctx.0, a, b),
+            None => ctx.compose(a, b),
+        }
+    }
+
+    fn reorder_marks(
+        &self,
+        plan: &hb_ot_shape_plan_t,
+        buffer: &mut ShaperBuffer,
+        start: usize,
+        end: usize,
+    ) {
+        if let Some(func) = self.reorder_marks {
+            func(plan, buffer.0, start, end);
+        }
+    }
+
+    fn setup_masks(&self, plan: &hb_ot_shape_plan_t, face: &hb_font_t, buffer: &mut ShaperBuffer) {
+        if let Some(func) = self.setup_masks {
+            func(plan, face, buffer.0);
+        }
+    }
+
+    fn postprocess_glyphs(
+        &self,
+        plan: &hb_ot_shape_plan_t,
+        face: &hb_font_t,
+        buffer: &mut GlyphBuffer,
+    ) {
+        if let Some(func) = self.postprocess_glyphs {
+            func(plan, face, &mut buffer.0);
+        }
+    }
+}
+
+/// Temporarily moves the internal buffer into a public wrapper,
+/// so it can be passed to a user hook.
+pub(crate) fn with_unicode_buffer(buffer: &mut hb_buffer_t, f: impl FnOnce(&mut UnicodeBuffer)) {
+    let mut wrapper = UnicodeBuffer(core::mem::take(buffer));
+    f(&mut wrapper);
+    *buffer = wrapper.0;
+
+    if buffer.replace_invalid_chars() {
+        buffer.shaping_failed = true;
+    }
+}
+
+/// Same as [`with_unicode_buffer`], but for a [`GlyphBuffer`].
+pub(crate) fn with_glyph_buffer(buffer: &mut hb_buffer_t, f: impl FnOnce(&mut GlyphBuffer)) {
+    let mut wrapper = GlyphBuffer(core::mem::take(buffer));
+    f(&mut wrapper);
+    *buffer = wrapper.0;
+}
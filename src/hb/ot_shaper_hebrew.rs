@@ -1,13 +1,13 @@
+use super::hb_tag_t;
 use super::ot_shape_normalize::*;
 use super::ot_shaper::*;
-use super::{hb_tag_t, unicode};
 use crate::hb::buffer::hb_buffer_t;
 use crate::hb::ot_layout::_hb_glyph_info_get_modified_combining_class;
 use crate::hb::ot_shape_plan::hb_ot_shape_plan_t;
 use crate::hb::unicode::modified_combining_class;
 use unicode_ccc::CanonicalCombiningClass;
 
-pub const HEBREW_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
+pub static HEBREW_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
     collect_features: None,
     override_features: None,
     create_data: None,
@@ -85,7 +85,7 @@ fn compose(ctx: &hb_ot_shape_normalize_context_t, a: char, b: char) -> Option<ch
     // https://bugzilla.mozilla.org/show_bug.cgi?id=728866
     // Hebrew presentation forms with dagesh, for characters U+05D0..05EA;
     // Note that some letters do not have a dagesh presForm encoded.
-    match unicode::compose(a, b) {
+    match ctx.unicode().compose(a, b) {
         Some(c) => Some(c),
         None if !ctx.plan.has_gpos_mark => {
             // Special-case Hebrew presentation forms that are excluded from
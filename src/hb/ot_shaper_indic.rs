@@ -14,10 +14,10 @@ use super::ot_shape::*;
 use super::ot_shape_normalize::*;
 use super::ot_shape_plan::hb_ot_shape_plan_t;
 use super::ot_shaper::*;
-use super::unicode::{hb_gc, CharExt, GeneralCategoryExt};
+use super::unicode::{hb_gc, GeneralCategoryExt};
 use super::{hb_font_t, hb_glyph_info_t, hb_mask_t, hb_tag_t, script, Script};
 
-pub const INDIC_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
+pub static INDIC_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
     collect_features: Some(collect_features),
     override_features: Some(override_features),
     create_data: Some(|plan| Box::new(IndicShapePlan::new(plan))),
@@ -632,7 +632,7 @@ fn preprocess_text(_: &hb_ot_shape_plan_t, _: &hb_font_t, buffer: &mut hb_buffer
     super::ot_shaper_vowel_constraints::preprocess_text_vowel_constraints(buffer);
 }
 
-fn decompose(_: &hb_ot_shape_normalize_context_t, ab: char) -> Option<(char, char)> {
+fn decompose(ctx: &hb_ot_shape_normalize_context_t, ab: char) -> Option<(char, char)> {
     // Don't decompose these.
     match ab {
         '\u{0931}' |               // DEVANAGARI LETTER RRA
@@ -643,12 +643,12 @@ fn decompose(_: &hb_ot_shape_normalize_context_t, ab: char) -> Option<(char, cha
         _ => {}
     }
 
-    crate::hb::unicode::decompose(ab)
+    ctx.unicode().decompose(ab)
 }
 
-fn compose(_: &hb_ot_shape_normalize_context_t, a: char, b: char) -> Option<char> {
+fn compose(ctx: &hb_ot_shape_normalize_context_t, a: char, b: char) -> Option<char> {
     // Avoid recomposing split matras.
-    if a.general_category().is_mark() {
+    if ctx.unicode().general_category(a).is_mark() {
         return None;
     }
 
@@ -657,7 +657,7 @@ fn compose(_: &hb_ot_shape_normalize_context_t, a: char, b: char) -> Option<char
         return Some('\u{09DF}');
     }
 
-    crate::hb::unicode::compose(a, b)
+    ctx.unicode().compose(a, b)
 }
 
 fn setup_masks(_: &hb_ot_shape_plan_t, _: &hb_font_t, buffer: &mut hb_buffer_t) {
@@ -1450,10 +1450,10 @@ fn final_reordering_impl(
 
                         break;
                     }
+                }
 
-                    if base == end {
-                        break;
-                    }
+                if base == end {
+                    break;
                 }
             }
 
@@ -7,10 +7,10 @@ use super::ot_shape_normalize::*;
 use super::ot_shape_plan::hb_ot_shape_plan_t;
 use super::ot_shaper::*;
 use super::ot_shaper_indic::ot_category_t;
-use super::unicode::{CharExt, GeneralCategoryExt};
+use super::unicode::GeneralCategoryExt;
 use super::{hb_font_t, hb_glyph_info_t, hb_mask_t, hb_tag_t};
 
-pub const KHMER_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
+pub static KHMER_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
     collect_features: Some(collect_features),
     override_features: Some(override_features),
     create_data: Some(|plan| Box::new(KhmerShapePlan::new(plan))),
@@ -284,21 +284,21 @@ fn override_features(planner: &mut hb_ot_shape_planner_t) {
         .disable_feature(hb_tag_t::from_bytes(b"liga"));
 }
 
-fn decompose(_: &hb_ot_shape_normalize_context_t, ab: char) -> Option<(char, char)> {
+fn decompose(ctx: &hb_ot_shape_normalize_context_t, ab: char) -> Option<(char, char)> {
     // Decompose split matras that don't have Unicode decompositions.
     match ab {
         '\u{17BE}' | '\u{17BF}' | '\u{17C0}' | '\u{17C4}' | '\u{17C5}' => Some(('\u{17C1}', ab)),
-        _ => crate::hb::unicode::decompose(ab),
+        _ => ctx.unicode().decompose(ab),
     }
 }
 
-fn compose(_: &hb_ot_shape_normalize_context_t, a: char, b: char) -> Option<char> {
+fn compose(ctx: &hb_ot_shape_normalize_context_t, a: char, b: char) -> Option<char> {
     // Avoid recomposing split matras.
-    if a.general_category().is_mark() {
+    if ctx.unicode().general_category(a).is_mark() {
         return None;
     }
 
-    crate::hb::unicode::compose(a, b)
+    ctx.unicode().compose(a, b)
 }
 
 fn setup_masks(_: &hb_ot_shape_plan_t, _: &hb_font_t, buffer: &mut hb_buffer_t) {
@@ -8,7 +8,7 @@ use super::ot_shaper_indic::{ot_category_t, ot_position_t};
 use super::{hb_font_t, hb_glyph_info_t, hb_tag_t};
 use crate::hb::ot_shaper_indic::ot_category_t::OT_VPre;
 
-pub const MYANMAR_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
+pub static MYANMAR_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
     collect_features: Some(collect_features),
     override_features: None,
     create_data: None,
@@ -27,7 +27,7 @@ pub const MYANMAR_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
 // Ugly Zawgyi encoding.
 // Disable all auto processing.
 // https://github.com/harfbuzz/harfbuzz/issues/1162
-pub const MYANMAR_ZAWGYI_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
+pub static MYANMAR_ZAWGYI_SHAPER: hb_ot_shaper_t = hb_ot_shaper_t {
     collect_features: None,
     override_features: None,
     create_data: None,
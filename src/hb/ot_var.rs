@@ -0,0 +1,326 @@
+#[cfg(not(feature = "std"))]
+use core_maths::CoreFloat;
+
+use alloc::vec::Vec;
+
+use skrifa::raw::tables::avar::{Avar, SegmentMaps};
+use skrifa::raw::tables::glyf::Glyph;
+use skrifa::raw::tables::gvar::Gvar;
+use skrifa::raw::tables::variations::{DeltaSetIndex, DeltaSetIndexMap, ItemVariationStore};
+use skrifa::raw::types::{F2Dot14, Fixed, GlyphId};
+use skrifa::raw::{FontRead, TableProvider};
+use ttf_parser::NormalizedCoordinate;
+
+use super::common::Variation;
+
+/// Returns the default user-space coordinates of all axes.
+pub(crate) fn default_design_coords<'a>(font: &impl TableProvider<'a>) -> Vec<f32> {
+    let axes = font.fvar().and_then(|fvar| fvar.axes()).unwrap_or_default();
+    axes.iter()
+        .map(|axis| axis.default_value().to_f64() as f32)
+        .collect()
+}
+
+/// Sets user-space `variations` in `design_coords`.
+///
+/// Axes that are not listed in `variations` keep their current values.
+pub(crate) fn set_design_coords<'a>(
+    font: &impl TableProvider<'a>,
+    variations: &[Variation],
+    design_coords: &mut [f32],
+) {
+    let axes = font.fvar().and_then(|fvar| fvar.axes()).unwrap_or_default();
+    for variation in variations {
+        let tag = variation.tag.to_bytes();
+        // A tag can be used by multiple axes, so we have to check all of them.
+        for (axis, value) in axes.iter().zip(design_coords.iter_mut()) {
+            if axis.axis_tag().into_bytes() == tag {
+                *value = variation.value;
+            }
+        }
+    }
+}
+
+/// Normalizes user-space coordinates and maps them through `avar`.
+///
+/// Based on `hb_ot_var_normalize_coords`.
+pub(crate) fn normalize_coords<'a>(
+    font: &impl TableProvider<'a>,
+    design_coords: &[f32],
+    coords: &mut [NormalizedCoordinate],
+) {
+    let axes = font.fvar().and_then(|fvar| fvar.axes()).unwrap_or_default();
+    let mut normalized: Vec<i32> = axes
+        .iter()
+        .zip(design_coords)
+        .map(|(axis, value)| {
+            let value = axis.normalize(Fixed::from_f64(f64::from(*value)));
+            i32::from(value.to_f2dot14().to_bits())
+        })
+        .collect();
+
+    if let Ok(avar) = font.avar() {
+        map_coords(&avar, &mut normalized);
+    }
+
+    for (coord, value) in coords.iter_mut().zip(normalized) {
+        *coord = NormalizedCoordinate::from(value as i16);
+    }
+}
+
+/// Applies the `avar` segment maps and, for version 2 tables, the axis variations.
+fn map_coords(avar: &Avar, coords: &mut [i32]) {
+    let axis_count = usize::from(avar.axis_count());
+    // The version 2 fields follow the segment maps.
+    let mut tail_offset = 8;
+    for (i, map) in avar.axis_segment_maps().iter().take(axis_count).enumerate() {
+        let Ok(map) = map else {
+            return;
+        };
+
+        if let Some(coord) = coords.get_mut(i) {
+            *coord = map_value(&map, *coord);
+        }
+
+        tail_offset += 2 + map.axis_value_maps().len() * 4;
+    }
+
+    if avar.version().major < 2 {
+        return;
+    }
+
+    let data = avar.offset_data();
+    let offset = |pos: usize| {
+        data.read_at::<u32>(tail_offset + pos)
+            .ok()
+            .filter(|offset| *offset != 0)
+            .and_then(|offset| data.split_off(offset as usize))
+    };
+    let index_map = offset(0).and_then(|data| DeltaSetIndexMap::read(data).ok());
+    let Some(store) = offset(4).and_then(|data| ItemVariationStore::read(data).ok()) else {
+        return;
+    };
+
+    // Deltas are computed using the coordinates mapped by the segment maps.
+    let mapped: Vec<F2Dot14> = coords
+        .iter()
+        .map(|coord| F2Dot14::from_bits(*coord as i16))
+        .collect();
+
+    for (i, coord) in coords.iter_mut().enumerate() {
+        let index = match index_map {
+            Some(ref map) => match map.get(i as u32) {
+                Ok(index) => index,
+                Err(_) => continue,
+            },
+            None => DeltaSetIndex {
+                outer: 0,
+                inner: i as u16,
+            },
+        };
+
+        let delta = store.compute_delta(index, &mapped).unwrap_or(0);
+        *coord = (*coord + delta).clamp(-(1 << 14), 1 << 14);
+    }
+}
+
+/// Returns the `gvar` deltas of the right and bottom phantom points of a glyph.
+///
+/// As in ttf-parser, the phantom points of a composite glyph follow one point per
+/// component. `None` when the glyph has no variation data.
+pub(crate) fn phantom_deltas<'a>(
+    font: &impl TableProvider<'a>,
+    coords: &[F2Dot14],
+    glyph: GlyphId,
+) -> Option<(f32, f32)> {
+    let (loca, glyf, gvar) = (font.loca(None).ok()?, font.glyf().ok()?, font.gvar().ok()?);
+    let points_len = match loca.get_glyf(glyph, &glyf).ok().flatten() {
+        Some(Glyph::Simple(simple)) => simple.num_points(),
+        Some(Glyph::Composite(composite)) => composite.components().count(),
+        None => 0,
+    };
+
+    let data = gvar.glyph_variation_data(glyph).ok()?;
+    let (mut right, mut bottom) = (Fixed::ZERO, Fixed::ZERO);
+    // Phantom points aren't part of any contour, so there is nothing to interpolate.
+    for (tuple, scalar) in data.active_tuples_at(coords) {
+        for delta in tuple.deltas() {
+            match (delta.position as usize).checked_sub(points_len) {
+                Some(1) => right += delta.apply_scalar(scalar).x,
+                Some(3) => bottom += delta.apply_scalar(scalar).y,
+                _ => {}
+            }
+        }
+    }
+
+    Some((right.to_f32(), bottom.to_f32()))
+}
+
+/// Returns the `gvar` deltas of the points of a glyph, phantom points excluded.
+///
+/// The deltas of points that a tuple leaves out are inferred from the points of their
+/// contour, ending at `contour_ends`. The points of a composite glyph are the offsets of
+/// its components, which aren't in any contour, so their missing deltas are zero.
+pub(crate) fn point_deltas(
+    gvar: &Gvar,
+    coords: &[F2Dot14],
+    glyph: GlyphId,
+    points: &[(f32, f32)],
+    contour_ends: &[u16],
+) -> Vec<(f32, f32)> {
+    let mut deltas = alloc::vec![(0.0, 0.0); points.len()];
+    let Ok(data) = gvar.glyph_variation_data(glyph) else {
+        return deltas;
+    };
+
+    let mut tuple_deltas = Vec::new();
+    let mut has_delta = Vec::new();
+    for (tuple, scalar) in data.active_tuples_at(coords) {
+        tuple_deltas.clear();
+        tuple_deltas.resize(points.len(), (0.0, 0.0));
+        has_delta.clear();
+        has_delta.resize(points.len(), false);
+
+        for delta in tuple.deltas() {
+            let i = delta.position as usize;
+            if i < points.len() {
+                let delta = delta.apply_scalar::<Fixed>(scalar);
+                tuple_deltas[i] = (delta.x.to_f32(), delta.y.to_f32());
+                has_delta[i] = true;
+            }
+        }
+
+        if !tuple.has_deltas_for_all_points() {
+            infer_deltas(points, contour_ends, &has_delta, &mut tuple_deltas);
+        }
+
+        for (delta, tuple_delta) in deltas.iter_mut().zip(&tuple_deltas) {
+            delta.0 += tuple_delta.0;
+            delta.1 += tuple_delta.1;
+        }
+    }
+
+    deltas
+}
+
+/// Infers the deltas of the points without one from their neighbours in the contour.
+///
+/// Based on the "Inferred deltas for un-referenced point numbers" section of the
+/// `gvar` specification.
+fn infer_deltas(
+    points: &[(f32, f32)],
+    contour_ends: &[u16],
+    has_delta: &[bool],
+    deltas: &mut [(f32, f32)],
+) {
+    let mut start = 0;
+    for &end in contour_ends {
+        let end = usize::from(end);
+        if end < start || end >= points.len() {
+            return;
+        }
+
+        let contour = start..end + 1;
+        start = end + 1;
+
+        let referenced: Vec<usize> = contour.clone().filter(|&i| has_delta[i]).collect();
+        match referenced.len() {
+            // Nothing moves.
+            0 => continue,
+            // The whole contour is shifted.
+            1 => {
+                let delta = deltas[referenced[0]];
+                for i in contour {
+                    deltas[i] = delta;
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        for (k, &prev) in referenced.iter().enumerate() {
+            let next = referenced[(k + 1) % referenced.len()];
+            let mut i = prev;
+            loop {
+                i = if i == end { contour.start } else { i + 1 };
+                if i == next {
+                    break;
+                }
+
+                deltas[i] = (
+                    interpolate_delta(
+                        points[i].0,
+                        (points[prev].0, deltas[prev].0),
+                        (points[next].0, deltas[next].0),
+                    ),
+                    interpolate_delta(
+                        points[i].1,
+                        (points[prev].1, deltas[prev].1),
+                        (points[next].1, deltas[next].1),
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Interpolates the delta of a coordinate between two referenced ones.
+fn interpolate_delta(value: f32, a: (f32, f32), b: (f32, f32)) -> f32 {
+    let (a, b) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+    if a.0 == b.0 {
+        return if a.1 == b.1 { a.1 } else { 0.0 };
+    }
+
+    if value <= a.0 {
+        a.1
+    } else if value >= b.0 {
+        b.1
+    } else {
+        a.1 + (value - a.0) * (b.1 - a.1) / (b.0 - a.0)
+    }
+}
+
+/// Maps a normalized coordinate using a segment map.
+///
+/// Malformed maps, which have decreasing coordinates, are ignored.
+/// Based on `SegmentMaps::map` from HarfBuzz.
+fn map_value(map: &SegmentMaps, value: i32) -> i32 {
+    let maps = map.axis_value_maps();
+    let from = |i: usize| i32::from(maps[i].from_coordinate().to_bits());
+    let to = |i: usize| i32::from(maps[i].to_coordinate().to_bits());
+
+    let is_monotonic = (1..maps.len()).all(|i| from(i - 1) <= from(i) && to(i - 1) <= to(i));
+    if !is_monotonic {
+        return value;
+    }
+
+    // Not part of OpenType, which requires at least the -1, 0 and +1 mappings,
+    // but HarfBuzz handles these cases for better error recovery.
+    match maps.len() {
+        0 => return value,
+        1 => return value - from(0) + to(0),
+        _ => {}
+    }
+
+    if value <= from(0) {
+        return value - from(0) + to(0);
+    }
+
+    let mut i = 1;
+    while i < maps.len() - 1 && value > from(i) {
+        i += 1;
+    }
+
+    if value >= from(i) {
+        return value - from(i) + to(i);
+    }
+
+    if from(i - 1) == from(i) {
+        return to(i - 1);
+    }
+
+    let denom = from(i) - from(i - 1);
+    let value = to(i - 1) as f32
+        + ((to(i) - to(i - 1)) as f32 * (value - from(i - 1)) as f32) / denom as f32;
+    value.round() as i32
+}
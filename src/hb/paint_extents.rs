@@ -2,6 +2,8 @@ use alloc::vec;
 use ttf_parser::colr::{ClipBox, CompositeMode, Paint};
 use ttf_parser::{GlyphId, RectF, Transform};
 
+use super::hb_font_t;
+
 /*
  * This file implements bounds-extraction as well as boundedness
  * computation of COLRv1 fonts as described in:
@@ -133,12 +135,12 @@ pub(crate) struct hb_paint_extents_context_t<'a> {
     // are passed as part of `pop`, while ttf-parser passes it as part of `push`, so we need to
     // store it in the meanwhile.
     composite_modes: vec::Vec<CompositeMode>,
-    face: &'a ttf_parser::Face<'a>,
+    face: &'a hb_font_t<'a>,
     current_glyph: GlyphId,
 }
 
 impl<'a> hb_paint_extents_context_t<'a> {
-    pub(crate) fn new(face: &'a ttf_parser::Face<'a>) -> Self {
+    pub(crate) fn new(face: &'a hb_font_t<'a>) -> Self {
         Self {
             clips: vec![hb_bounds_t::from_status(status_t::UNBOUNDED)],
             groups: vec![hb_bounds_t::from_status(status_t::EMPTY)],
@@ -223,7 +225,7 @@ impl ttf_parser::colr::Painter<'_> for hb_paint_extents_context_t<'_> {
     }
 
     fn push_clip(&mut self) {
-        if let Some(glyph_bbox) = self.face.glyph_bounding_box(self.current_glyph) {
+        if let Some(glyph_bbox) = self.face.glyph_bbox(self.current_glyph) {
             self.push_clip(hb_extents_t {
                 x_min: glyph_bbox.x_min as f32,
                 y_min: glyph_bbox.y_min as f32,
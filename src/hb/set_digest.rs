@@ -15,6 +15,10 @@ pub trait hb_set_digest_ext: Clone + Default {
     fn add_range(&mut self, a: GlyphId, b: GlyphId) -> bool;
     fn may_have(&self, o: &Self::A) -> bool;
     fn may_have_glyph(&self, g: GlyphId) -> bool;
+    /// Passes the masks of the digest to `f`, in a fixed order.
+    fn write_masks(&self, f: &mut impl FnMut(mask_t));
+    /// Creates a digest from masks written by `write_masks`.
+    fn read_masks(f: &mut impl FnMut() -> Option<mask_t>) -> Option<Self>;
 }
 
 #[derive(Clone, Default, Debug)]
@@ -110,6 +114,14 @@ impl<const shift: u8> hb_set_digest_ext for hb_set_digest_bits_pattern_t<shift>
     fn may_have_glyph(&self, g: GlyphId) -> bool {
         self.mask & hb_set_digest_bits_pattern_t::<shift>::mask_for(g) != 0
     }
+
+    fn write_masks(&self, f: &mut impl FnMut(mask_t)) {
+        f(self.mask);
+    }
+
+    fn read_masks(f: &mut impl FnMut() -> Option<mask_t>) -> Option<Self> {
+        Some(Self { mask: f()? })
+    }
 }
 
 #[derive(Clone, Default, Debug)]
@@ -167,6 +179,18 @@ where
     fn may_have_glyph(&self, g: GlyphId) -> bool {
         self.head.may_have_glyph(g) && self.tail.may_have_glyph(g)
     }
+
+    fn write_masks(&self, f: &mut impl FnMut(mask_t)) {
+        self.head.write_masks(f);
+        self.tail.write_masks(f);
+    }
+
+    fn read_masks(f: &mut impl FnMut() -> Option<mask_t>) -> Option<Self> {
+        Some(Self {
+            head: head_t::read_masks(f)?,
+            tail: tail_t::read_masks(f)?,
+        })
+    }
 }
 
 #[rustfmt::skip]
@@ -1,7 +1,11 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use super::buffer::hb_buffer_t;
 use super::hb_font_t;
 use super::ot_shape::{hb_ot_shape_context_t, shape_internal};
 use super::ot_shape_plan::hb_ot_shape_plan_t;
-use crate::{script, Feature, GlyphBuffer, UnicodeBuffer};
+use crate::{script, BufferFlags, Direction, Feature, GlyphBuffer, UnicodeBuffer};
 
 /// Shapes the buffer content using provided font and features.
 ///
@@ -39,8 +43,65 @@ pub fn shape_with_plan(
     face: &hb_font_t,
     plan: &hb_ot_shape_plan_t,
     buffer: UnicodeBuffer,
+) -> GlyphBuffer {
+    shape_with_plan_impl(face, plan, buffer, false)
+}
+
+/// Shapes the buffer content like [`shape`], but stops right after glyph substitution.
+///
+/// The returned glyph ids and clusters are the ones GSUB (or `morx`) produced, in the same
+/// order [`shape`] would return them, while all positions are zero. Glyphs deleted by `morx`
+/// are removed and default ignorables are hidden like in [`shape`], only the shaper
+/// postprocessing that follows positioning is skipped.
+pub fn shape_substitute_only(
+    face: &hb_font_t,
+    features: &[Feature],
+    mut buffer: UnicodeBuffer,
+) -> GlyphBuffer {
+    buffer.0.guess_segment_properties();
+    let plan = hb_ot_shape_plan_t::new(
+        face,
+        buffer.0.direction,
+        buffer.0.script,
+        buffer.0.language.as_ref(),
+        features,
+    );
+    shape_with_plan_impl(face, &plan, buffer, true)
+}
+
+/// Shapes the buffer content like [`shape`] and returns the total advance of the glyphs.
+///
+/// The advance is measured along the buffer direction: the sum of the
+/// x advances for horizontal text and of the y advances for vertical text,
+/// which are negative like in [`GlyphPosition::y_advance`](crate::GlyphPosition::y_advance).
+///
+/// The text, segment properties and flags are taken from `buffer`, like for [`shape`].
+/// The sum saturates at the bounds of `i32` for very long texts.
+pub fn measure(face: &hb_font_t, features: &[Feature], buffer: UnicodeBuffer) -> i32 {
+    let buffer = shape(face, features, buffer).0;
+    let vertical = buffer.direction.is_vertical();
+    let advance: i64 = buffer.pos[..buffer.len]
+        .iter()
+        .map(|pos| {
+            if vertical {
+                pos.y_advance
+            } else {
+                pos.x_advance
+            }
+        })
+        .map(i64::from)
+        .sum();
+    advance.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+fn shape_with_plan_impl(
+    face: &hb_font_t,
+    plan: &hb_ot_shape_plan_t,
+    buffer: UnicodeBuffer,
+    substitute_only: bool,
 ) -> GlyphBuffer {
     let mut buffer = buffer.0;
+    buffer.replace_invalid_chars();
     buffer.guess_segment_properties();
 
     buffer.enter();
@@ -57,12 +118,18 @@ pub fn shape_with_plan(
 
         #[cfg(feature = "wasm-shaper")]
         {
-            super::shape_wasm::shape_with_wasm(face, plan, &mut buffer).unwrap_or_else(|| {
+            let wasm = if substitute_only {
+                None
+            } else {
+                super::shape_wasm::shape_with_wasm(face, plan, &mut buffer)
+            };
+            wasm.unwrap_or_else(|| {
                 shape_internal(&mut hb_ot_shape_context_t {
                     plan,
                     face,
                     buffer: &mut buffer,
                     target_direction,
+                    substitute_only,
                 });
             });
         }
@@ -73,9 +140,186 @@ pub fn shape_with_plan(
                 face,
                 buffer: &mut buffer,
                 target_direction,
+                substitute_only,
             });
         }
     }
 
+    buffer.diagnostics.notdef_count = buffer
+        .info_slice_mut()
+        .iter()
+        .filter(|info| info.glyph_id == 0)
+        .count();
+    buffer.diagnostics.shaping_failed = buffer.shaping_failed;
+
     GlyphBuffer(buffer)
 }
+
+/// Shapes a single directional run of a paragraph, e.g. as resolved by a bidi algorithm.
+///
+/// `range` is the byte range of the run in `text` and must lie on char boundaries. The
+/// surrounding text is used as context, and clusters are byte offsets into `text`, so that
+/// the runs of a paragraph can be laid out next to each other. The script is guessed
+/// from the run, and [`BufferFlags::BEGINNING_OF_TEXT`] and [`BufferFlags::END_OF_TEXT`]
+/// are set when the run starts or ends the paragraph.
+///
+/// Glyphs are returned in visual order, i.e. reversed for [`Direction::RightToLeft`],
+/// with each cluster still keeping its glyphs in the order [`shape`] returns them.
+pub fn shape_run(
+    face: &hb_font_t,
+    features: &[Feature],
+    text: &str,
+    range: Range<usize>,
+    direction: Direction,
+) -> GlyphBuffer {
+    let mut buffer = UnicodeBuffer::new();
+    buffer.set_direction(direction);
+
+    if range.start == 0 {
+        buffer.0.flags.insert(BufferFlags::BEGINNING_OF_TEXT);
+    }
+    if range.end == text.len() {
+        buffer.0.flags.insert(BufferFlags::END_OF_TEXT);
+    }
+
+    buffer.set_pre_context(&text[..range.start]);
+    for (i, c) in text[range.clone()].char_indices() {
+        buffer.add(c, (range.start + i) as u32);
+    }
+    buffer.set_post_context(&text[range.end..]);
+
+    shape(face, features, buffer)
+}
+
+/// Splits a shaped buffer at a text offset, e.g. at a line break.
+///
+/// `text` must be the string `buffer` was shaped from using [`UnicodeBuffer::push_str`],
+/// so that clusters are byte offsets into it, and `offset` must be a char boundary.
+///
+/// When `offset` is the start of a cluster that is not
+/// [unsafe to break](crate::GlyphInfo::unsafe_to_break), the already shaped glyphs are reused.
+/// Otherwise both halves are reshaped, each one using the other as context.
+///
+/// Returns the glyphs for `text[..offset]` and `text[offset..]`, in this order.
+/// The pre- and post-context of `buffer` are kept on the outer sides of the halves.
+///
+/// # Panics
+///
+/// Panics if `offset` is not a char boundary of `text` or is past its end.
+pub fn split_shaped(
+    face: &hb_font_t,
+    features: &[Feature],
+    text: &str,
+    buffer: GlyphBuffer,
+    offset: usize,
+) -> (GlyphBuffer, GlyphBuffer) {
+    assert!(
+        text.is_char_boundary(offset),
+        "split offset {} is not a char boundary of the text",
+        offset
+    );
+
+    let mut buffer = buffer.0;
+    let len = buffer.len;
+    let offset_cluster = offset as u32;
+
+    // Number of glyphs that belong to the first half.
+    let first_len = buffer.info[..len]
+        .iter()
+        .filter(|info| info.cluster < offset_cluster)
+        .count();
+
+    // For backward directions glyphs are in reverse order.
+    let backward = buffer.direction.is_backward();
+    let split = if backward { len - first_len } else { first_len };
+    let (head, tail) = buffer.info[..len].split_at(split);
+    let (first, second) = if backward { (tail, head) } else { (head, tail) };
+
+    let is_contiguous = first.iter().all(|info| info.cluster < offset_cluster)
+        && second.iter().all(|info| info.cluster >= offset_cluster);
+    let is_safe = offset == 0
+        || offset >= text.len()
+        || second
+            .iter()
+            .any(|info| info.cluster == offset_cluster && !info.unsafe_to_break());
+
+    if is_contiguous && is_safe {
+        buffer.info.truncate(len);
+        buffer.pos.truncate(len);
+        let tail_info = buffer.info.split_off(split);
+        let tail_pos = buffer.pos.split_off(split);
+        let head_info = core::mem::take(&mut buffer.info);
+        let head_pos = core::mem::take(&mut buffer.pos);
+        let (head_starts, tail_starts) = {
+            let starts = core::mem::take(&mut buffer.grapheme_starts);
+            let first_starts = starts.partition_point(|&cluster| cluster < offset_cluster);
+            let (before, after) = starts.split_at(first_starts);
+            (before.to_vec(), after.to_vec())
+        };
+        let (head_starts, tail_starts) = if backward {
+            (tail_starts, head_starts)
+        } else {
+            (head_starts, tail_starts)
+        };
+        let head = split_part(&buffer, head_info, head_pos, head_starts);
+        let tail = split_part(&buffer, tail_info, tail_pos, tail_starts);
+        return if backward { (tail, head) } else { (head, tail) };
+    }
+
+    let (before, after) = text.split_at(offset);
+
+    let mut first = empty_like(&buffer);
+    first.0.flags.remove(BufferFlags::END_OF_TEXT);
+    first.push_str(before);
+    first.0.prepend_context(1, after.chars());
+
+    let mut second = empty_like(&buffer);
+    second.0.flags.remove(BufferFlags::BEGINNING_OF_TEXT);
+    second.0.prepend_context(0, before.chars().rev());
+    for (i, c) in after.char_indices() {
+        second.add(c, (offset + i) as u32);
+    }
+    // Adding text clears the post-context.
+    second.0.context[1] = buffer.context[1];
+    second.0.context_len[1] = buffer.context_len[1];
+
+    (shape(face, features, first), shape(face, features, second))
+}
+
+fn split_part(
+    buffer: &hb_buffer_t,
+    info: Vec<crate::GlyphInfo>,
+    pos: Vec<crate::GlyphPosition>,
+    grapheme_starts: Vec<u32>,
+) -> GlyphBuffer {
+    let mut part = empty_like(buffer).0;
+    part.len = info.len();
+    part.info = info;
+    part.pos = pos;
+    part.have_positions = true;
+    part.grapheme_starts = grapheme_starts;
+    part.diagnostics = buffer.diagnostics;
+    part.diagnostics.notdef_count = part.info.iter().filter(|info| info.glyph_id == 0).count();
+    GlyphBuffer(part)
+}
+
+/// Creates an empty buffer with the same properties and context as `buffer`.
+fn empty_like(buffer: &hb_buffer_t) -> UnicodeBuffer {
+    let mut part = hb_buffer_t::new();
+    part.direction = buffer.direction;
+    part.script = buffer.script;
+    part.language = buffer.language.clone();
+    part.flags = buffer.flags;
+    part.cluster_level = buffer.cluster_level;
+    part.invisible = buffer.invisible;
+    part.not_found = buffer.not_found;
+    part.max_ops_limit = buffer.max_ops_limit;
+    part.max_expansion_factor = buffer.max_expansion_factor;
+    part.fallback_mark_band = buffer.fallback_mark_band;
+    part.max_nesting_level = buffer.max_nesting_level;
+    part.budget = buffer.budget.clone();
+    part.unicode = buffer.unicode.clone();
+    part.context = buffer.context;
+    part.context_len = buffer.context_len;
+    UnicodeBuffer(part)
+}
@@ -0,0 +1,389 @@
+//! A restricted shaping path that writes into caller-provided slices.
+
+use super::buffer::{hb_glyph_info_t, GlyphPosition};
+use super::hb_font_t;
+use super::ot_layout::TableIndex;
+use super::ot_layout_common::lookup_flags;
+use super::ot_layout_gpos_table::ValueRecordExt;
+use super::unicode::hb_unicode_funcs_get;
+use crate::{script, Direction, Feature, Script};
+use ttf_parser::gpos::{PairAdjustment, PositioningSubtable, SingleAdjustment, ValueRecord};
+use ttf_parser::gsub::{SingleSubstitution, SubstitutionSubtable};
+use ttf_parser::opentype_layout::{LanguageSystem, LayoutTable, LookupIndex};
+use ttf_parser::{GlyphId, Tag};
+
+const GSUB_HORIZONTAL_FEATURES: &[Tag] = &[
+    Tag::from_bytes(b"rvrn"),
+    Tag::from_bytes(b"ccmp"),
+    Tag::from_bytes(b"locl"),
+    Tag::from_bytes(b"rlig"),
+    Tag::from_bytes(b"calt"),
+    Tag::from_bytes(b"clig"),
+    Tag::from_bytes(b"liga"),
+    Tag::from_bytes(b"rclt"),
+];
+
+const GSUB_VERTICAL_FEATURES: &[Tag] = &[
+    Tag::from_bytes(b"rvrn"),
+    Tag::from_bytes(b"ccmp"),
+    Tag::from_bytes(b"locl"),
+    Tag::from_bytes(b"rlig"),
+    Tag::from_bytes(b"vert"),
+];
+
+const GPOS_HORIZONTAL_FEATURES: &[Tag] = &[
+    Tag::from_bytes(b"abvm"),
+    Tag::from_bytes(b"blwm"),
+    Tag::from_bytes(b"mark"),
+    Tag::from_bytes(b"mkmk"),
+    Tag::from_bytes(b"curs"),
+    Tag::from_bytes(b"dist"),
+    Tag::from_bytes(b"kern"),
+];
+
+const GPOS_VERTICAL_FEATURES: &[Tag] = &[
+    Tag::from_bytes(b"abvm"),
+    Tag::from_bytes(b"blwm"),
+    Tag::from_bytes(b"mark"),
+    Tag::from_bytes(b"mkmk"),
+];
+
+/// Shapes `text` into caller-provided glyph info and position slices, without allocating.
+///
+/// Only creating the [`Face`](crate::Face) allocates, shaping itself doesn't.
+///
+/// This is a restricted shaping path for embedded use. Characters are mapped through `cmap`,
+/// then single substitutions (GSUB type 1) and single and pair adjustments (GPOS types 1
+/// and 2) of the default features and `features` are applied. All other lookup types,
+/// normalization, script-specific shapers, device tables and feature variations are skipped,
+/// so the result only matches [`shape`](crate::shape) for simple scripts and fonts.
+///
+/// Clusters are byte offsets into `text`, like with
+/// [`UnicodeBuffer::push_str`](crate::UnicodeBuffer::push_str). The script is guessed from the text and the default language system is used.
+/// When `direction` is [`Direction::Invalid`], the direction is guessed from the script.
+/// Vertical text gets the vertical advances and origins of the glyphs and
+/// the `vert` feature instead of horizontal features like `kern` and `liga`.
+///
+/// Returns the number of glyphs written, or the number of glyphs required
+/// if `infos` or `positions` is too short.
+pub fn shape_into(
+    face: &hb_font_t,
+    features: &[Feature],
+    direction: Direction,
+    text: &str,
+    infos: &mut [hb_glyph_info_t],
+    positions: &mut [GlyphPosition],
+) -> Result<usize, usize> {
+    let len = text.chars().count();
+    if infos.len() < len || positions.len() < len {
+        return Err(len);
+    }
+
+    let infos = &mut infos[..len];
+    let positions = &mut positions[..len];
+
+    let unicode = hb_unicode_funcs_get(&None);
+    let script = text
+        .chars()
+        .map(|c| unicode.script(c))
+        .find(|s| !matches!(*s, script::COMMON | script::INHERITED | script::UNKNOWN));
+
+    for (info, (cluster, c)) in infos.iter_mut().zip(text.char_indices()) {
+        let glyph = face.get_nominal_glyph(c as u32).unwrap_or(GlyphId(0));
+        *info = hb_glyph_info_t {
+            glyph_id: u32::from(glyph.0),
+            cluster: cluster as u32,
+            ..hb_glyph_info_t::default()
+        };
+    }
+
+    let direction = match direction {
+        Direction::Invalid => script
+            .and_then(Direction::from_script)
+            .unwrap_or(Direction::LeftToRight),
+        direction => direction,
+    };
+
+    substitute(face, script, direction, features, infos);
+    position(face, script, direction, features, infos, positions);
+
+    if direction.is_backward() {
+        infos.reverse();
+        positions.reverse();
+    }
+
+    Ok(len)
+}
+
+fn substitute(
+    face: &hb_font_t,
+    script: Option<Script>,
+    direction: Direction,
+    features: &[Feature],
+    infos: &mut [hb_glyph_info_t],
+) {
+    let Some(gsub) = face.gsub.as_ref() else {
+        return;
+    };
+    let Some((table, sys)) = face.layout_language_system(TableIndex::GSUB, script, None) else {
+        return;
+    };
+
+    let defaults = if direction.is_horizontal() {
+        GSUB_HORIZONTAL_FEATURES
+    } else {
+        GSUB_VERTICAL_FEATURES
+    };
+
+    for (index, lookup) in gsub.lookups.iter().enumerate() {
+        let index = index as LookupIndex;
+        let state = lookup_state(table, &sys, defaults, features, index);
+        if state == LookupState::Disabled {
+            continue;
+        }
+
+        for info in infos.iter_mut() {
+            let glyph = info.as_glyph();
+            if is_ignored(face, glyph, lookup.props)
+                || (state == LookupState::Ranged
+                    && !lookup_enabled(table, &sys, defaults, features, index, info.cluster))
+            {
+                continue;
+            }
+
+            let subst = lookup.subtables.iter().find_map(|subtable| match subtable {
+                SubstitutionSubtable::Single(single) => single_substitute(single, glyph),
+                _ => None,
+            });
+
+            if let Some(subst) = subst {
+                info.glyph_id = u32::from(subst.0);
+            }
+        }
+    }
+}
+
+fn position(
+    face: &hb_font_t,
+    script: Option<Script>,
+    direction: Direction,
+    features: &[Feature],
+    infos: &[hb_glyph_info_t],
+    positions: &mut [GlyphPosition],
+) {
+    let horizontal = direction.is_horizontal();
+    for (info, pos) in infos.iter().zip(positions.iter_mut()) {
+        let glyph = info.as_glyph();
+        *pos = if horizontal {
+            GlyphPosition {
+                x_advance: face.glyph_h_advance(glyph),
+                ..GlyphPosition::default()
+            }
+        } else {
+            let (x, y) = face.glyph_v_origin(glyph);
+            GlyphPosition {
+                y_advance: face.glyph_v_advance(glyph),
+                x_offset: -x,
+                y_offset: -y,
+                ..GlyphPosition::default()
+            }
+        };
+    }
+
+    let Some(gpos) = face.gpos.as_ref() else {
+        return;
+    };
+    let Some((table, sys)) = face.layout_language_system(TableIndex::GPOS, script, None) else {
+        return;
+    };
+
+    let defaults = if horizontal {
+        GPOS_HORIZONTAL_FEATURES
+    } else {
+        GPOS_VERTICAL_FEATURES
+    };
+
+    for (index, lookup) in gpos.lookups.iter().enumerate() {
+        let index = index as LookupIndex;
+        let state = lookup_state(table, &sys, defaults, features, index);
+        if state == LookupState::Disabled {
+            continue;
+        }
+
+        let mut i = 0;
+        while i < infos.len() {
+            let glyph = infos[i].as_glyph();
+            if is_ignored(face, glyph, lookup.props)
+                || (state == LookupState::Ranged
+                    && !lookup_enabled(table, &sys, defaults, features, index, infos[i].cluster))
+            {
+                i += 1;
+                continue;
+            }
+
+            let next = (i + 1..infos.len())
+                .find(|&j| !is_ignored(face, infos[j].as_glyph(), lookup.props));
+
+            let mut advance = 1;
+            for subtable in &lookup.subtables {
+                match subtable {
+                    PositioningSubtable::Single(single) => {
+                        if let Some(record) = single_adjustment(single, glyph) {
+                            apply_value(&record, horizontal, &mut positions[i]);
+                            break;
+                        }
+                    }
+                    PositioningSubtable::Pair(pair) => {
+                        let Some(j) = next else {
+                            continue;
+                        };
+
+                        if let Some((record1, record2)) =
+                            pair_adjustment(pair, glyph, infos[j].as_glyph())
+                        {
+                            apply_value(&record1, horizontal, &mut positions[i]);
+                            apply_value(&record2, horizontal, &mut positions[j]);
+                            advance = if record2.is_empty() { j - i } else { j - i + 1 };
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            i += advance;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LookupState {
+    Disabled,
+    Enabled,
+    // Enabled by a feature with a cluster range, checked for each glyph.
+    Ranged,
+}
+
+fn lookup_state(
+    table: &LayoutTable,
+    sys: &LanguageSystem,
+    defaults: &[Tag],
+    features: &[Feature],
+    lookup: LookupIndex,
+) -> LookupState {
+    let mut state = LookupState::Disabled;
+    for index in sys.required_feature.into_iter().chain(sys.feature_indices) {
+        let Some(feature) = table.features.get(index) else {
+            continue;
+        };
+
+        if !feature.lookup_indices.into_iter().any(|i| i == lookup) {
+            continue;
+        }
+
+        if sys.required_feature == Some(index) {
+            return LookupState::Enabled;
+        }
+
+        if features
+            .iter()
+            .any(|f| f.tag == feature.tag && !f.is_global())
+        {
+            state = LookupState::Ranged;
+        } else if feature_enabled(feature.tag, defaults, features, 0) {
+            return LookupState::Enabled;
+        }
+    }
+
+    state
+}
+
+fn lookup_enabled(
+    table: &LayoutTable,
+    sys: &LanguageSystem,
+    defaults: &[Tag],
+    features: &[Feature],
+    lookup: LookupIndex,
+    cluster: u32,
+) -> bool {
+    sys.required_feature
+        .into_iter()
+        .chain(sys.feature_indices)
+        .any(|index| {
+            let Some(feature) = table.features.get(index) else {
+                return false;
+            };
+
+            feature.lookup_indices.into_iter().any(|i| i == lookup)
+                && (sys.required_feature == Some(index)
+                    || feature_enabled(feature.tag, defaults, features, cluster))
+        })
+}
+
+fn feature_enabled(tag: Tag, defaults: &[Tag], features: &[Feature], cluster: u32) -> bool {
+    let mut enabled = defaults.contains(&tag);
+    for feature in features {
+        if feature.tag == tag && feature.start <= cluster && cluster < feature.end {
+            enabled = feature.value != 0;
+        }
+    }
+
+    enabled
+}
+
+// Only the glyph classes are checked, mark attachment types and
+// mark filtering sets are not supported.
+fn is_ignored(face: &hb_font_t, glyph: GlyphId, lookup_props: u32) -> bool {
+    face.glyph_props(glyph) & lookup_props as u16 & lookup_flags::IGNORE_FLAGS != 0
+}
+
+fn single_substitute(subtable: &SingleSubstitution, glyph: GlyphId) -> Option<GlyphId> {
+    match *subtable {
+        SingleSubstitution::Format1 { coverage, delta } => {
+            coverage.get(glyph)?;
+            Some(GlyphId((i32::from(glyph.0) + i32::from(delta)) as u16))
+        }
+        SingleSubstitution::Format2 {
+            coverage,
+            substitutes,
+        } => substitutes.get(coverage.get(glyph)?),
+    }
+}
+
+fn single_adjustment<'a>(
+    subtable: &SingleAdjustment<'a>,
+    glyph: GlyphId,
+) -> Option<ValueRecord<'a>> {
+    match *subtable {
+        SingleAdjustment::Format1 { coverage, value } => {
+            coverage.get(glyph)?;
+            Some(value)
+        }
+        SingleAdjustment::Format2 { coverage, values } => values.get(coverage.get(glyph)?),
+    }
+}
+
+fn pair_adjustment<'a>(
+    subtable: &PairAdjustment<'a>,
+    first: GlyphId,
+    second: GlyphId,
+) -> Option<(ValueRecord<'a>, ValueRecord<'a>)> {
+    let index = subtable.coverage().get(first)?;
+    match subtable {
+        PairAdjustment::Format1 { sets, .. } => sets.get(index)?.get(second),
+        PairAdjustment::Format2 {
+            classes, matrix, ..
+        } => matrix.get((classes.0.get(first), classes.1.get(second))),
+    }
+}
+
+fn apply_value(record: &ValueRecord, horizontal: bool, pos: &mut GlyphPosition) {
+    pos.x_offset += i32::from(record.x_placement);
+    pos.y_offset += i32::from(record.y_placement);
+    if horizontal {
+        pos.x_advance += i32::from(record.x_advance);
+    } else {
+        // y_advance values grow downward but font-space grows upward, hence negation
+        pos.y_advance -= i32::from(record.y_advance);
+    }
+}
@@ -3,9 +3,10 @@ use core::ffi::CStr;
 use ttf_parser::{GlyphId, Tag};
 use wasmi::{self, AsContextMut, Caller, Config, Engine, Linker, Module, Store};
 
+use crate::BufferFlags;
+
 use super::{
     buffer::{hb_buffer_t, GlyphPosition},
-    face::hb_glyph_extents_t,
     hb_font_t, hb_glyph_info_t,
     ot_shape::{hb_ot_shape_context_t, shape_internal},
     ot_shape_plan::hb_ot_shape_plan_t,
@@ -21,6 +22,33 @@ pub(crate) fn shape_with_wasm(
     font: &hb_font_t,
     plan: &hb_ot_shape_plan_t,
     buffer: &mut hb_buffer_t,
+) -> Option<()> {
+    // Wasm shapers work in font units, like the font functions they call,
+    // so fractional positions are only scaled once they are done.
+    let scale = buffer.position_scale();
+    let flags = buffer.flags;
+    buffer
+        .flags
+        .remove(BufferFlags::PRODUCE_FRACTIONAL_POSITIONS);
+    let result = shape_with_wasm_unscaled(font, plan, buffer);
+    buffer.flags = flags;
+
+    if result.is_some() && scale != 1 {
+        for pos in &mut buffer.pos[..buffer.len] {
+            pos.x_advance *= scale;
+            pos.y_advance *= scale;
+            pos.x_offset *= scale;
+            pos.y_offset *= scale;
+        }
+    }
+
+    result
+}
+
+fn shape_with_wasm_unscaled(
+    font: &hb_font_t,
+    plan: &hb_ot_shape_plan_t,
+    buffer: &mut hb_buffer_t,
 ) -> Option<()> {
     // If font has no Wasm blob just return None to carry on as usual.
     let wasm_blob = font
@@ -160,12 +188,8 @@ fn font_get_glyph_extents(
 ) -> u32 {
     let memory = caller.get_export("memory").unwrap().into_memory().unwrap();
 
-    let mut glyph_extents = hb_glyph_extents_t::default();
-    let ret = caller
-        .data()
-        .font
-        .glyph_extents(GlyphId(glyph as u16), &mut glyph_extents);
-    if ret {
+    let glyph_extents = caller.data().font.glyph_extents(GlyphId(glyph as u16));
+    if let Some(glyph_extents) = glyph_extents {
         _ = memory.write(
             caller.as_context_mut(),
             extents as usize,
@@ -173,7 +197,7 @@ fn font_get_glyph_extents(
         );
     }
 
-    ret as u32
+    glyph_extents.is_some() as u32
 }
 
 // fn font_glyph_to_string(font: u32, glyph: u32, str: *const u8, len: u32);
@@ -304,7 +328,7 @@ fn font_copy_glyph_outline(
     if caller
         .data()
         .font
-        .outline_glyph(GlyphId(glyph as u16), &mut builder)
+        .glyph_outline(GlyphId(glyph as u16), &mut builder)
         .is_none()
     {
         return 0;
@@ -564,6 +588,7 @@ fn shape_with(
         face,
         buffer: caller.data_mut().buffer,
         target_direction,
+        substitute_only: false,
     });
 
     1
@@ -85,7 +85,7 @@ impl<'a> TextParser<'a> {
 
     pub fn consume_tag(&mut self) -> Option<hb_tag_t> {
         let tag = self.consume_bytes(|c| c.is_ascii_alphanumeric() || c == b'_');
-        if tag.len() > 4 {
+        if tag.is_empty() || tag.len() > 4 {
             return None;
         }
 
@@ -1,3 +1,4 @@
+use alloc::sync::Arc;
 use core::convert::TryFrom;
 
 pub use unicode_ccc::CanonicalCombiningClass;
@@ -318,11 +319,83 @@ impl GeneralCategoryExt for hb_unicode_general_category_t {
     }
 }
 
+/// Unicode character properties used during shaping.
+///
+/// Every method defaults to the built-in tables, so an implementation
+/// only needs to override the properties it wants to provide itself.
+///
+/// See [`UnicodeBuffer::set_unicode_funcs`](crate::UnicodeBuffer::set_unicode_funcs).
+pub trait UnicodeFuncs: Send + Sync {
+    /// Returns the general category of a character.
+    fn general_category(&self, c: char) -> hb_unicode_general_category_t {
+        c.general_category()
+    }
+
+    /// Returns the canonical combining class of a character.
+    fn combining_class(&self, c: char) -> u8 {
+        unicode_ccc::get_canonical_combining_class(c) as u8
+    }
+
+    /// Returns the Bidi mirroring glyph of a character, if any.
+    fn mirroring(&self, c: char) -> Option<char> {
+        c.mirrored()
+    }
+
+    /// Returns the script of a character.
+    fn script(&self, c: char) -> Script {
+        c.script()
+    }
+
+    /// Returns the canonical composition of two characters, if any.
+    fn compose(&self, a: char, b: char) -> Option<char> {
+        compose(a, b)
+    }
+
+    /// Returns the canonical decomposition of a character, if any.
+    ///
+    /// A singleton decomposition is returned with `'\0'` as the second character.
+    fn decompose(&self, ab: char) -> Option<(char, char)> {
+        decompose(ab)
+    }
+}
+
+struct hb_unicode_funcs_default_t;
+
+impl UnicodeFuncs for hb_unicode_funcs_default_t {}
+
+/// Returns the user-provided Unicode functions or the built-in ones.
+#[inline]
+pub fn hb_unicode_funcs_get(funcs: &Option<Arc<dyn UnicodeFuncs>>) -> &dyn UnicodeFuncs {
+    match funcs {
+        Some(funcs) => funcs.as_ref(),
+        None => &hb_unicode_funcs_default_t,
+    }
+}
+
+/// Maps a canonical combining class to the one used for mark reordering.
+pub fn hb_modified_combining_class(u: char, ccc: u8) -> u8 {
+    // Reorder SAKOT to ensure it comes after any tone marks.
+    if u == '\u{1A60}' {
+        return 254;
+    }
+
+    // Reorder PADMA to ensure it comes after any vowel marks.
+    if u == '\u{0FC6}' {
+        return 254;
+    }
+
+    // Reorder TSA -PHRU to reorder before U+0F74
+    if u == '\u{0F39}' {
+        return 127;
+    }
+
+    MODIFIED_COMBINING_CLASS[ccc as usize]
+}
+
 pub trait CharExt {
     fn script(self) -> Script;
     fn general_category(self) -> hb_unicode_general_category_t;
     fn space_fallback(self) -> hb_unicode_funcs_t::space_t;
-    fn modified_combining_class(self) -> u8;
     fn mirrored(self) -> Option<char>;
     fn is_emoji_extended_pictographic(self) -> bool;
     fn is_default_ignorable(self) -> bool;
@@ -525,28 +598,6 @@ impl CharExt for char {
         }
     }
 
-    fn modified_combining_class(self) -> u8 {
-        let u = self;
-
-        // Reorder SAKOT to ensure it comes after any tone marks.
-        if u == '\u{1A60}' {
-            return 254;
-        }
-
-        // Reorder PADMA to ensure it comes after any vowel marks.
-        if u == '\u{0FC6}' {
-            return 254;
-        }
-
-        // Reorder TSA -PHRU to reorder before U+0F74
-        if u == '\u{0F39}' {
-            return 127;
-        }
-
-        let k = unicode_ccc::get_canonical_combining_class(u);
-        MODIFIED_COMBINING_CLASS[k as usize]
-    }
-
     fn mirrored(self) -> Option<char> {
         unicode_bidi_mirroring::get_mirrored(self)
     }
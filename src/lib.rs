@@ -15,19 +15,37 @@ mod hb;
 pub use ttf_parser;
 
 pub use hb::buffer::hb_glyph_info_t as GlyphInfo;
-pub use hb::buffer::{GlyphBuffer, GlyphPosition, UnicodeBuffer};
-pub use hb::common::{script, Direction, Feature, Language, Script, Variation};
-pub use hb::face::hb_font_t as Face;
+pub use hb::buffer::{
+    GlyphArrays, GlyphBuffer, GlyphPosition, ShapeBudget, ShapeDiagnostics, UnicodeBuffer,
+};
+pub use hb::buffer_diff::GlyphBufferDiff;
+pub use hb::color::{ColorBrush, ColorFill, ColorStop};
+pub use hb::common::{script, Direction, Feature, InvalidVariation, Language, Script, Variation};
+pub use hb::face::{
+    hb_font_t as Face, hb_glyph_extents_t as GlyphExtents, AatFeature, AatFeatureSelector,
+    CmapSubtableInfo, FeatureNameIds, LookupCacheStats, NamedInstance, SizeParams,
+};
+pub use hb::ot_layout::TableIndex;
+pub use hb::ot_math::{MathConstant, MathGlyphAssembly, MathGlyphPart, MathGlyphVariant};
 pub use hb::ot_shape_plan::hb_ot_shape_plan_t as ShapePlan;
-pub use hb::shape::{shape, shape_with_plan};
+pub use hb::ot_shaper_custom::{ComplexShaper, NormalizeContext, ShapePlanner, ShaperBuffer};
+pub use hb::shape::{
+    measure, shape, shape_run, shape_substitute_only, shape_with_plan, split_shaped,
+};
+pub use hb::shape_simple::shape_into;
+pub use hb::unicode::{hb_unicode_general_category_t as GeneralCategory, UnicodeFuncs};
 
 bitflags::bitflags! {
     /// Flags for buffers.
     #[derive(Default, Debug, Clone, Copy)]
     pub struct BufferFlags: u32 {
         /// Indicates that special handling of the beginning of text paragraph can be applied to this buffer. Should usually be set, unless you are passing to the buffer only part of the text without the full context.
+        ///
+        /// As in HarfBuzz, this only allows inserting a dotted circle before a mark at the start of the buffer. Lookups never match outside the buffer either way, and joining with the text before the buffer is controlled by [`UnicodeBuffer::set_pre_context`].
         const BEGINNING_OF_TEXT             = 0x00000001;
         /// Indicates that special handling of the end of text paragraph can be applied to this buffer, similar to [`BufferFlags::BEGINNING_OF_TEXT`].
+        ///
+        /// Joining with the text after the buffer is controlled by [`UnicodeBuffer::set_post_context`].
         const END_OF_TEXT                   = 0x00000002;
         /// Indicates that characters with `Default_Ignorable` Unicode property should use the corresponding glyph from the font, instead of hiding them (done by replacing them with the space glyph and zeroing the advance width.) This flag takes precedence over [`BufferFlags::REMOVE_DEFAULT_IGNORABLES`].
         const PRESERVE_DEFAULT_IGNORABLES   = 0x00000004;
@@ -41,8 +59,18 @@ bitflags::bitflags! {
         const PRODUCE_UNSAFE_TO_CONCAT      = 0x00000040;
         /// Indicates that the `SAFE_TO_INSERT_TATWEEL` glyph-flag should be produced by the shaper. By default it will not be produced.
         const PRODUCE_SAFE_TO_INSERT_TATWEEL      = 0x00000040;
+        /// Indicates that combining marks should be kept in input order instead of being reordered by their combining class during normalization. Marks are still positioned by GPOS, but mark-to-mark positioning and fallback mark positioning expect marks in canonical order, so stacked marks can be positioned differently.
+        const PRESERVE_MARK_ORDER           = 0x00000100;
+        /// Indicates that the grapheme cluster of each glyph should be recorded, so that it can be queried using [`GlyphBuffer::grapheme_indices`]. Graphemes are determined from the input text, independent of the clusters merged by the font.
+        const PRODUCE_GRAPHEME_INDICES      = 0x00000200;
+        /// Indicates that glyph positions should keep the fractions of font units that would otherwise be rounded away, like those of `GPOS` device tables and variation deltas. Positions are then in 1/64 of a font unit, a position of 64 being one font unit. Values read from other tables, like `hmtx` and `HVAR` advances, are still whole font units.
+        const PRODUCE_FRACTIONAL_POSITIONS  = 0x00000400;
+        /// Indicates that `.notdef` glyphs, like those of characters that are not in the font, should be removed from the glyph string, like default-ignorables are by [`BufferFlags::REMOVE_DEFAULT_IGNORABLES`]. Useful when the missing characters are shaped with a fallback font instead.
+        const REMOVE_NOTDEF                 = 0x00000800;
+        /// Indicates that [`GlyphInfo::positioned`] should be produced. By default it will not be produced, since the positions have to be copied before positioning to detect changes.
+        const PRODUCE_POSITIONED_FLAGS      = 0x00001000;
         /// All currently defined flags
-        const DEFINED = 0x000000FF;
+        const DEFINED = 0x00001FFF;
     }
 }
 
@@ -83,3 +111,35 @@ bitflags::bitflags! {
         const DEFINED = 0b00111111;
     }
 }
+
+bitflags::bitflags! {
+    /// Differences found by [`GlyphBuffer::diff`].
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct BufferDiffFlags: u32 {
+        /// The buffers are equal.
+        const EQUAL                 = 0x0000;
+        /// The buffers have a different number of glyphs.
+        const LENGTH_MISMATCH       = 0x0002;
+        /// The buffer contains a `.notdef` glyph. Not a difference by itself.
+        const NOTDEF_PRESENT        = 0x0004;
+        /// A glyph id differs.
+        const CODEPOINT_MISMATCH    = 0x0010;
+        /// A cluster differs.
+        const CLUSTER_MISMATCH      = 0x0020;
+        /// Glyph flags differ.
+        const GLYPH_FLAGS_MISMATCH  = 0x0040;
+        /// A glyph position differs by more than the allowed fuzz.
+        const POSITION_MISMATCH     = 0x0080;
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags of a CPAL color palette.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PaletteFlags: u32 {
+        /// The palette is appropriate to use when displaying the font on a light background.
+        const USABLE_WITH_LIGHT_BACKGROUND = 0x00000001;
+        /// The palette is appropriate to use when displaying the font on a dark background.
+        const USABLE_WITH_DARK_BACKGROUND  = 0x00000002;
+    }
+}
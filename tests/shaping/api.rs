@@ -0,0 +1,2857 @@
+use std::str::FromStr;
+
+/// Loads the first face of a test font.
+///
+/// The font data is leaked, so that the face can outlive this function.
+fn load_face(path: &str) -> harfruzz::Face<'static> {
+    let font_data =
+        std::fs::read(path).unwrap_or_else(|e| panic!("Could not read {}: {}", path, e));
+    harfruzz::Face::from_slice(font_data.leak(), 0).unwrap()
+}
+
+/// Shapes `text` with comma-separated `features`, e.g. `"smcp,-liga"`.
+fn shape_text(face: &harfruzz::Face, text: &str, features: &str) -> harfruzz::GlyphBuffer {
+    let features: Vec<_> = features
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| harfruzz::Feature::from_str(s).unwrap())
+        .collect();
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    harfruzz::shape(face, &features, buffer)
+}
+
+#[test]
+fn shape_diagnostics() {
+    let face = load_face("tests/fonts/aots/gsub_context1_simple_f1.otf");
+
+    let glyph_buffer = shape_text(&face, "\u{0000}\u{0014}\u{0015}\u{0016}\u{0000}", "test");
+
+    // The font has neither GPOS nor a kern table.
+    assert_eq!(
+        *glyph_buffer.diagnostics(),
+        harfruzz::ShapeDiagnostics {
+            notdef_count: 2,
+            fallback_mark_positioning: true,
+            fallback_kerning: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn serialize_without_advances_and_positions() {
+    let face = load_face("tests/fonts/in-house/3998336402905b8be8301ef7f47cf7e050cbb1bd.ttf");
+
+    let glyph_buffer = shape_text(&face, "\u{1780}\u{17D2}\u{1798}\u{1780}", "");
+
+    let serialize = |flags| glyph_buffer.serialize(&face, flags);
+
+    assert_eq!(
+        serialize(harfruzz::SerializeFlags::default()),
+        "uni1780=0+636|uni17D21798=0@21,-26+0|uni1780=9+636"
+    );
+
+    // Offsets become absolute positions, advances are omitted.
+    assert_eq!(
+        serialize(harfruzz::SerializeFlags::NO_ADVANCES),
+        "uni1780=0|uni17D21798=0@657,-26|uni1780=9@636,0"
+    );
+
+    assert_eq!(
+        serialize(harfruzz::SerializeFlags::NO_POSITIONS),
+        "uni1780=0|uni17D21798=0|uni1780=9"
+    );
+
+    assert_eq!(
+        serialize(harfruzz::SerializeFlags::NO_POSITIONS | harfruzz::SerializeFlags::NO_CLUSTERS),
+        "uni1780|uni17D21798|uni1780"
+    );
+}
+
+#[test]
+fn custom_shaper() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const SUPS: harfruzz::ttf_parser::Tag = harfruzz::ttf_parser::Tag::from_bytes(b"sups");
+
+    #[derive(Default)]
+    struct CountingShaper {
+        collect_features: AtomicUsize,
+        override_features: AtomicUsize,
+        preprocess_text: AtomicUsize,
+        decompose: AtomicUsize,
+        compose: AtomicUsize,
+        reorder_marks: AtomicUsize,
+        setup_masks: AtomicUsize,
+        postprocess_glyphs: AtomicUsize,
+    }
+
+    impl harfruzz::ComplexShaper for CountingShaper {
+        fn collect_features(&self, planner: &mut harfruzz::ShapePlanner) {
+            assert_eq!(planner.script(), Some(harfruzz::script::LATIN));
+            self.collect_features.fetch_add(1, Ordering::SeqCst);
+            planner.add_feature(SUPS, 1);
+        }
+
+        fn override_features(&self, _: &mut harfruzz::ShapePlanner) {
+            self.override_features.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn preprocess_text(
+            &self,
+            _: &harfruzz::ShapePlan,
+            _: &harfruzz::Face,
+            buffer: &mut harfruzz::UnicodeBuffer,
+        ) {
+            self.preprocess_text.fetch_add(1, Ordering::SeqCst);
+
+            // Glyph infos still hold codepoints here.
+            for info in buffer.glyph_infos_mut() {
+                if info.glyph_id == 'a' as u32 {
+                    info.glyph_id = 'b' as u32;
+                }
+            }
+        }
+
+        fn decompose(&self, ctx: &harfruzz::NormalizeContext, ab: char) -> Option<(char, char)> {
+            self.decompose.fetch_add(1, Ordering::SeqCst);
+            ctx.decompose(ab)
+        }
+
+        fn compose(&self, ctx: &harfruzz::NormalizeContext, a: char, b: char) -> Option<char> {
+            self.compose.fetch_add(1, Ordering::SeqCst);
+            if (a, b) == ('q', '\u{0301}') {
+                Some('x')
+            } else {
+                ctx.compose(a, b)
+            }
+        }
+
+        fn reorder_marks(
+            &self,
+            _: &harfruzz::ShapePlan,
+            _: &mut harfruzz::ShaperBuffer,
+            _: usize,
+            _: usize,
+        ) {
+            self.reorder_marks.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn setup_masks(
+            &self,
+            plan: &harfruzz::ShapePlan,
+            _: &harfruzz::Face,
+            buffer: &mut harfruzz::ShaperBuffer,
+        ) {
+            self.setup_masks.fetch_add(1, Ordering::SeqCst);
+
+            // Only the first digit becomes superscript.
+            let mask = plan.feature_mask(SUPS);
+            assert_ne!(mask, 0);
+            if let Some(index) = buffer
+                .glyph_infos()
+                .iter()
+                .position(|info| info.glyph_id == '1' as u32)
+            {
+                buffer.set_feature_mask(index, mask, true);
+            }
+        }
+
+        fn postprocess_glyphs(
+            &self,
+            _: &harfruzz::ShapePlan,
+            _: &harfruzz::Face,
+            _: &mut harfruzz::GlyphBuffer,
+        ) {
+            self.postprocess_glyphs.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+    let shaper = Arc::new(CountingShaper::default());
+    face.register_shaper(harfruzz::script::LATIN, shaper.clone());
+
+    let glyph_buffer = shape_text(&face, "aq\u{0301}11", "");
+    assert_eq!(
+        glyph_buffer.serialize(&face, harfruzz::SerializeFlags::NO_POSITIONS),
+        "b=0|x=1|onesuperior=4|one=5"
+    );
+
+    assert_eq!(shaper.collect_features.load(Ordering::SeqCst), 1);
+    assert_eq!(shaper.override_features.load(Ordering::SeqCst), 1);
+    assert_eq!(shaper.preprocess_text.load(Ordering::SeqCst), 1);
+    assert!(shaper.decompose.load(Ordering::SeqCst) > 0);
+    assert_eq!(shaper.compose.load(Ordering::SeqCst), 1);
+    assert_eq!(shaper.reorder_marks.load(Ordering::SeqCst), 1);
+    assert_eq!(shaper.setup_masks.load(Ordering::SeqCst), 1);
+    assert_eq!(shaper.postprocess_glyphs.load(Ordering::SeqCst), 1);
+
+    // Other scripts are not affected.
+    shape_text(&face, "\u{0430}", "");
+    assert_eq!(shaper.preprocess_text.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn custom_shaper_invalid_char() {
+    struct InvalidShaper;
+
+    impl harfruzz::ComplexShaper for InvalidShaper {
+        fn preprocess_text(
+            &self,
+            _: &harfruzz::ShapePlan,
+            _: &harfruzz::Face,
+            buffer: &mut harfruzz::UnicodeBuffer,
+        ) {
+            buffer.glyph_infos_mut()[0].glyph_id = 0xD800;
+        }
+    }
+
+    let mut face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    let expected = shape_text(&face, "\u{FFFD}b", "");
+    assert!(!expected.diagnostics().shaping_failed);
+
+    face.register_shaper(harfruzz::script::LATIN, std::sync::Arc::new(InvalidShaper));
+
+    // The invalid codepoint is replaced instead of panicking.
+    let glyph_buffer = shape_text(&face, "ab", "");
+    assert_eq!(
+        glyph_buffer.serialize(&face, harfruzz::SerializeFlags::NO_CLUSTERS),
+        expected.serialize(&face, harfruzz::SerializeFlags::NO_CLUSTERS)
+    );
+    assert!(glyph_buffer.diagnostics().shaping_failed);
+}
+
+#[test]
+fn custom_unicode_funcs() {
+    struct LatinOnly;
+
+    impl harfruzz::UnicodeFuncs for LatinOnly {
+        fn script(&self, _: char) -> harfruzz::Script {
+            harfruzz::script::LATIN
+        }
+    }
+
+    let face = load_face("tests/fonts/rb_custom/AdobeBlank-Regular.ttf");
+
+    let shape = |buffer: harfruzz::UnicodeBuffer| {
+        harfruzz::shape(&face, &[], buffer).serialize(&face, harfruzz::SerializeFlags::NO_POSITIONS)
+    };
+
+    // The Indic shaper moves the pre-base matra in front of the consonant.
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str("\u{0915}\u{093F}");
+    assert_eq!(shape(buffer), "cid00320=0|cid00278=0");
+
+    // With every character reported as Latin, the default shaper is used.
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.set_unicode_funcs(std::sync::Arc::new(LatinOnly));
+    buffer.push_str("\u{0915}\u{093F}");
+    assert_eq!(shape(buffer), "cid00278=0|cid00320=0");
+}
+
+#[test]
+fn custom_unicode_funcs_arabic_context() {
+    struct MarkX;
+
+    impl harfruzz::UnicodeFuncs for MarkX {
+        fn general_category(&self, c: char) -> harfruzz::GeneralCategory {
+            match c {
+                'x' => harfruzz::GeneralCategory::NonspacingMark,
+                _ => harfruzz::GeneralCategory::OtherLetter,
+            }
+        }
+    }
+
+    let face = load_face("tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf");
+
+    let shape = |funcs: Option<std::sync::Arc<dyn harfruzz::UnicodeFuncs>>| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        if let Some(funcs) = funcs {
+            buffer.set_unicode_funcs(funcs);
+        }
+        buffer.set_pre_context("\u{0628}x");
+        buffer.push_str("\u{0628}");
+        buffer.guess_segment_properties();
+        harfruzz::shape(&face, &[], buffer).serialize(&face, harfruzz::SerializeFlags::NO_POSITIONS)
+    };
+
+    // The pre-context is looked up through the buffer's Unicode functions, so a
+    // transparent 'x' lets the beh join to the one before it.
+    assert_eq!(shape(None), "OneDotBelowNS=0|BehxSep=0");
+    assert_eq!(
+        shape(Some(std::sync::Arc::new(MarkX))),
+        "OneDotBelowNS=0|BehxFin=0"
+    );
+}
+
+#[test]
+fn glyph_flags() {
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str("AVfix");
+    buffer.set_flags(harfruzz::BufferFlags::PRODUCE_UNSAFE_TO_CONCAT);
+    let glyph_buffer = harfruzz::shape(&face, &[], buffer);
+
+    let flags: Vec<_> = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .map(|info| {
+            (
+                info.cluster,
+                info.unsafe_to_break(),
+                info.unsafe_to_concat(),
+            )
+        })
+        .collect();
+
+    // The kerned pair must not be broken between A and V.
+    // The ligature forms a single cluster, so it cannot be broken at all.
+    assert_eq!(
+        flags,
+        [
+            (0, false, false),
+            (1, true, true),
+            (2, false, true),
+            (4, false, true),
+        ]
+    );
+}
+
+#[test]
+fn split_shaped() {
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    let text = "AVfix";
+    let shape = || shape_text(&face, text, "");
+    let serialize = |buffer: &harfruzz::GlyphBuffer| {
+        buffer.serialize(&face, harfruzz::SerializeFlags::default())
+    };
+
+    assert_eq!(serialize(&shape()), "A=0+600|V=1+628|fi=2+643|x=4+592");
+
+    // Before the ligature is safe to break, so the glyphs are reused.
+    let (first, second) = harfruzz::split_shaped(&face, &[], text, shape(), 2);
+    assert_eq!(serialize(&first), "A=0+600|V=1+628");
+    assert_eq!(serialize(&second), "fi=2+643|x=4+592");
+
+    // Between the kerned pair it is not, so both halves are reshaped.
+    let (first, second) = harfruzz::split_shaped(&face, &[], text, shape(), 1);
+    assert_eq!(serialize(&first), "A=0+645");
+    assert_eq!(serialize(&second), "V=1+628|fi=2+643|x=4+592");
+
+    // Breaking inside the ligature splits it.
+    let (first, second) = harfruzz::split_shaped(&face, &[], text, shape(), 3);
+    assert_eq!(serialize(&first), "A=0+600|V=1+628|f=2+364");
+    assert_eq!(serialize(&second), "i=3+317|x=4+592");
+}
+
+#[test]
+fn split_shaped_grapheme_indices() {
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    let text = "AVfix";
+    let shape = || {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_flags(harfruzz::BufferFlags::PRODUCE_GRAPHEME_INDICES);
+        harfruzz::shape(&face, &[], buffer)
+    };
+    let indices = |buffer: &harfruzz::GlyphBuffer| buffer.grapheme_indices().collect::<Vec<_>>();
+
+    // The glyphs are reused, and the indices of each half start at its first grapheme.
+    let (first, second) = harfruzz::split_shaped(&face, &[], text, shape(), 2);
+    assert_eq!(indices(&first), [0, 1]);
+    assert_eq!(indices(&second), [0, 2]);
+
+    // The same holds when the halves are reshaped.
+    let (first, second) = harfruzz::split_shaped(&face, &[], text, shape(), 1);
+    assert_eq!(indices(&first), [0]);
+    assert_eq!(indices(&second), [0, 1, 3]);
+}
+
+#[test]
+fn split_shaped_keeps_context() {
+    let face = load_face("tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf");
+
+    let text = "\u{0628}\u{0628}";
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.set_pre_context("\u{0628}");
+    buffer.push_str(text);
+    buffer.set_post_context("\u{0628}");
+    let glyph_buffer = harfruzz::shape(&face, &[], buffer);
+
+    // The behs join, so both halves are reshaped and the outer context
+    // of the buffer keeps them in their medial forms.
+    let (first, second) = harfruzz::split_shaped(&face, &[], text, glyph_buffer, 2);
+    let serialize = |buffer: &harfruzz::GlyphBuffer| {
+        buffer.serialize(&face, harfruzz::SerializeFlags::NO_POSITIONS)
+    };
+    assert_eq!(serialize(&first), "OneDotBelowNS=0|BehxMed=0");
+    assert_eq!(serialize(&second), "OneDotBelowNS=2|BehxMed=2");
+}
+
+#[test]
+fn color_layers() {
+    use harfruzz::ttf_parser::{GlyphId, RgbaColor};
+
+    let face = load_face("tests/fonts/in-house/53374c7ca3657be37efde7ed02ae34229a56ae1f.ttf");
+
+    let layers: Vec<_> = face.color_layers(GlyphId(8), 0).collect();
+    assert_eq!(
+        layers,
+        [
+            (GlyphId(9), Some(RgbaColor::new(0, 0, 0, 255))),
+            (GlyphId(10), Some(RgbaColor::new(255, 0, 0, 255))),
+            (GlyphId(11), Some(RgbaColor::new(255, 204, 0, 255))),
+        ]
+    );
+
+    let layers: Vec<_> = face.color_layers(GlyphId(8), 1).collect();
+    assert_eq!(
+        layers,
+        [
+            (GlyphId(9), Some(RgbaColor::new(0, 0, 0, 255))),
+            (GlyphId(10), Some(RgbaColor::new(255, 240, 0, 255))),
+            (GlyphId(11), Some(RgbaColor::new(0, 35, 149, 255))),
+        ]
+    );
+
+    // Layer glyphs are not color glyphs themselves.
+    assert_eq!(face.color_layers(GlyphId(9), 0).count(), 0);
+}
+
+#[test]
+fn color_palettes() {
+    use harfruzz::ttf_parser::RgbaColor;
+    use harfruzz::PaletteFlags;
+
+    let face = load_face("tests/fonts/rb_custom/CpalPalettes.ttf");
+
+    assert_eq!(face.palette_count(), 2);
+    assert_eq!(
+        face.palette(0).collect::<Vec<_>>(),
+        [
+            RgbaColor::new(0x10, 0x20, 0x30, 0xFF),
+            RgbaColor::new(0xFF, 0xFF, 0xFF, 0x80)
+        ]
+    );
+    assert_eq!(
+        face.palette(1).collect::<Vec<_>>(),
+        [
+            RgbaColor::new(0xF0, 0xE0, 0xD0, 0xFF),
+            RgbaColor::new(0x00, 0x00, 0x00, 0xFF)
+        ]
+    );
+    assert_eq!(face.palette(2).count(), 0);
+
+    assert_eq!(
+        face.palette_flags(0),
+        PaletteFlags::USABLE_WITH_LIGHT_BACKGROUND
+    );
+    assert_eq!(
+        face.palette_flags(1),
+        PaletteFlags::USABLE_WITH_DARK_BACKGROUND
+    );
+
+    let name = |name_id| {
+        face.names()
+            .into_iter()
+            .find(|name| name.name_id == name_id)
+            .and_then(|name| name.to_string())
+    };
+    assert_eq!(
+        face.palette_name_id(1).and_then(name).as_deref(),
+        Some("Dark")
+    );
+    assert_eq!(
+        face.palette_entry_name_id(0).and_then(name).as_deref(),
+        Some("Background")
+    );
+    assert_eq!(face.palette_entry_name_id(1), None);
+}
+
+#[test]
+fn glyph_svg() {
+    use harfruzz::ttf_parser::GlyphId;
+
+    let face = load_face("tests/fonts/rb_custom/SvgDocuments.ttf");
+
+    assert_eq!(face.glyph_svg(GlyphId(0)), None);
+
+    // Glyphs 1 and 2 share a document.
+    let document = face.glyph_svg(GlyphId(2)).unwrap();
+    assert_eq!(face.glyph_svg(GlyphId(1)), Some(document));
+    let document = std::str::from_utf8(document).unwrap();
+    assert!(document.starts_with("<svg"));
+    assert!(document.contains(r#"id="glyph2""#));
+
+    // Compressed documents are returned as is.
+    let document = face.glyph_svg(GlyphId(3)).unwrap();
+    assert_eq!(document[..2], [0x1F, 0x8B]);
+}
+
+#[test]
+fn named_instances() {
+    use harfruzz::ttf_parser::Tag;
+    use harfruzz::Variation;
+
+    let font_data = std::fs::read("tests/fonts/rb_custom/NotoSansSinhala.subset1.otf").unwrap();
+    let mut face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+
+    let instances: Vec<_> = face.named_instances().collect();
+    assert_eq!(instances[0].subfamily_name_id, 289);
+    assert_eq!(instances[0].postscript_name_id, None);
+    assert_eq!(
+        instances[5].coordinates,
+        [
+            Variation {
+                tag: Tag::from_bytes(b"wght"),
+                value: 600.0
+            },
+            Variation {
+                tag: Tag::from_bytes(b"wdth"),
+                value: 100.0
+            },
+        ]
+    );
+
+    // The font has an `avar` table, so this checks that both paths map through it.
+    assert!(face.set_named_instance(5));
+    let coords = face.variation_coordinates().to_vec();
+    assert_ne!(coords[0].get(), 0);
+
+    let mut other = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    other.set_variations(&[Variation {
+        tag: Tag::from_bytes(b"wght"),
+        value: 600.0,
+    }]);
+    assert_eq!(other.variation_coordinates(), coords);
+
+    assert!(!face.set_named_instance(instances.len()));
+}
+
+#[test]
+fn avar_mapping() {
+    use harfruzz::ttf_parser::Tag;
+    use harfruzz::Variation;
+
+    fn coords(font_data: &[u8], value: f32) -> Vec<i16> {
+        let mut face = harfruzz::Face::from_slice(font_data, 0).unwrap();
+        face.set_variations(&[Variation {
+            tag: Tag::from_bytes(b"TEST"),
+            value,
+        }]);
+        face.variation_coordinates()
+            .iter()
+            .map(|c| c.get())
+            .collect()
+    }
+
+    fn find_table(font_data: &[u8], tag: &[u8; 4]) -> usize {
+        let count = u16::from_be_bytes([font_data[4], font_data[5]]) as usize;
+        (0..count)
+            .map(|i| 12 + i * 16)
+            .find(|offset| &font_data[*offset..*offset + 4] == tag)
+            .unwrap()
+    }
+
+    // The `avar` table maps 0.5 to 0.
+    let font_data = std::fs::read("tests/fonts/text-rendering-tests/TestAVAR.ttf").unwrap();
+    assert_eq!(coords(&font_data, 650.0), [0]);
+    assert_eq!(coords(&font_data, 900.0), [16384]);
+
+    // Without `avar` the coordinate is left as is.
+    let mut no_avar = font_data.clone();
+    let record = find_table(&no_avar, b"avar");
+    no_avar[record..record + 4].copy_from_slice(b"xvar");
+    assert_eq!(coords(&no_avar, 650.0), [8192]);
+
+    // Segment maps with decreasing coordinates are ignored.
+    let mut malformed = font_data.clone();
+    let offset = u32::from_be_bytes(malformed[record + 8..record + 12].try_into().unwrap());
+    // Map 0.5 to -0.5, which is less than the 0 to 0 mapping before it.
+    let to_coord = offset as usize + 10 + 3 * 4 + 2;
+    malformed[to_coord..to_coord + 2].copy_from_slice(&(-8192i16).to_be_bytes());
+    assert_eq!(coords(&malformed, 650.0), [8192]);
+
+    // `avar2` moves the axis by -0.25 at its maximum.
+    let font_data = std::fs::read("tests/fonts/rb_custom/Avar2.ttf").unwrap();
+    assert_eq!(coords(&font_data, 900.0), [12288]);
+    assert_eq!(coords(&font_data, 650.0), [6144]);
+}
+
+#[test]
+fn variation_metrics() {
+    use harfruzz::ttf_parser::{GlyphId, Tag};
+    use harfruzz::Variation;
+
+    let font_data = std::fs::read("tests/fonts/rb_custom/NotoSansSinhala.subset1.otf").unwrap();
+    let wght = Variation {
+        tag: Tag::from_bytes(b"wght"),
+        value: 340.0,
+    };
+    let wdth = Variation {
+        tag: Tag::from_bytes(b"wdth"),
+        value: 62.5,
+    };
+
+    // Metrics use the same coordinates as layout, which don't depend on the order
+    // of the variations, with `avar` applied once.
+    for variations in [[wght, wdth], [wdth, wght]] {
+        let mut face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+        face.set_variations(&variations);
+        let coords: Vec<_> = face
+            .variation_coordinates()
+            .iter()
+            .map(|c| c.get())
+            .collect();
+        assert_eq!(coords, [-4916, -16384]);
+        assert_eq!(face.glyph_h_advance(GlyphId(1)), 519);
+        assert_eq!(face.glyph_h_advance(GlyphId(2)), 680);
+    }
+}
+
+#[test]
+fn layout_features() {
+    use harfruzz::ttf_parser::Tag;
+    use harfruzz::{script, TableIndex};
+
+    let face = load_face("tests/fonts/rb_custom/SmallCaps.ttf");
+
+    let features = |table, script, language: Option<&str>| {
+        let language = language.map(|l| harfruzz::Language::from_str(l).unwrap());
+        face.layout_features(table, script, language.as_ref())
+            .collect::<Vec<_>>()
+    };
+
+    let liga = Tag::from_bytes(b"liga");
+    let smcp = Tag::from_bytes(b"smcp");
+    let kern = Tag::from_bytes(b"kern");
+
+    assert_eq!(
+        features(TableIndex::GSUB, Some(script::LATIN), None),
+        [liga, smcp]
+    );
+    assert_eq!(
+        features(TableIndex::GPOS, Some(script::LATIN), None),
+        [kern]
+    );
+    // Falls back to the default script.
+    assert_eq!(
+        features(TableIndex::GSUB, Some(script::ARABIC), None),
+        [liga, smcp]
+    );
+    assert_eq!(
+        features(TableIndex::GSUB, Some(script::LATIN), Some("tr")),
+        [liga, smcp]
+    );
+
+    let language = harfruzz::Language::from_str("tr").unwrap();
+    assert_eq!(
+        face.layout_required_feature(TableIndex::GSUB, Some(script::LATIN), Some(&language)),
+        Some(Tag::from_bytes(b"locl"))
+    );
+    assert_eq!(
+        face.layout_required_feature(TableIndex::GSUB, Some(script::LATIN), None),
+        None
+    );
+}
+
+#[test]
+fn would_substitute() {
+    use harfruzz::script;
+    use harfruzz::ttf_parser::Tag;
+
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    let glyphs = |text: &str| {
+        text.chars()
+            .map(|c| face.glyph_index(c).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let liga = Tag::from_bytes(b"liga");
+    let latin = Some(script::LATIN);
+    assert!(face.would_substitute(liga, latin, None, &glyphs("fi")));
+    assert!(!face.would_substitute(liga, latin, None, &glyphs("fx")));
+    assert!(!face.would_substitute(liga, latin, None, &glyphs("f")));
+    assert!(!face.would_substitute(Tag::from_bytes(b"dlig"), latin, None, &glyphs("fi")));
+    assert!(!face.would_substitute(liga, latin, None, &[]));
+}
+
+#[test]
+fn glyph_extents() {
+    use harfruzz::ttf_parser::{GlyphId, Tag};
+    use harfruzz::{GlyphExtents, Variation};
+
+    let mut face = load_face("tests/fonts/in-house/a232bb734d4c6c898a44506547d19768f0eba6a6.ttf");
+
+    assert_eq!(
+        face.glyph_extents(GlyphId(1)),
+        Some(GlyphExtents {
+            x_bearing: 55,
+            y_bearing: 546,
+            width: 1014,
+            height: -556,
+        })
+    );
+
+    face.set_variations(&[Variation {
+        tag: Tag::from_bytes(b"wght"),
+        value: 700.0,
+    }]);
+    assert_eq!(
+        face.glyph_extents(GlyphId(1)),
+        Some(GlyphExtents {
+            x_bearing: 45,
+            y_bearing: 556,
+            width: 1020,
+            height: -566,
+        })
+    );
+
+    // Empty glyphs have zero extents.
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+    let space = face.glyph_index(' ').unwrap();
+    assert_eq!(face.glyph_extents(space), Some(GlyphExtents::default()));
+}
+
+#[test]
+fn glyph_names() {
+    use harfruzz::ttf_parser::GlyphId;
+
+    let face = load_face("tests/fonts/rb_custom/SmallCaps.ttf");
+
+    // Standard Macintosh names and custom names from `post` version 2.0.
+    for name in [".notdef", "a", "space", "A.sc", "f_i"] {
+        let glyph = face.glyph_index_by_name(name).unwrap();
+        assert_eq!(face.glyph_name(glyph), Some(name));
+    }
+
+    assert_eq!(face.glyph_index_by_name("uni0061"), Some(GlyphId(1)));
+    assert_eq!(face.glyph_index_by_name("uni0063"), None);
+    assert_eq!(face.glyph_index_by_name("uni061"), None);
+    assert_eq!(face.glyph_index_by_name("gid10"), Some(GlyphId(10)));
+    assert_eq!(face.glyph_index_by_name("gid11"), None);
+    assert_eq!(face.glyph_index_by_name("gid+1"), None);
+    assert_eq!(face.glyph_index_by_name("cid1"), None);
+
+    // `post` version 3.0, the names come from the CFF charset.
+    let face = load_face("tests/fonts/text-rendering-tests/TestGPOSTwo.otf");
+    assert_eq!(face.glyph_name(GlyphId(1)), Some("uni25EF"));
+    assert_eq!(face.glyph_index_by_name("uni25EF"), Some(GlyphId(1)));
+}
+
+#[test]
+fn glyph_variation_index() {
+    use harfruzz::ttf_parser::GlyphId;
+
+    let face = load_face("tests/fonts/rb_custom/EmojiVariants.ttf");
+
+    // Heart has a text variant and uses the nominal glyph as the emoji variant.
+    assert_eq!(
+        face.glyph_variation_index('\u{2764}', '\u{FE0E}'),
+        Some(GlyphId(2))
+    );
+    assert_eq!(
+        face.glyph_variation_index('\u{2764}', '\u{FE0F}'),
+        Some(GlyphId(1))
+    );
+    assert_eq!(face.glyph_variation_index('\u{263A}', '\u{FE0E}'), None);
+    assert_eq!(
+        face.glyph_variation_index('\u{263A}', '\u{FE0F}'),
+        Some(GlyphId(4))
+    );
+}
+
+#[test]
+fn glyph_metrics() {
+    use harfruzz::ttf_parser::{GlyphId, Tag};
+    use harfruzz::Variation;
+
+    let mut face = load_face("tests/fonts/in-house/a232bb734d4c6c898a44506547d19768f0eba6a6.ttf");
+
+    assert_eq!(face.glyph_h_advance(GlyphId(1)), 1124);
+
+    // The HVAR delta of this glyph is -14 at the maximum weight.
+    face.set_variations(&[Variation {
+        tag: Tag::from_bytes(b"wght"),
+        value: 700.0,
+    }]);
+    assert_eq!(face.glyph_h_advance(GlyphId(1)), 1110);
+
+    // Without vmtx, glyphs are centered between the ascender and the descender.
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+    let glyph = face.glyph_index('a').unwrap();
+    let extents = face.glyph_extents(glyph).unwrap();
+    let advance = i32::from(face.ascender()) - i32::from(face.descender());
+    assert_eq!(face.glyph_v_advance(glyph), -advance);
+    assert_eq!(
+        face.glyph_v_origin(glyph),
+        (
+            face.glyph_h_advance(glyph) / 2,
+            extents.y_bearing + ((advance + extents.height) >> 1)
+        )
+    );
+}
+
+#[test]
+fn contextual_positioning() {
+    let face = load_face("tests/fonts/aots/gpos_context1_lookupflag_f1.otf");
+
+    let glyph_buffer = shape_text(
+        &face,
+        "\u{0000}\u{0014}\u{005A}\u{0015}\u{005B}\u{005C}\u{0016}\u{0000}",
+        "test",
+    );
+
+    let offsets: Vec<_> = glyph_buffer
+        .glyph_positions()
+        .iter()
+        .map(|pos| (pos.x_offset, pos.y_offset))
+        .collect();
+    // The nested lookup moves the bases, skipping the marks in between.
+    assert_eq!(
+        offsets,
+        [
+            (0, 0),
+            (20, 0),
+            (0, 0),
+            (20, 0),
+            (0, 0),
+            (0, 0),
+            (20, 0),
+            (0, 0)
+        ]
+    );
+    assert_eq!(glyph_buffer.diagnostics().fallback_lookups, 0);
+}
+
+#[test]
+fn lookup_limits() {
+    // A contextual lookup that recurses into itself.
+    let face = load_face("tests/fonts/rb_custom/RecursiveContext.ttf");
+
+    let glyph_buffer = shape_text(&face, &"ab".repeat(1000), "");
+    assert_eq!(glyph_buffer.len(), 2000);
+    assert_eq!(glyph_buffer.glyph_infos()[0].glyph_id, 1);
+    assert!(glyph_buffer.diagnostics().shaping_failed);
+
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str("ab");
+    buffer.set_max_ops(10);
+    let glyph_buffer = harfruzz::shape(&face, &[], buffer);
+    assert_eq!(glyph_buffer.len(), 2);
+    assert!(glyph_buffer.diagnostics().shaping_failed);
+
+    // Nested lookups are skipped when nesting is disabled.
+    let face = load_face("tests/fonts/aots/gsub_context1_simple_f1.otf");
+    let features = [harfruzz::Feature::from_str("test").unwrap()];
+    let shape = |max_nesting_level| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str("\u{0014}\u{0015}\u{0016}");
+        buffer.set_max_nesting_level(max_nesting_level);
+        let glyph_buffer = harfruzz::shape(&face, &features, buffer);
+        let glyphs: Vec<_> = glyph_buffer
+            .glyph_infos()
+            .iter()
+            .map(|info| info.glyph_id)
+            .collect();
+        (glyphs, glyph_buffer.diagnostics().shaping_failed)
+    };
+    assert_eq!(shape(64), (vec![60, 61, 62], false));
+    assert_eq!(shape(0), (vec![20, 21, 22], true));
+}
+
+#[test]
+fn shape_budget() {
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    let shape = |text: &str, budget: &harfruzz::ShapeBudget| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_budget(budget.clone());
+        harfruzz::shape(&face, &[], buffer)
+    };
+
+    let budget = harfruzz::ShapeBudget::new(1000);
+    let glyph_buffer = shape("fi", &budget);
+    assert_eq!(glyph_buffer.len(), 1);
+    assert!(!glyph_buffer.diagnostics().shaping_failed);
+    assert!(budget.remaining() < 1000);
+
+    // The budget is exhausted before `liga` is applied.
+    let glyph_buffer = shape(&"fi ".repeat(100_000), &budget);
+    assert_eq!(glyph_buffer.len(), 300_000);
+    assert!(glyph_buffer.diagnostics().shaping_failed);
+    assert_eq!(budget.remaining(), 0);
+
+    // The budget is exhausted while `liga` is applied, so only the first
+    // pairs are ligated.
+    let budget = harfruzz::ShapeBudget::new(40);
+    let glyph_buffer = shape(&"fi ".repeat(10), &budget);
+    assert_eq!(glyph_buffer.len(), 25);
+    assert_eq!(glyph_buffer.glyph_infos()[1].cluster, 2);
+    assert_eq!(glyph_buffer.glyph_infos()[23].cluster, 28);
+    assert!(glyph_buffer.diagnostics().shaping_failed);
+
+    let budget = harfruzz::ShapeBudget::new(1000);
+    budget.cancel();
+    let glyph_buffer = shape("fi", &budget);
+    assert_eq!(glyph_buffer.len(), 2);
+    assert!(glyph_buffer.diagnostics().shaping_failed);
+}
+
+#[test]
+fn shape_budget_single_subst_run() {
+    let face = load_face("tests/fonts/rb_custom/SmallCaps.ttf");
+
+    // `smcp` substitutes the whole run in one call, but each glyph is charged.
+    let budget = harfruzz::ShapeBudget::new(50);
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str(&"a".repeat(100));
+    buffer.set_budget(budget.clone());
+    let features = [harfruzz::Feature::from_str("smcp").unwrap()];
+    let glyph_buffer = harfruzz::shape(&face, &features, buffer);
+    let small_caps = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .take_while(|info| info.glyph_id == 6)
+        .count();
+    assert_eq!(small_caps, 50);
+    assert_eq!(glyph_buffer.glyph_infos()[99].glyph_id, 1);
+    assert!(glyph_buffer.diagnostics().shaping_failed);
+}
+
+#[test]
+fn shape_budget_aat() {
+    let face = load_face("tests/fonts/text-rendering-tests/TestMORXTwentyeight.ttf");
+
+    let budget = harfruzz::ShapeBudget::new(1000);
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str("AED");
+    buffer.set_budget(budget.clone());
+    let glyph_buffer = harfruzz::shape(&face, &[], buffer);
+    assert!(!glyph_buffer.diagnostics().shaping_failed);
+    assert!(budget.remaining() < 1000);
+
+    budget.cancel();
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str("AED");
+    buffer.set_budget(budget);
+    let glyph_buffer = harfruzz::shape(&face, &[], buffer);
+    assert!(glyph_buffer.diagnostics().shaping_failed);
+}
+
+#[test]
+fn shape_plan() {
+    use harfruzz::{Feature, ShapePlan, TableIndex, UnicodeBuffer};
+
+    let face = load_face("tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf");
+    let features = [Feature::from_str("-kern").unwrap()];
+
+    let buffer = |text: &str| {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        buffer
+    };
+    let plan_for = |buffer: &UnicodeBuffer| {
+        ShapePlan::new(
+            &face,
+            buffer.direction(),
+            Some(buffer.script()),
+            buffer.language().as_ref(),
+            &features,
+        )
+    };
+    let serialize = |buffer: harfruzz::GlyphBuffer| {
+        buffer.serialize(&face, harfruzz::SerializeFlags::default())
+    };
+
+    let arabic = "\u{0628}\u{0633}\u{0645}";
+    let plan = plan_for(&buffer(arabic));
+    assert!(plan.matches(&face, &buffer(arabic)));
+    assert_eq!(plan.user_features(), &features);
+
+    let expected = serialize(harfruzz::shape(&face, &features, buffer(arabic)));
+    for _ in 0..2 {
+        let glyph_buffer = harfruzz::shape_with_plan(&face, &plan, buffer(arabic));
+        assert_eq!(serialize(glyph_buffer), expected);
+    }
+
+    // A different script or direction requires a different plan.
+    let latin = buffer("abc");
+    assert!(!plan.matches(&face, &latin));
+    let latin_plan = plan_for(&latin);
+    assert!(latin_plan.matches(&face, &latin));
+    assert_ne!(
+        plan.lookups(TableIndex::GSUB).collect::<Vec<_>>(),
+        latin_plan.lookups(TableIndex::GSUB).collect::<Vec<_>>()
+    );
+
+    let mut ltr = buffer(arabic);
+    ltr.set_direction(harfruzz::Direction::LeftToRight);
+    assert!(!plan.matches(&face, &ltr));
+}
+
+#[test]
+fn feature_masks() {
+    let face = load_face("tests/fonts/rb_custom/SmallCaps.ttf");
+
+    let text = "bafi ".repeat(10_000);
+    let shape = |features: &str| shape_text(&face, &text, features);
+    let glyphs = |glyph_buffer: &harfruzz::GlyphBuffer| -> Vec<u32> {
+        glyph_buffer
+            .glyph_infos()
+            .iter()
+            .map(|i| i.glyph_id)
+            .collect()
+    };
+
+    let glyph_buffer = shape("");
+    let default = glyphs(&glyph_buffer);
+    assert_eq!(default.len(), 40_000);
+    assert_eq!(glyph_buffer.diagnostics().skipped_lookups, 0);
+
+    // Per-range features only apply to the glyphs in their range.
+    let glyph_buffer = shape("smcp[0:2],smcp[49997:50000]");
+    let ranged = glyphs(&glyph_buffer);
+    assert_eq!(ranged.len(), 40_001);
+    assert_eq!(ranged[..2], [7, 6]);
+    assert_eq!(ranged[2..39_997], default[2..39_997]);
+    assert_eq!(ranged[39_998..], [8, 9, 5]);
+    assert_eq!(glyph_buffer.diagnostics().skipped_lookups, 0);
+
+    // Lookups are skipped when no glyph has their feature enabled.
+    let glyph_buffer = shape("smcp[60000:],-liga[0:50000]");
+    assert_eq!(glyph_buffer.len(), 50_000);
+    assert_eq!(glyph_buffer.diagnostics().skipped_lookups, 2);
+}
+
+#[test]
+fn grapheme_indices() {
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    let shape = |flags: harfruzz::BufferFlags, cluster_level| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        // The family emoji is a single grapheme made of five characters.
+        buffer.push_str("ae\u{0301}\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}fi");
+        buffer.set_flags(flags);
+        buffer.set_cluster_level(cluster_level);
+        harfruzz::shape(&face, &[], buffer)
+    };
+
+    let flags = harfruzz::BufferFlags::PRODUCE_GRAPHEME_INDICES;
+    let glyph_buffer = shape(flags, harfruzz::BufferClusterLevel::Characters);
+    let clusters: Vec<_> = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .map(|i| i.cluster)
+        .collect();
+    assert_eq!(clusters, [0, 1, 4, 8, 11, 15, 18, 22]);
+    assert_eq!(
+        glyph_buffer.grapheme_indices().collect::<Vec<_>>(),
+        [0, 1, 2, 2, 2, 2, 2, 3]
+    );
+
+    // The `fi` ligature spans two graphemes and is assigned to the first one.
+    let glyph_buffer = shape(flags, harfruzz::BufferClusterLevel::MonotoneGraphemes);
+    let clusters: Vec<_> = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .map(|i| i.cluster)
+        .collect();
+    assert_eq!(clusters, [0, 1, 4, 4, 4, 4, 4, 22]);
+    assert_eq!(
+        glyph_buffer.grapheme_indices().collect::<Vec<_>>(),
+        [0, 1, 2, 2, 2, 2, 2, 3]
+    );
+
+    let glyph_buffer = shape(
+        harfruzz::BufferFlags::empty(),
+        harfruzz::BufferClusterLevel::Characters,
+    );
+    assert_eq!(glyph_buffer.grapheme_indices().count(), 0);
+}
+
+#[test]
+fn text_ranges() {
+    fn shape<'a>(font: &str, text: &'a str) -> Vec<&'a str> {
+        let face = load_face(font);
+        let glyph_buffer = shape_text(&face, text, "");
+        glyph_buffer
+            .text_ranges(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    // The `fi` ligature covers both characters.
+    let text = "fixe\u{0301}";
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            text
+        ),
+        ["fi", "x", "e\u{0301}"]
+    );
+
+    // A decomposed character is covered by each of its glyphs.
+    let text = "a\u{1E0B}\u{0323}";
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            text
+        ),
+        ["a", "\u{1E0B}\u{0323}", "\u{1E0B}\u{0323}"]
+    );
+
+    // Glyphs are in visual order, and the font draws the dot of beh as a
+    // separate glyph.
+    let text = "\u{0644}\u{0627}\u{0628}";
+    assert_eq!(
+        shape("tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf", text),
+        ["\u{0628}", "\u{0628}", "\u{0627}", "\u{0644}"]
+    );
+
+    // The reordered vowel sign shares the range of its syllable.
+    let text = "\u{0915}\u{093F}\u{0915}";
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/d629e7fedc0b350222d7987345fe61613fa3929a.ttf",
+            text
+        ),
+        ["\u{0915}\u{093F}", "\u{0915}\u{093F}", "\u{0915}"]
+    );
+}
+
+#[test]
+fn rvrn_threshold() {
+    use harfruzz::{ShapePlan, UnicodeBuffer, Variation};
+
+    let mut face = load_face("tests/fonts/in-house/d23d76ea0909c14972796937ba072b5a40c1e257.ttf");
+
+    let buffer = || {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str("r");
+        buffer.guess_segment_properties();
+        buffer
+    };
+    let plan_for = |face: &harfruzz::Face| {
+        let buffer = buffer();
+        ShapePlan::new(
+            face,
+            buffer.direction(),
+            Some(buffer.script()),
+            buffer.language().as_ref(),
+            &[],
+        )
+    };
+    let glyph_name = |face: &harfruzz::Face, plan: &ShapePlan| {
+        let glyph_buffer = harfruzz::shape_with_plan(face, plan, buffer());
+        let glyph = glyph_buffer.glyph_infos()[0].glyph_id;
+        face.glyph_name(harfruzz::ttf_parser::GlyphId(glyph as u16))
+            .map(str::to_string)
+    };
+
+    face.set_variations(&[Variation::from_str("FVTT=481").unwrap()]);
+    let base_plan = plan_for(&face);
+    assert_eq!(glyph_name(&face, &base_plan).as_deref(), Some("rvrn_base"));
+
+    // `rvrn` only substitutes past the threshold of its condition set. The
+    // lookups are selected when the plan is built, so it has to be rebuilt.
+    face.set_variations(&[Variation::from_str("FVTT=491").unwrap()]);
+    assert!(!base_plan.matches(&face, &buffer()));
+    let subst_plan = plan_for(&face);
+    assert!(subst_plan.matches(&face, &buffer()));
+    assert_eq!(
+        glyph_name(&face, &subst_plan).as_deref(),
+        Some("rvrn_subst")
+    );
+}
+
+#[test]
+fn feature_names() {
+    use harfruzz::ttf_parser::Tag;
+    use harfruzz::FeatureNameIds;
+
+    let face = load_face("tests/fonts/rb_custom/FeatureNames.ttf");
+
+    let ss01 = Tag::from_bytes(b"ss01");
+    assert_eq!(
+        face.feature_name_ids(ss01),
+        Some(FeatureNameIds {
+            label: Some(256),
+            tooltip: None,
+            sample: None,
+            first_param: None,
+            num_params: 0,
+        })
+    );
+    // The English name is preferred over the German one listed before it.
+    assert_eq!(face.feature_name(ss01).as_deref(), Some("Single-storey a"));
+
+    let cv01 = Tag::from_bytes(b"cv01");
+    let ids = face.feature_name_ids(cv01).unwrap();
+    assert_eq!(ids.label, Some(257));
+    assert_eq!(ids.tooltip, Some(258));
+    assert_eq!(ids.sample, None);
+    assert_eq!(ids.param_ids().collect::<Vec<_>>(), [259, 260]);
+    assert_eq!(face.feature_name(cv01).as_deref(), Some("Alternate a"));
+
+    let param_names: Vec<_> = ids
+        .param_ids()
+        .map(|id| {
+            face.names()
+                .into_iter()
+                .find(|name| name.name_id == id)
+                .and_then(|name| name.to_string())
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(param_names, ["Round", "Tailed"]);
+
+    // No params and not a stylistic set or character variant.
+    assert_eq!(face.feature_name_ids(Tag::from_bytes(b"ss02")), None);
+    assert_eq!(face.feature_name(Tag::from_bytes(b"liga")), None);
+}
+
+#[test]
+fn language_to_ot_tags() {
+    use harfruzz::ttf_parser::Tag;
+    use harfruzz::Language;
+
+    let tags = |language: &str| {
+        Language::from_str(language)
+            .unwrap()
+            .to_ot_tags()
+            .collect::<Vec<_>>()
+    };
+
+    let ot = |tags: &[&[u8; 4]]| tags.iter().map(|t| Tag::from_bytes(t)).collect::<Vec<_>>();
+
+    assert_eq!(tags("zh-Hant"), ot(&[b"ZHT "]));
+    assert_eq!(tags("zh-Hant-HK"), ot(&[b"ZHH "]));
+    assert_eq!(tags("sr-Cyrl"), ot(&[b"SRB "]));
+    assert_eq!(tags("en-US"), ot(&[b"ENG "]));
+    // Languages with several tags, in priority order.
+    assert_eq!(tags("zh-MO"), ot(&[b"ZHTM", b"ZHH "]));
+    assert_eq!(tags("ro-MD"), ot(&[b"MOL ", b"ROM "]));
+    assert_eq!(tags("hy"), ot(&[b"HYE0", b"HYE "]));
+    // Private use and unknown languages.
+    assert_eq!(tags("zh-x-hbotabc"), ot(&[b"ABC "]));
+    assert_eq!(tags("qaa"), ot(&[b"QAA "]));
+}
+
+#[test]
+fn script_to_ot_tags() {
+    use harfruzz::ttf_parser::Tag;
+    use harfruzz::{script, ShapePlan, TableIndex};
+
+    let tags = |script: harfruzz::Script| script.to_ot_tags().collect::<Vec<_>>();
+    assert_eq!(
+        tags(script::DEVANAGARI),
+        [
+            Tag::from_bytes(b"dev3"),
+            Tag::from_bytes(b"dev2"),
+            Tag::from_bytes(b"deva")
+        ]
+    );
+    assert_eq!(
+        tags(script::MYANMAR),
+        [Tag::from_bytes(b"mym2"), Tag::from_bytes(b"mymr")]
+    );
+    assert_eq!(tags(script::HIRAGANA), [Tag::from_bytes(b"kana")]);
+
+    let chosen_script = |font: &str| {
+        let face = load_face(font);
+        let plan = ShapePlan::new(
+            &face,
+            harfruzz::Direction::LeftToRight,
+            Some(script::DEVANAGARI),
+            None,
+            &[],
+        );
+        plan.chosen_script(TableIndex::GSUB)
+    };
+
+    // Fonts are matched using the newest tag they have a script for.
+    assert_eq!(
+        chosen_script("tests/fonts/in-house/46669c8860cbfea13562a6ca0d83130ee571137b.ttf"),
+        Some(Tag::from_bytes(b"dev3"))
+    );
+    assert_eq!(
+        chosen_script("tests/fonts/in-house/d629e7fedc0b350222d7987345fe61613fa3929a.ttf"),
+        Some(Tag::from_bytes(b"dev2"))
+    );
+    assert_eq!(
+        chosen_script("tests/fonts/in-house/b722a7d09e60421f3efbc706ad348ab47b88567b.ttf"),
+        Some(Tag::from_bytes(b"deva"))
+    );
+}
+
+#[test]
+fn shape_substitute_only() {
+    let face = load_face("tests/fonts/rb_custom/SmallCaps.ttf");
+
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str("fiba");
+    let substituted = harfruzz::shape_substitute_only(&face, &[], buffer);
+
+    // f_i, b and a, as GSUB left them.
+    let glyphs: Vec<_> = substituted
+        .glyph_infos()
+        .iter()
+        .map(|info| (info.glyph_id, info.cluster))
+        .collect();
+    assert_eq!(glyphs, [(10, 0), (2, 2), (1, 3)]);
+    assert!(substituted
+        .glyph_positions()
+        .iter()
+        .all(|pos| pos.x_advance == 0
+            && pos.y_advance == 0
+            && pos.x_offset == 0
+            && pos.y_offset == 0));
+
+    let mut buffer = substituted.clear();
+    buffer.push_str("fiba");
+    let shaped = harfruzz::shape(&face, &[], buffer);
+    let infos: Vec<_> = shaped.glyph_infos().iter().map(|i| i.glyph_id).collect();
+    assert_eq!(infos, [10, 2, 1]);
+    let advances: Vec<_> = shaped
+        .glyph_positions()
+        .iter()
+        .map(|pos| pos.x_advance)
+        .collect();
+    assert_eq!(advances, [550, 410, 450]);
+}
+
+#[test]
+fn substituted_and_positioned_glyphs() {
+    let face = load_face("tests/fonts/rb_custom/SmallCaps.ttf");
+
+    let shape = |flags| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str("fiba");
+        buffer.set_flags(flags);
+        let buffer = harfruzz::shape(&face, &[], buffer);
+        buffer
+            .glyph_infos()
+            .iter()
+            .map(|info| (info.glyph_id, info.substituted(), info.positioned()))
+            .collect::<Vec<_>>()
+    };
+
+    // `f i` form a ligature, `b` is kerned against `a`.
+    assert_eq!(
+        shape(harfruzz::BufferFlags::PRODUCE_POSITIONED_FLAGS),
+        [(10, true, false), (2, false, true), (1, false, false)]
+    );
+    // Positioned glyphs are only flagged on request.
+    assert_eq!(
+        shape(harfruzz::BufferFlags::empty()),
+        [(10, true, false), (2, false, false), (1, false, false)]
+    );
+}
+
+#[test]
+fn shape_into() {
+    use harfruzz::{Direction, Feature, GlyphInfo, GlyphPosition};
+
+    let face = load_face("tests/fonts/rb_custom/SmallCaps.ttf");
+
+    let mut infos = [GlyphInfo::default(); 8];
+    let mut positions = [GlyphPosition::default(); 8];
+
+    // The `f i` ligature is not supported, `b a` is kerned.
+    let len = harfruzz::shape_into(
+        &face,
+        &[],
+        Direction::Invalid,
+        "fiba",
+        &mut infos,
+        &mut positions,
+    )
+    .unwrap();
+    assert_eq!(len, 4);
+    let glyphs: Vec<_> = infos[..len]
+        .iter()
+        .zip(&positions[..len])
+        .map(|(info, pos)| (info.glyph_id, info.cluster, pos.x_advance))
+        .collect();
+    assert_eq!(glyphs, [(3, 0, 300), (4, 1, 300), (2, 2, 410), (1, 3, 450)]);
+
+    let features = [Feature::from_str("smcp[2:]").unwrap()];
+    let len = harfruzz::shape_into(
+        &face,
+        &features,
+        Direction::Invalid,
+        "fiba",
+        &mut infos,
+        &mut positions,
+    )
+    .unwrap();
+    let glyphs: Vec<_> = infos[..len]
+        .iter()
+        .zip(&positions[..len])
+        .map(|(info, pos)| (info.glyph_id, pos.x_advance))
+        .collect();
+    assert_eq!(glyphs, [(3, 300), (4, 300), (7, 510), (6, 520)]);
+
+    assert_eq!(
+        harfruzz::shape_into(
+            &face,
+            &[],
+            Direction::Invalid,
+            "fiba fiba",
+            &mut infos,
+            &mut positions
+        ),
+        Err(9)
+    );
+
+    // Vertical text uses the vertical advances and origins, like `shape`.
+    let face = load_face("tests/fonts/text-rendering-tests/TestGVAROne.ttf");
+    let len = harfruzz::shape_into(
+        &face,
+        &[],
+        Direction::TopToBottom,
+        "彌楓",
+        &mut infos,
+        &mut positions,
+    )
+    .unwrap();
+    let glyphs: Vec<_> = positions[..len]
+        .iter()
+        .map(|pos| (pos.x_advance, pos.y_advance, pos.x_offset, pos.y_offset))
+        .collect();
+    assert_eq!(glyphs, [(0, -1000, -500, -836), (0, -1000, -500, -844)]);
+
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str("彌楓");
+    buffer.set_direction(Direction::TopToBottom);
+    let glyph_buffer = harfruzz::shape(&face, &[], buffer);
+    let shaped: Vec<_> = glyph_buffer
+        .glyph_positions()
+        .iter()
+        .map(|pos| (pos.x_advance, pos.y_advance, pos.x_offset, pos.y_offset))
+        .collect();
+    assert_eq!(shaped, glyphs);
+}
+
+#[test]
+fn lookup_caches() {
+    let font_data = std::fs::read("tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf").unwrap();
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    let caches = face.lookup_caches();
+    let restored = harfruzz::Face::from_slice_with_lookup_caches(&font_data, 0, &caches).unwrap();
+    assert_eq!(restored.lookup_caches(), caches);
+
+    let shape = |face: &harfruzz::Face| {
+        let buffer = shape_text(face, "نستعلیق کی ترتیب", "");
+        buffer.serialize(face, harfruzz::SerializeFlags::default())
+    };
+    assert_eq!(shape(&restored), shape(&face));
+
+    // Caches of a different font are rejected.
+    let other_data =
+        std::fs::read("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf").unwrap();
+    let other = harfruzz::Face::from_slice(&other_data, 0).unwrap();
+    assert!(
+        harfruzz::Face::from_slice_with_lookup_caches(&font_data, 0, &other.lookup_caches())
+            .is_none()
+    );
+    assert!(harfruzz::Face::from_slice_with_lookup_caches(
+        &font_data,
+        0,
+        &caches[..caches.len() / 2]
+    )
+    .is_none());
+}
+
+#[test]
+fn face_for_glyphs() {
+    use harfruzz::ttf_parser::GlyphId;
+
+    let font_data = std::fs::read("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf").unwrap();
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    let shape = |face: &harfruzz::Face| shape_text(face, "abc", "");
+
+    let glyph_buffer = shape(&face);
+    let glyphs: Vec<_> = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .map(|info| GlyphId(info.glyph_id as u16))
+        .collect();
+    let lazy_face = harfruzz::Face::from_slice_for_glyphs(&font_data, 0, &glyphs).unwrap();
+    assert_eq!(
+        shape(&lazy_face).serialize(&lazy_face, harfruzz::SerializeFlags::default()),
+        glyph_buffer.serialize(&face, harfruzz::SerializeFlags::default())
+    );
+    // The lookups that can't apply are not built.
+    assert!(lazy_face.lookup_caches().len() < face.lookup_caches().len());
+}
+
+#[test]
+fn face_for_glyphs_outside_set() {
+    use harfruzz::ttf_parser::GlyphId;
+
+    let font_data = std::fs::read("tests/fonts/rb_custom/SmallCaps.ttf").unwrap();
+    let shape = |face: &harfruzz::Face| shape_text(face, "a", "smcp");
+
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    let expected = shape(&face);
+    assert_eq!(expected.glyph_infos()[0].glyph_id, 6);
+    assert_eq!(expected.diagnostics().fallback_lookups, 0);
+
+    // The `smcp` lookup isn't built, since `a` is not in the set,
+    // but it is still applied by the fallback path.
+    let lazy_face = harfruzz::Face::from_slice_for_glyphs(&font_data, 0, &[GlyphId(6)]).unwrap();
+    let glyph_buffer = shape(&lazy_face);
+    assert_eq!(
+        glyph_buffer.serialize(&lazy_face, harfruzz::SerializeFlags::default()),
+        expected.serialize(&face, harfruzz::SerializeFlags::default())
+    );
+    assert!(glyph_buffer.diagnostics().fallback_lookups > 0);
+}
+
+#[test]
+fn lookup_cache_stats() {
+    use harfruzz::TableIndex;
+
+    let font_data = std::fs::read("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf").unwrap();
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    let stats = face.lookup_cache_stats(TableIndex::GSUB).unwrap();
+    assert_eq!((stats.lookups, stats.ready_lookups), (14, 14));
+    assert_eq!(stats.subtables, 18);
+    // Single, ligature and chained context substitutions.
+    assert_eq!(stats.subtables_per_type[1], 8);
+    assert_eq!(stats.subtables_per_type[4], 3);
+    assert_eq!(stats.subtables_per_type[6], 6);
+
+    let stats = face.lookup_cache_stats(TableIndex::GPOS).unwrap();
+    // Single and pair positioning.
+    assert_eq!(stats.subtables_per_type[1], 5);
+    assert_eq!(stats.subtables_per_type[2], 3);
+
+    let face = harfruzz::Face::from_slice_for_glyphs(&font_data, 0, &[]).unwrap();
+    let stats = face.lookup_cache_stats(TableIndex::GPOS).unwrap();
+    assert_eq!(stats.vacant_lookups, stats.lookups);
+    assert_eq!(stats.subtables, 0);
+
+    let font_data =
+        std::fs::read("tests/fonts/text-rendering-tests/TestMORXFourtyone.ttf").unwrap();
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    assert!(face.lookup_cache_stats(TableIndex::GSUB).is_none());
+}
+
+#[test]
+fn attachment_points() {
+    use harfruzz::ttf_parser::GlyphId;
+
+    let face = load_face("tests/fonts/in-house/85fe0be440c64ac77699e21c2f1bd933a919167e.ttf");
+
+    let points = |glyph| face.attachment_points(GlyphId(glyph)).collect::<Vec<_>>();
+    assert_eq!(points(1), [0, 45]);
+    assert_eq!(points(2), [13]);
+    assert_eq!(points(3), [11]);
+    // Not in the coverage.
+    assert!(points(0).is_empty());
+    assert!(points(4).is_empty());
+
+    // No attachment list.
+    let face = load_face("tests/fonts/rb_custom/SmallCaps.ttf");
+    assert_eq!(face.attachment_points(GlyphId(1)).count(), 0);
+}
+
+#[test]
+fn glyph_class_override() {
+    use harfruzz::ttf_parser::gdef::GlyphClass;
+    use harfruzz::ttf_parser::GlyphId;
+
+    let mut face = load_face("tests/fonts/rb_custom/MisclassifiedMark.ttf");
+
+    let shape = |face: &harfruzz::Face| {
+        let glyphs = shape_text(face, "ab\u{301}", "");
+        glyphs
+            .glyph_positions()
+            .iter()
+            .map(|pos| (pos.x_advance, pos.x_offset, pos.y_offset))
+            .collect::<Vec<_>>()
+    };
+
+    // b is a mark in GDEF, so it is zero-width, skipped by the kerning
+    // and the accent attaches to a.
+    assert_eq!(shape(&face), [(450, 0, 0), (0, 0, 0), (0, -250, 200)]);
+
+    face.set_glyph_class_override(GlyphId(2), Some(GlyphClass::Base));
+    assert_eq!(shape(&face), [(400, 0, 0), (500, 0, 0), (0, -200, 200)]);
+
+    face.set_glyph_class_override(GlyphId(2), None);
+    assert_eq!(shape(&face), [(450, 0, 0), (0, 0, 0), (0, -250, 200)]);
+}
+
+#[test]
+fn guess_segment_properties() {
+    use harfruzz::{script, Direction, UnicodeBuffer};
+
+    let guess = |text: &str| {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        (buffer.direction(), buffer.script())
+    };
+
+    assert_eq!(
+        guess("\u{0628}\u{0633}\u{0645}"),
+        (Direction::RightToLeft, script::ARABIC)
+    );
+    // The first strong character decides.
+    assert_eq!(
+        guess("1. \u{0915}\u{093F} abc"),
+        (Direction::LeftToRight, script::DEVANAGARI)
+    );
+    // Only neutral characters.
+    assert_eq!(guess("123 (!)"), (Direction::LeftToRight, script::LATIN));
+
+    // Properties that are set are kept.
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str("\u{0628}\u{0633}\u{0645}");
+    buffer.set_direction(Direction::TopToBottom);
+    buffer.guess_segment_properties();
+    assert_eq!(buffer.direction(), Direction::TopToBottom);
+    assert_eq!(buffer.script(), script::ARABIC);
+}
+
+#[test]
+fn shape_guesses_latin_for_neutral_text() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct LatinShaper(AtomicUsize);
+
+    impl harfruzz::ComplexShaper for LatinShaper {
+        fn preprocess_text(
+            &self,
+            _: &harfruzz::ShapePlan,
+            _: &harfruzz::Face,
+            _: &mut harfruzz::UnicodeBuffer,
+        ) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let mut face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+    let shaper = Arc::new(LatinShaper::default());
+    face.register_shaper(harfruzz::script::LATIN, shaper.clone());
+
+    // `shape` guesses the same script as `guess_segment_properties`.
+    shape_text(&face, "123 (!)", "");
+    assert_eq!(shaper.0.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn shape_run() {
+    use harfruzz::Direction;
+
+    let face = load_face("tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf");
+
+    // A left-to-right paragraph with an Arabic run, which has a mark.
+    let text = "1 \u{0628}\u{064E}\u{0633}\u{0645} 2";
+    let range = 2..text.len() - 2;
+    let run = harfruzz::shape_run(&face, &[], text, range.clone(), Direction::RightToLeft);
+
+    let glyphs = |buffer: &harfruzz::GlyphBuffer| {
+        buffer
+            .glyph_infos()
+            .iter()
+            .zip(buffer.glyph_positions())
+            .map(|(info, pos)| {
+                (
+                    info.glyph_id,
+                    info.cluster,
+                    pos.x_advance,
+                    pos.x_offset,
+                    pos.y_offset,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    // Visual order, with the clusters of the paragraph. The mark is in the cluster of its base.
+    let clusters: Vec<_> = run.glyph_infos().iter().map(|info| info.cluster).collect();
+    assert_eq!(clusters, [8, 6, 2, 2, 2, 2]);
+
+    // Same glyphs as when shaping the run on its own.
+    let shaped = shape_text(&face, &text[range.clone()], "");
+    let expected: Vec<_> = glyphs(&shaped)
+        .into_iter()
+        .map(|(glyph, cluster, x_advance, x_offset, y_offset)| {
+            (
+                glyph,
+                cluster + range.start as u32,
+                x_advance,
+                x_offset,
+                y_offset,
+            )
+        })
+        .collect();
+    assert_eq!(glyphs(&run), expected);
+
+    // Left-to-right runs stay in logical order.
+    let run = harfruzz::shape_run(
+        &face,
+        &[],
+        text,
+        range.end..text.len(),
+        Direction::LeftToRight,
+    );
+    let clusters: Vec<_> = run.glyph_infos().iter().map(|info| info.cluster).collect();
+    assert_eq!(clusters, [10, 11]);
+}
+
+#[test]
+fn glyph_contour_point_cff() {
+    use harfruzz::ttf_parser::{GlyphId, Tag};
+    use harfruzz::{GlyphExtents, Variation};
+
+    let face = load_face("tests/fonts/rb_custom/CffPoints.otf");
+
+    let points = |face: &harfruzz::Face, glyph| {
+        (0..)
+            .map_while(|i| face.glyph_contour_point(GlyphId(glyph), i))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        face.glyph_extents(GlyphId(1)),
+        Some(GlyphExtents {
+            x_bearing: 100,
+            y_bearing: 200,
+            width: 300,
+            height: -200,
+        })
+    );
+    assert_eq!(
+        points(&face, 1),
+        [(100, 0), (400, 0), (400, 200), (100, 200)]
+    );
+
+    // The extents include the control points. The contour ends on its start point,
+    // which is not counted twice.
+    assert_eq!(
+        face.glyph_extents(GlyphId(2)),
+        Some(GlyphExtents {
+            x_bearing: 100,
+            y_bearing: 400,
+            width: 300,
+            height: -300,
+        })
+    );
+    assert_eq!(
+        points(&face, 2),
+        [(100, 100), (300, 100), (400, 200), (400, 400)]
+    );
+    assert!(points(&face, 0).is_empty());
+
+    // CFF2 points follow the blended outline and span the extents.
+    let mut face = load_face("tests/fonts/text-rendering-tests/AdobeVFPrototype-Subset.otf");
+    let glyph = face.glyph_index('$').unwrap();
+    let extents_of = |points: &[(i32, i32)]| {
+        let x_min = points.iter().map(|p| p.0).min().unwrap();
+        let x_max = points.iter().map(|p| p.0).max().unwrap();
+        let y_min = points.iter().map(|p| p.1).min().unwrap();
+        let y_max = points.iter().map(|p| p.1).max().unwrap();
+        GlyphExtents {
+            x_bearing: x_min,
+            y_bearing: y_max,
+            width: x_max - x_min,
+            height: y_min - y_max,
+        }
+    };
+
+    let default = points(&face, glyph.0);
+    assert_eq!(default[0], (260, 39));
+    assert_eq!(face.glyph_extents(glyph), Some(extents_of(&default)));
+
+    face.set_variations(&[Variation {
+        tag: Tag::from_bytes(b"wght"),
+        value: 900.0,
+    }]);
+    let varied = points(&face, glyph.0);
+    assert_eq!(varied[0], (258, 38));
+    assert_eq!(varied.len(), default.len());
+    assert_eq!(face.glyph_extents(glyph), Some(extents_of(&varied)));
+}
+
+#[test]
+fn glyph_contour_point_glyf() {
+    use harfruzz::ttf_parser::GlyphId;
+
+    let face = load_face("tests/fonts/rb_custom/ContourAnchors.ttf");
+
+    let points = |glyph| {
+        (0..)
+            .map_while(|i| face.glyph_contour_point(GlyphId(glyph), i))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(points(1), [(0, 0), (400, 0), (400, 600), (0, 600)]);
+    // A composite of the first glyph, shifted.
+    assert_eq!(points(2), [(100, 0), (500, 0), (500, 600), (100, 600)]);
+    assert_eq!(points(3), [(0, 0), (400, 0), (200, 500)]);
+    assert!(points(0).is_empty());
+    // A composite of the first and the third glyph, attached to point 2 of the first one.
+    let attached = [(400, 600), (800, 600), (600, 1100)];
+    assert_eq!(points(5)[..4], points(1));
+    assert_eq!(points(5)[4..], attached);
+    // Point numbers of the nested composite are relative to it.
+    assert_eq!(points(6)[..3], points(3));
+    assert_eq!(points(6)[3..], points(5));
+}
+
+#[test]
+fn glyph_contour_point_gvar() {
+    use harfruzz::ttf_parser::{GlyphId, Tag};
+    use harfruzz::Variation;
+
+    let mut face = load_face("tests/fonts/text-rendering-tests/TestGVARNine.ttf");
+    let glyph = GlyphId(2);
+    assert_eq!(face.glyph_contour_point(glyph, 0), Some((0, 250)));
+    assert_eq!(face.glyph_contour_point(glyph, 18), Some((700, 250)));
+
+    face.set_variations(&[Variation {
+        tag: Tag::from_bytes(b"TEST"),
+        value: 1.0,
+    }]);
+    assert_eq!(face.glyph_contour_point(glyph, 0), Some((0, 350)));
+    // Not in the tuple, so the delta is inferred from the neighbouring points.
+    assert_eq!(face.glyph_contour_point(glyph, 18), Some((700, 349)));
+}
+
+#[test]
+fn set_variations_ttf_parser_face() {
+    use harfruzz::ttf_parser::{GlyphId, Tag};
+    use harfruzz::Variation;
+
+    let mut face = load_face("tests/fonts/text-rendering-tests/TestGVARNine.ttf");
+    let default_bbox = face.glyph_bounding_box(GlyphId(2)).unwrap();
+
+    face.set_variations(&[Variation {
+        tag: Tag::from_bytes(b"TEST"),
+        value: 1.0,
+    }]);
+    // Reached through `Deref`, so it uses the coordinates of the `ttf_parser::Face`.
+    let ttfp_face: &harfruzz::ttf_parser::Face = &face;
+    assert_eq!(
+        ttfp_face.variation_coordinates(),
+        face.variation_coordinates()
+    );
+    assert_ne!(face.glyph_bounding_box(GlyphId(2)).unwrap(), default_bbox);
+}
+
+#[test]
+fn contour_anchors_fallback_lookups() {
+    let font_data = std::fs::read("tests/fonts/rb_custom/ContourAnchors.ttf").unwrap();
+    let mut face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    // No lookup is built, so they are all applied by the fallback path.
+    let mut lazy_face = harfruzz::Face::from_slice_for_glyphs(&font_data, 0, &[]).unwrap();
+    face.set_pixels_per_em(Some((12, 12)));
+    lazy_face.set_pixels_per_em(Some((12, 12)));
+
+    let shape = |face: &harfruzz::Face| shape_text(face, "a\u{0301}b\u{0301}c\u{0301}", "");
+
+    let glyph_buffer = shape(&lazy_face);
+    assert!(glyph_buffer.diagnostics().fallback_lookups > 0);
+    assert_eq!(
+        glyph_buffer.serialize(&lazy_face, harfruzz::SerializeFlags::default()),
+        shape(&face).serialize(&face, harfruzz::SerializeFlags::default())
+    );
+}
+
+#[test]
+fn collection_face_index() {
+    use harfruzz::{Direction, UnicodeBuffer};
+
+    // Faces 0, 1 and 2 are copies of these fonts.
+    let fonts = ["SmallCaps.ttf", "Cursive.ttf", "Vertical.ttf"];
+    let collection = std::fs::read("tests/fonts/rb_custom/Collection.ttc").unwrap();
+    assert_eq!(harfruzz::Face::face_count(&collection), 3);
+
+    let shape = |face: &harfruzz::Face, direction| {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str("aab");
+        buffer.set_direction(direction);
+        let glyphs = harfruzz::shape(face, &[], buffer);
+        glyphs
+            .glyph_infos()
+            .iter()
+            .zip(glyphs.glyph_positions())
+            .map(|(info, pos)| (info.glyph_id, pos.x_advance, pos.y_advance, pos.y_offset))
+            .collect::<Vec<_>>()
+    };
+
+    for (index, name) in fonts.iter().enumerate() {
+        let data = std::fs::read(format!("tests/fonts/rb_custom/{}", name)).unwrap();
+        let expected = harfruzz::Face::from_slice(&data, 0).unwrap();
+        assert_eq!(harfruzz::Face::face_count(&data), 1);
+
+        let face = harfruzz::Face::from_slice(&collection, index as u32).unwrap();
+        assert_eq!(face.face_index(), index as u32);
+        let ttfp_face = harfruzz::ttf_parser::Face::parse(&collection, index as u32).unwrap();
+        let from_face = harfruzz::Face::from_face(ttfp_face);
+        assert_eq!(from_face.face_index(), index as u32);
+
+        for direction in [Direction::LeftToRight, Direction::TopToBottom] {
+            let expected = shape(&expected, direction);
+            assert_eq!(shape(&face, direction), expected);
+            assert_eq!(shape(&from_face, direction), expected);
+        }
+    }
+
+    assert!(harfruzz::Face::from_slice(&collection, 3).is_none());
+}
+
+#[test]
+fn collection_face_index_shared_tables() {
+    use harfruzz::ttf_parser::{RawFace, Tag};
+
+    // A collection of two faces sharing the tables of a font, the second one
+    // without `GSUB`.
+    let data = std::fs::read("tests/fonts/rb_custom/SmallCaps.ttf").unwrap();
+    let records: Vec<_> = RawFace::parse(&data, 0)
+        .unwrap()
+        .table_records
+        .into_iter()
+        .collect();
+    let directory_len = |count: usize| 12 + 16 * count;
+    let first_len = directory_len(records.len());
+    let second_len = directory_len(records.len() - 1);
+    let data_start = (20 + first_len + second_len) as u32;
+
+    let mut collection = Vec::new();
+    collection.extend(b"ttcf");
+    collection.extend(0x0001_0000u32.to_be_bytes());
+    collection.extend(2u32.to_be_bytes());
+    collection.extend(20u32.to_be_bytes());
+    collection.extend((20 + first_len as u32).to_be_bytes());
+    for skipped in [None, Some(Tag::from_bytes(b"GSUB"))] {
+        let records: Vec<_> = records.iter().filter(|r| Some(r.tag) != skipped).collect();
+        collection.extend(&data[..4]);
+        collection.extend((records.len() as u16).to_be_bytes());
+        collection.extend([0; 6]);
+        for record in records {
+            collection.extend(record.tag.to_bytes());
+            collection.extend(record.check_sum.to_be_bytes());
+            collection.extend((data_start + record.offset).to_be_bytes());
+            collection.extend(record.length.to_be_bytes());
+        }
+    }
+    collection.extend(&data);
+
+    for index in 0..2 {
+        let ttfp_face = harfruzz::ttf_parser::Face::parse(&collection, index).unwrap();
+        assert_eq!(ttfp_face.tables().gsub.is_some(), index == 0);
+        let face = harfruzz::Face::from_face(ttfp_face);
+        assert_eq!(face.face_index(), index);
+    }
+}
+
+#[test]
+fn synthetic_bold() {
+    let mut face = load_face("tests/fonts/rb_custom/ContourAnchors.ttf");
+    let glyph = face.glyph_index('a').unwrap();
+    let h_advance = face.glyph_h_advance(glyph);
+    let v_advance = face.glyph_v_advance(glyph);
+    let extents = face.glyph_extents(glyph).unwrap();
+
+    // 0.05 and 0.02 of the 1000 units per em.
+    face.set_synthetic_bold(0.05, 0.02, false);
+    assert_eq!(face.synthetic_bold(), (0.05, 0.02, false));
+    assert_eq!(face.glyph_h_advance(glyph), h_advance + 50);
+    assert_eq!(face.glyph_v_advance(glyph), v_advance - 20);
+    assert_eq!(
+        face.glyph_extents(glyph).unwrap(),
+        harfruzz::GlyphExtents {
+            x_bearing: extents.x_bearing,
+            y_bearing: extents.y_bearing + 20,
+            width: extents.width + 50,
+            height: extents.height - 20,
+        }
+    );
+
+    // The outline grows to the left and right and the advances are kept.
+    // Like in HarfBuzz, the extents still grow at the top.
+    face.set_synthetic_bold(0.05, 0.02, true);
+    assert_eq!(face.glyph_h_advance(glyph), h_advance);
+    assert_eq!(face.glyph_v_advance(glyph), v_advance);
+    assert_eq!(
+        face.glyph_extents(glyph).unwrap(),
+        harfruzz::GlyphExtents {
+            x_bearing: extents.x_bearing - 25,
+            y_bearing: extents.y_bearing + 20,
+            width: extents.width + 50,
+            height: extents.height - 20,
+        }
+    );
+}
+
+#[test]
+fn synthetic_bold_vertical_origin() {
+    // The font has `vmtx` but no `VORG`, so the vertical origin is computed
+    // from the glyph extents, which must not include the synthetic bold.
+    let mut face = load_face("tests/fonts/text-rendering-tests/TestGVAROne.ttf");
+
+    let shape = |face: &harfruzz::Face| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str("A");
+        buffer.set_direction(harfruzz::Direction::TopToBottom);
+        harfruzz::shape(face, &[], buffer).serialize(face, harfruzz::SerializeFlags::GLYPH_EXTENTS)
+    };
+
+    // Like `synthetic_glyph_extents` and `hb_ot_get_glyph_v_origins` in HarfBuzz.
+    assert_eq!(shape(&face), "gid0=0@-263,-946+0,-1053<98,848,334,-1053>");
+
+    face.set_synthetic_bold(0.02, 0.05, false);
+    assert_eq!(shape(&face), "gid0=0@-273,-946+0,-1103<98,898,354,-1103>");
+
+    face.set_synthetic_bold(0.02, 0.05, true);
+    assert_eq!(shape(&face), "gid0=0@-263,-946+0,-1053<88,898,354,-1103>");
+}
+
+#[test]
+fn color_fills() {
+    use harfruzz::ttf_parser::colr::GradientExtend;
+    use harfruzz::ttf_parser::{GlyphId, RgbaColor, Tag, Transform};
+    use harfruzz::{ColorBrush, ColorFill, ColorStop, Variation};
+
+    let mut face = load_face("tests/fonts/rb_custom/ColorV1.ttf");
+    let foreground = RgbaColor::new(0, 0, 0, 255);
+    let red = RgbaColor::new(255, 0, 0, 255);
+    let blue = RgbaColor::new(0, 0, 255, 255);
+    let identity = Transform::default();
+    let (square, triangle) = (Some(GlyphId(1)), Some(GlyphId(2)));
+
+    let glyphs: Vec<_> = shape_text(&face, "abc", "")
+        .glyph_infos()
+        .iter()
+        .map(|info| GlyphId(info.glyph_id as u16))
+        .collect();
+
+    let gradient = |p0, red| ColorFill {
+        glyph: triangle,
+        glyph_transform: identity,
+        brush: ColorBrush::LinearGradient {
+            p0,
+            p1: (500.0, 0.0),
+            stops: vec![
+                ColorStop {
+                    offset: 0.0,
+                    color: red,
+                },
+                ColorStop {
+                    offset: 1.0,
+                    color: blue,
+                },
+            ],
+            extend: GradientExtend::Pad,
+        },
+        // Only the gradient is translated, not the triangle.
+        brush_transform: Transform::new_translate(100.0, 0.0),
+    };
+    let solid_square = ColorFill {
+        glyph: square,
+        glyph_transform: identity,
+        brush: ColorBrush::Solid(red),
+        brush_transform: identity,
+    };
+
+    assert_eq!(
+        face.color_fills(glyphs[0], 0, foreground),
+        Some(vec![solid_square.clone(), gradient((0.0, 0.0), red)])
+    );
+    // The foreground color, with the alpha of the paint.
+    assert_eq!(
+        face.color_fills(glyphs[1], 0, foreground),
+        Some(vec![ColorFill {
+            glyph: square,
+            glyph_transform: Transform::new_translate(0.0, -50.0),
+            brush: ColorBrush::Solid(RgbaColor::new(0, 0, 0, 128)),
+            brush_transform: Transform::new_translate(0.0, -50.0),
+        }])
+    );
+    // A multiply composite can't be flattened.
+    assert_eq!(face.color_fills(glyphs[2], 0, foreground), None);
+    assert_eq!(face.color_fills(GlyphId(1), 0, foreground), None);
+
+    // The first stop varies to 0.25 with half its alpha, the stops being normalized.
+    face.set_variations(&[Variation {
+        tag: Tag::from_bytes(b"wght"),
+        value: 900.0,
+    }]);
+    assert_eq!(
+        face.color_fills(glyphs[0], 0, foreground),
+        Some(vec![
+            solid_square,
+            gradient((125.0, 0.0), RgbaColor::new(255, 0, 0, 128)),
+        ])
+    );
+}
+
+#[test]
+fn glyph_buffer_diff() {
+    use harfruzz::BufferDiffFlags;
+
+    let font_data = std::fs::read("tests/fonts/rb_custom/GposDevice.ttf").unwrap();
+    let shape = |text: &str, ppem| {
+        let mut face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+        face.set_pixels_per_em(Some((ppem, ppem)));
+        shape_text(&face, text, "")
+    };
+
+    // The Device table of the A/V kerning pair adds 100 units to the advance of A at 10 ppem.
+    let adjusted = shape("AV", 10);
+    let reference = shape("AV", 13);
+
+    let diff = reference.diff(&reference, 0);
+    assert!(diff.is_equal());
+    assert_eq!(diff.flags, BufferDiffFlags::EQUAL);
+    assert_eq!(diff.to_string(), "buffers are equal");
+
+    let diff = adjusted.diff(&reference, 0);
+    assert_eq!(diff.flags, BufferDiffFlags::POSITION_MISMATCH);
+    assert_eq!(diff.index, Some(0));
+    assert_eq!(diff.glyph.unwrap().1.x_advance, 550);
+    assert_eq!(diff.reference.unwrap().1.x_advance, 450);
+    assert_eq!(
+        diff.to_string(),
+        "glyph 0 differs: id 1 cluster 0 advance 550,0 offset 0,0, \
+         expected id 1 cluster 0 advance 450,0 offset 0,0"
+    );
+
+    // Within the fuzz.
+    assert!(adjusted.diff(&reference, 100).is_equal());
+    assert!(!adjusted.diff(&reference, 99).is_equal());
+
+    let diff = shape("AVA", 13).diff(&reference, 0);
+    assert!(diff.flags.contains(BufferDiffFlags::LENGTH_MISMATCH));
+    assert_eq!(diff.index, Some(2));
+    assert_eq!(diff.glyph.unwrap().0.cluster, 2);
+    assert!(diff.reference.is_none());
+
+    let diff = shape("AX", 13).diff(&reference, 0);
+    assert!(diff
+        .flags
+        .contains(BufferDiffFlags::NOTDEF_PRESENT | BufferDiffFlags::CODEPOINT_MISMATCH));
+    assert!(!diff.flags.contains(BufferDiffFlags::LENGTH_MISMATCH));
+    assert_eq!(diff.index, Some(0));
+}
+
+#[test]
+fn glyph_to_unicode() {
+    use harfruzz::ttf_parser::GlyphId;
+
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    for c in ['a', 'Z', '!', 'é'] {
+        let glyph = face.glyph_index(c).unwrap();
+        assert_eq!(face.glyph_to_unicode(glyph), Some(c));
+    }
+
+    // Both U+00B5 MICRO SIGN and U+03BC GREEK SMALL LETTER MU map to `mu`.
+    let mu = face.glyph_index('\u{03BC}').unwrap();
+    assert_eq!(face.glyph_index('\u{00B5}'), Some(mu));
+    assert_eq!(face.glyph_to_unicode(mu), Some('\u{00B5}'));
+
+    assert_eq!(face.glyph_to_unicode(GlyphId(0)), None);
+    assert_eq!(face.glyph_to_unicode(GlyphId(u16::MAX)), None);
+
+    // Glyphs only reachable through layout have no character.
+    let face = load_face("tests/fonts/rb_custom/Directional.ttf");
+    let a_ltra = face.glyph_index_by_name("a.ltra").unwrap();
+    assert_eq!(face.glyph_to_unicode(a_ltra), None);
+}
+
+#[test]
+fn glyph_alternates() {
+    use harfruzz::ttf_parser::Tag;
+
+    let face = load_face("tests/fonts/rb_custom/Alternates.ttf");
+    let salt = Tag::from_bytes(b"salt");
+
+    let a = face.glyph_index('a').unwrap();
+    let names: Vec<_> = face
+        .glyph_alternates(a, salt)
+        .map(|glyph| face.glyph_name(glyph).unwrap())
+        .collect();
+    assert_eq!(names, ["a.alt1", "a.alt2", "a.alt3"]);
+
+    let b = face.glyph_index('b').unwrap();
+    assert_eq!(face.glyph_alternates(b, salt).count(), 0);
+    assert_eq!(
+        face.glyph_alternates(a, Tag::from_bytes(b"aalt")).count(),
+        0
+    );
+}
+
+#[test]
+fn preferred_cmap() {
+    let face = load_face("tests/fonts/rb_custom/Symbol.ttf");
+    assert_eq!(
+        face.preferred_cmap(),
+        Some(harfruzz::CmapSubtableInfo {
+            platform_id: 3,
+            encoding_id: 0,
+            format: 4,
+        })
+    );
+
+    let glyphs = shape_text(&face, "A\u{F041}\u{B7}", "");
+    let names: Vec<_> = glyphs
+        .glyph_infos()
+        .iter()
+        .map(|info| {
+            let glyph = harfruzz::ttf_parser::GlyphId(info.glyph_id as u16);
+            face.glyph_name(glyph).unwrap()
+        })
+        .collect();
+    assert_eq!(names, ["A.symbol", "A.symbol", "bullet"]);
+
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+    assert_eq!(
+        face.preferred_cmap(),
+        Some(harfruzz::CmapSubtableInfo {
+            platform_id: 3,
+            encoding_id: 1,
+            format: 4,
+        })
+    );
+}
+
+#[test]
+fn symbol_cmap_glyph_index() {
+    let face = load_face("tests/fonts/rb_custom/Symbol.ttf");
+    let a_symbol = face.glyph_index_by_name("A.symbol");
+    assert!(a_symbol.is_some());
+
+    // U+0041 is not in the symbol subtable and is retried at U+F041,
+    // even though the Unicode subtable maps it to `A`.
+    assert_eq!(face.glyph_index('\u{F041}'), a_symbol);
+    assert_eq!(face.glyph_index('A'), a_symbol);
+    assert_eq!(face.glyph_index('B'), None);
+    // Only U+0000..U+00FF are retried.
+    assert_eq!(face.glyph_index('\u{0141}'), None);
+}
+
+#[test]
+fn glyph_buffer_into_soa() {
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+
+    let glyphs = shape_text(&face, "Ta\u{0301}ffy AV", "");
+    let infos = glyphs.glyph_infos().to_vec();
+    let positions = glyphs.glyph_positions().to_vec();
+
+    let arrays = glyphs.into_soa();
+    assert_eq!(arrays.glyphs.len(), infos.len());
+    assert_eq!(arrays.clusters.len(), infos.len());
+    assert_eq!(arrays.x_advances.len(), infos.len());
+    assert_eq!(arrays.y_advances.len(), infos.len());
+    assert_eq!(arrays.x_offsets.len(), infos.len());
+    assert_eq!(arrays.y_offsets.len(), infos.len());
+
+    for (i, (info, pos)) in infos.iter().zip(&positions).enumerate() {
+        assert_eq!(arrays.glyphs[i], info.glyph_id);
+        assert_eq!(arrays.clusters[i], info.cluster);
+        assert_eq!(arrays.x_advances[i], pos.x_advance);
+        assert_eq!(arrays.y_advances[i], pos.y_advance);
+        assert_eq!(arrays.x_offsets[i], pos.x_offset);
+        assert_eq!(arrays.y_offsets[i], pos.y_offset);
+    }
+
+    // Existing arrays are refilled.
+    let mut reused = arrays.clone();
+    let glyphs = shape_text(&face, "AV", "");
+    glyphs.write_soa(&mut reused);
+    let expected = glyphs.into_soa();
+    assert_eq!(reused.glyphs, expected.glyphs);
+    assert_eq!(reused.clusters, expected.clusters);
+    assert_eq!(reused.x_advances, expected.x_advances);
+    assert_eq!(reused.y_advances, expected.y_advances);
+    assert_eq!(reused.x_offsets, expected.x_offsets);
+    assert_eq!(reused.y_offsets, expected.y_offsets);
+    assert_eq!(reused.glyphs.len(), 2);
+}
+
+#[test]
+fn cff2_private_dict_vsindex() {
+    use harfruzz::ttf_parser::{GlyphId, Tag};
+    use harfruzz::{GlyphExtents, Variation};
+
+    // The Private DICT selects the second item variation data, whose region
+    // peaks at wght 650, while the first one peaks at wght 900.
+    let mut face = load_face("tests/fonts/rb_custom/Cff2Vsindex.otf");
+    let box_at = |x: i32| GlyphExtents {
+        x_bearing: x,
+        y_bearing: 400,
+        width: 300,
+        height: -400,
+    };
+
+    for (wght, x) in [(400.0, 100), (525.0, 150), (650.0, 200), (900.0, 100)] {
+        face.set_variations(&[Variation {
+            tag: Tag::from_bytes(b"wght"),
+            value: wght,
+        }]);
+        assert_eq!(face.glyph_extents(GlyphId(1)), Some(box_at(x)), "{wght}");
+        assert_eq!(face.glyph_contour_point(GlyphId(1), 0), Some((x, 0)));
+        assert_eq!(face.glyph_contour_point(GlyphId(1), 3), Some((x, 400)));
+        assert_eq!(face.glyph_contour_point(GlyphId(1), 4), None);
+    }
+
+    assert_eq!(
+        face.glyph_extents(GlyphId(0)),
+        Some(GlyphExtents::default())
+    );
+}
+
+#[test]
+fn max_expansion_factor() {
+    let face = load_face("tests/fonts/rb_custom/MultipleSubst.ttf");
+    let a = face.glyph_index('a').unwrap().0 as u32;
+
+    let shape = |factor: Option<u32>| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str("aaaa");
+        if let Some(factor) = factor {
+            buffer.set_max_expansion_factor(factor);
+        }
+        harfruzz::shape(&face, &[], buffer)
+    };
+
+    let glyph_buffer = shape(None);
+    assert_eq!(glyph_buffer.len(), 12);
+    assert_eq!(glyph_buffer.diagnostics().skipped_expansions, 0);
+    let expanded: Vec<_> = glyph_buffer.glyph_infos()[..6]
+        .iter()
+        .map(|info| info.glyph_id)
+        .collect();
+
+    // Only two of the four substitutions fit in eight glyphs.
+    let glyph_buffer = shape(Some(2));
+    let glyphs: Vec<_> = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .map(|info| info.glyph_id)
+        .collect();
+    let clusters: Vec<_> = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .map(|info| info.cluster)
+        .collect();
+    assert_eq!(glyphs[..6], expanded[..]);
+    assert_eq!(glyphs[6..], [a, a]);
+    assert_eq!(clusters, [0, 0, 0, 1, 1, 1, 2, 3]);
+    assert_eq!(glyph_buffer.glyph_positions().len(), 8);
+    assert_eq!(glyph_buffer.diagnostics().skipped_expansions, 2);
+
+    let glyph_buffer = shape(Some(1));
+    assert_eq!(glyph_buffer.len(), 4);
+    assert_eq!(glyph_buffer.diagnostics().skipped_expansions, 4);
+}
+
+#[test]
+fn aat_features() {
+    let face = load_face("tests/fonts/rb_custom/AatSmallCaps.ttf");
+    let name = |name_id: u16| {
+        face.names()
+            .into_iter()
+            .find(|name| name.name_id == name_id)
+            .and_then(|name| name.to_string())
+            .unwrap()
+    };
+
+    let features: Vec<_> = face.aat_features().collect();
+    assert_eq!(features.len(), 2);
+
+    // Non-exclusive selectors are disabled by the following odd selector.
+    let ligatures = &features[0];
+    assert_eq!(ligatures.kind, 1);
+    assert_eq!(name(ligatures.name_id), "Ligatures");
+    assert!(!ligatures.exclusive);
+    assert_eq!(ligatures.default_index, None);
+    let selectors: Vec<_> = ligatures
+        .selectors
+        .iter()
+        .map(|selector| (name(selector.name_id), selector.enable, selector.disable))
+        .collect();
+    assert_eq!(
+        selectors,
+        [
+            ("Common Ligatures".to_string(), 2, 3),
+            ("Rare Ligatures".to_string(), 4, 5),
+        ]
+    );
+
+    // Exclusive selectors are disabled by selecting the default one.
+    let lower_case = &features[1];
+    assert_eq!(lower_case.kind, 37);
+    assert_eq!(name(lower_case.name_id), "Lower Case");
+    assert!(lower_case.exclusive);
+    assert_eq!(lower_case.default_index, Some(0));
+    let selectors: Vec<_> = lower_case
+        .selectors
+        .iter()
+        .map(|selector| (name(selector.name_id), selector.enable, selector.disable))
+        .collect();
+    assert_eq!(
+        selectors,
+        [
+            ("Default Lower Case".to_string(), 0, 0),
+            ("Small Capitals".to_string(), 1, 0),
+        ]
+    );
+
+    let face = load_face("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf");
+    assert_eq!(face.aat_features().count(), 0);
+}
+
+#[test]
+fn measure() {
+    let check = |path: &str, text: &str, direction: Option<harfruzz::Direction>| {
+        let face = load_face(path);
+        let features = [harfruzz::Feature::from_str("kern").unwrap()];
+        let buffer = || {
+            let mut buffer = harfruzz::UnicodeBuffer::new();
+            buffer.push_str(text);
+            if let Some(direction) = direction {
+                buffer.set_direction(direction);
+            }
+            buffer
+        };
+
+        let glyph_buffer = harfruzz::shape(&face, &features, buffer());
+        let positions = glyph_buffer.glyph_positions();
+        let advance = if direction == Some(harfruzz::Direction::TopToBottom) {
+            positions.iter().map(|pos| pos.y_advance).sum()
+        } else {
+            positions.iter().map(|pos| pos.x_advance).sum()
+        };
+        let measured = harfruzz::measure(&face, &features, buffer());
+        assert_eq!(measured, advance);
+        measured
+    };
+
+    let font = "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf";
+    assert!(check(font, "AVAV", None) > 0);
+    assert_eq!(check(font, "", None), 0);
+    assert!(
+        check(
+            "tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf",
+            "سلام",
+            None
+        ) > 0
+    );
+    assert!(
+        check(
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "ab",
+            Some(harfruzz::Direction::TopToBottom)
+        ) < 0
+    );
+}
+
+#[test]
+fn optical_size() {
+    let font_data = std::fs::read("tests/fonts/rb_custom/OpticalSize.ttf").unwrap();
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    let params = face.optical_size().unwrap();
+    assert_eq!(
+        params,
+        harfruzz::SizeParams {
+            design_size: 120,
+            subfamily_id: 2,
+            subfamily_name_id: Some(256),
+            range_start: 90,
+            range_end: 140,
+        }
+    );
+    let name = face
+        .names()
+        .into_iter()
+        .find(|name| Some(name.name_id) == params.subfamily_name_id)
+        .and_then(|name| name.to_string());
+    assert_eq!(name.as_deref(), Some("Text"));
+
+    // A design size outside of the range is rejected.
+    let mut font_data = font_data.clone();
+    let params = [0, 120, 0, 2, 1, 0, 0, 90, 0, 140];
+    let offset = font_data
+        .windows(params.len())
+        .position(|window| window == params)
+        .unwrap();
+    font_data[offset + 1] = 150;
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    assert_eq!(face.optical_size(), None);
+
+    // A range of a single size is accepted.
+    font_data[offset + 1] = 120;
+    font_data[offset + 7] = 120;
+    font_data[offset + 9] = 120;
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    let params = face.optical_size().unwrap();
+    assert_eq!((params.range_start, params.range_end), (120, 120));
+
+    // Only a design size.
+    let font_data =
+        std::fs::read("tests/fonts/text-rendering-tests/AdobeVFPrototype-Subset.otf").unwrap();
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    assert_eq!(
+        face.optical_size(),
+        Some(harfruzz::SizeParams {
+            design_size: 100,
+            subfamily_id: 0,
+            subfamily_name_id: None,
+            range_start: 0,
+            range_end: 0,
+        })
+    );
+
+    let font_data = std::fs::read("tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf").unwrap();
+    let face = harfruzz::Face::from_slice(&font_data, 0).unwrap();
+    assert_eq!(face.optical_size(), None);
+}
+
+#[test]
+fn math_constant() {
+    use harfruzz::MathConstant;
+
+    let face = load_face("tests/fonts/in-house/8d9c4b193808b8bde94389ba7831c1fc6f9e794e.ttf");
+    assert_eq!(face.math_constant(MathConstant::AxisHeight), Some(642));
+    assert_eq!(
+        face.math_constant(MathConstant::ScriptPercentScaleDown),
+        Some(80)
+    );
+    assert_eq!(
+        face.math_constant(MathConstant::DelimitedSubFormulaMinHeight),
+        Some(3072)
+    );
+    assert_eq!(
+        face.math_constant(MathConstant::RadicalDegreeBottomRaisePercent),
+        Some(60)
+    );
+
+    // axisHeight and the top accent attachment of `a` vary along wght.
+    let mut face = load_face("tests/fonts/rb_custom/Math.ttf");
+    let f = face.glyph_index('f').unwrap();
+    let a = face.glyph_index('a').unwrap();
+    assert_eq!(face.math_constant(MathConstant::AxisHeight), Some(250));
+    assert_eq!(face.math_constant(MathConstant::SpaceAfterScript), Some(41));
+    assert_eq!(
+        face.math_constant(MathConstant::FractionRuleThickness),
+        Some(40)
+    );
+    assert_eq!(
+        face.math_constant(MathConstant::RadicalKernAfterDegree),
+        Some(-555)
+    );
+    assert_eq!(
+        face.math_constant(MathConstant::ScriptScriptPercentScaleDown),
+        Some(50)
+    );
+    assert_eq!(
+        face.math_constant(MathConstant::DisplayOperatorMinHeight),
+        Some(1800)
+    );
+    assert_eq!(face.math_italic_correction(f), Some(60));
+    assert_eq!(face.math_italic_correction(a), None);
+    assert_eq!(face.math_top_accent_attachment(a), Some(260));
+    assert_eq!(face.math_top_accent_attachment(f), None);
+
+    face.set_variations(&[harfruzz::Variation::from_str("wght=900").unwrap()]);
+    assert_eq!(face.math_constant(MathConstant::AxisHeight), Some(350));
+    assert_eq!(face.math_top_accent_attachment(a), Some(280));
+    assert_eq!(face.math_italic_correction(f), Some(60));
+
+    face.set_variations(&[harfruzz::Variation::from_str("wght=650").unwrap()]);
+    assert_eq!(face.math_constant(MathConstant::AxisHeight), Some(300));
+
+    let face = load_face("tests/fonts/rb_custom/MultipleSubst.ttf");
+    assert_eq!(face.math_constant(MathConstant::AxisHeight), None);
+}
+
+#[test]
+fn math_glyph_variants() {
+    use harfruzz::{Direction, MathGlyphPart, MathGlyphVariant};
+
+    let face = load_face("tests/fonts/rb_custom/Math.ttf");
+    let paren = face.glyph_index('(').unwrap();
+    let overbrace = face.glyph_index('\u{23DE}').unwrap();
+    let glyph = |name| face.glyph_index_by_name(name).unwrap();
+    assert_eq!(face.math_min_connector_overlap(), Some(50));
+
+    let variants: Vec<_> = face
+        .math_glyph_variants(paren, Direction::TopToBottom)
+        .collect();
+    assert_eq!(
+        variants,
+        [
+            MathGlyphVariant {
+                glyph: paren,
+                advance: 1000
+            },
+            MathGlyphVariant {
+                glyph: glyph("parenleft.size1"),
+                advance: 1500
+            },
+            MathGlyphVariant {
+                glyph: glyph("parenleft.size2"),
+                advance: 2000
+            },
+        ]
+    );
+    assert_eq!(
+        face.math_glyph_variants(paren, Direction::LeftToRight)
+            .count(),
+        0
+    );
+
+    let assembly = face
+        .math_glyph_assembly(paren, Direction::BottomToTop)
+        .unwrap();
+    assert_eq!(assembly.italics_correction, 10);
+    assert_eq!(
+        assembly.parts,
+        [
+            MathGlyphPart {
+                glyph: glyph("parenleft.bottom"),
+                start_connector_length: 0,
+                end_connector_length: 150,
+                full_advance: 600,
+                extender: false,
+            },
+            MathGlyphPart {
+                glyph: glyph("parenleft.ext"),
+                start_connector_length: 150,
+                end_connector_length: 150,
+                full_advance: 500,
+                extender: true,
+            },
+            MathGlyphPart {
+                glyph: glyph("parenleft.top"),
+                start_connector_length: 150,
+                end_connector_length: 0,
+                full_advance: 600,
+                extender: false,
+            },
+        ]
+    );
+    assert_eq!(
+        face.math_glyph_assembly(paren, Direction::LeftToRight),
+        None
+    );
+
+    let variants: Vec<_> = face
+        .math_glyph_variants(overbrace, Direction::RightToLeft)
+        .map(|variant| variant.advance)
+        .collect();
+    assert_eq!(variants, [1000, 2000]);
+    assert_eq!(
+        face.math_glyph_variants(overbrace, Direction::TopToBottom)
+            .count(),
+        0
+    );
+    assert_eq!(
+        face.math_glyph_assembly(overbrace, Direction::LeftToRight),
+        None
+    );
+}
+
+#[test]
+fn fallback_mark_band() {
+    // The font has no GPOS, so that the marks get fallback positioning.
+    let face = load_face("tests/fonts/rb_custom/FallbackMarks.ttf");
+
+    let shape = |band: Option<(i32, i32)>| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str("a\u{0301}\u{0308}\u{0323}");
+        if let Some((min_y, max_y)) = band {
+            buffer.set_fallback_mark_band(min_y, max_y);
+        }
+        harfruzz::shape(&face, &[], buffer)
+    };
+    // Returns the bottom and top of the ink of each mark.
+    let ink = |glyph_buffer: &harfruzz::GlyphBuffer| -> Vec<(i32, i32)> {
+        glyph_buffer
+            .glyph_infos()
+            .iter()
+            .zip(glyph_buffer.glyph_positions())
+            .skip(1)
+            .map(|(info, pos)| {
+                let extents = face
+                    .glyph_extents(harfruzz::ttf_parser::GlyphId(info.glyph_id as u16))
+                    .unwrap();
+                let top = pos.y_offset + extents.y_bearing;
+                (top + extents.height, top)
+            })
+            .collect()
+    };
+
+    // Dot below, then acute and dieresis stacked above.
+    let unclamped = shape(None);
+    assert_eq!(ink(&unclamped), [(-162, -62), (562, 712), (774, 874)]);
+
+    // Both stacks are compressed, their outermost marks touching the band.
+    let clamped = shape(Some((-120, 800)));
+    assert_eq!(ink(&clamped), [(-120, -20), (526, 676), (700, 800)]);
+    let positions = |glyph_buffer: &harfruzz::GlyphBuffer| -> Vec<(i32, i32)> {
+        glyph_buffer
+            .glyph_positions()
+            .iter()
+            .map(|pos| (pos.x_advance, pos.x_offset))
+            .collect()
+    };
+    assert_eq!(positions(&clamped), positions(&unclamped));
+
+    // The dot below is beyond the band even next to the base, so it ends up on
+    // the band edge.
+    let clamped = shape(Some((0, 600)));
+    assert_eq!(ink(&clamped), [(0, 100), (430, 580), (500, 600)]);
+
+    // Marks within the band aren't moved.
+    let clamped = shape(Some((-1000, 1000)));
+    assert_eq!(ink(&clamped), ink(&unclamped));
+    let clamped = shape(Some((i32::MIN, i32::MAX)));
+    assert_eq!(ink(&clamped), ink(&unclamped));
+
+    // Swapped bounds are the same band.
+    let clamped = shape(Some((800, -120)));
+    assert_eq!(ink(&clamped), [(-120, -20), (526, 676), (700, 800)]);
+
+    // Offsets towards bands far from the marks saturate.
+    let clamped = shape(Some((i32::MAX, i32::MAX)));
+    assert_eq!(clamped.glyph_positions()[1].y_offset, i32::MAX);
+    let clamped = shape(Some((i32::MIN, i32::MIN)));
+    assert_eq!(clamped.glyph_positions()[3].y_offset, i32::MIN);
+}
+
+#[test]
+fn shape_substitute_only_morx() {
+    let face = load_face("tests/fonts/text-rendering-tests/TestMORXFourtyone.ttf");
+
+    // The font deletes glyphs, which must be removed like when shaping fully.
+    let glyphs = |buffer: &harfruzz::GlyphBuffer| -> Vec<_> {
+        buffer
+            .glyph_infos()
+            .iter()
+            .map(|info| (info.glyph_id, info.cluster))
+            .collect()
+    };
+    let text = "abcdefghijklmnopqrstuvwxyz";
+    let mut buffer = harfruzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    let substituted = harfruzz::shape_substitute_only(&face, &[], buffer);
+    let shaped = shape_text(&face, text, "");
+
+    assert_eq!(glyphs(&substituted), glyphs(&shaped));
+    assert!(substituted
+        .glyph_infos()
+        .iter()
+        .all(|info| info.glyph_id != 0xFFFF));
+    assert!(substituted
+        .glyph_positions()
+        .iter()
+        .all(|pos| pos.x_advance == 0 && pos.x_offset == 0));
+}
+
+#[test]
+fn preserve_mark_order() {
+    let shape = |font: &str, text: &str, flags: harfruzz::BufferFlags| {
+        let face = load_face(font);
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_flags(flags);
+        harfruzz::shape(&face, &[], buffer).serialize(&face, harfruzz::SerializeFlags::default())
+    };
+
+    // Marks are reordered by combining class, unless the flag is set.
+    let font = "tests/fonts/text-rendering-tests/TestGPOSOne.ttf";
+    let text = "\u{0078}\u{0301}\u{0323}";
+    assert_eq!(
+        shape(font, text, harfruzz::BufferFlags::empty()),
+        ".notdef=0+500|dotbelowcomb=0+0|acutecomb=0+0"
+    );
+    assert_eq!(
+        shape(font, text, harfruzz::BufferFlags::PRESERVE_MARK_ORDER),
+        ".notdef=0+500|acutecomb=0+0|dotbelowcomb=0+0"
+    );
+
+    // Kasra below and hamza below stay in input order, which changes how the
+    // font substitutes and positions them.
+    let font = "tests/fonts/in-house/21b7fb9c1eeae260473809fbc1fe330f66a507cd.ttf";
+    let text = "\u{0649}\u{0650}\u{0655}";
+    assert_eq!(
+        shape(font, text, harfruzz::BufferFlags::empty()),
+        "uni0650.small2=0@727,-774+0|uni0655=0@727,-209+0|uni0649=0+1566"
+    );
+    assert_eq!(
+        shape(font, text, harfruzz::BufferFlags::PRESERVE_MARK_ORDER),
+        "uni0655=0@727,-209+0|uni0650=0@166,0+0|uni0649=0+1566"
+    );
+}
+
+#[test]
+fn fractional_positions() {
+    let shape = |face: &harfruzz::Face, text: &str, flags: harfruzz::BufferFlags| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_flags(flags);
+        harfruzz::shape(face, &[], buffer).serialize(face, harfruzz::SerializeFlags::default())
+    };
+    let fractional = harfruzz::BufferFlags::PRODUCE_FRACTIONAL_POSITIONS;
+
+    // Positions are in 1/64 font units and keep the fraction of device deltas.
+    let mut face = load_face("tests/fonts/rb_custom/GposDevice.ttf");
+    assert_eq!(shape(&face, "AV", fractional), "gid1=0+28800|gid2=1+32000");
+    face.set_pixels_per_em(Some((11, 11)));
+    assert_eq!(shape(&face, "AV", fractional), "gid1=0+17164|gid2=1+32000");
+
+    // And of variation deltas.
+    let mut face = load_face("tests/fonts/text-rendering-tests/TestGPOSFour.ttf");
+    face.set_variations(&[harfruzz::Variation::from_str("wght=600").unwrap()]);
+    assert_eq!(
+        shape(&face, "\u{0634}\u{0652}", harfruzz::BufferFlags::empty()),
+        "uni0652=0@730,246+0|uni0634=0+1309"
+    );
+    assert_eq!(
+        shape(&face, "\u{0634}\u{0652}", fractional),
+        "uni0652=0@46722,15732+0|uni0634=0+83776"
+    );
+}
+
+#[test]
+fn remove_notdef() {
+    let face = load_face("tests/fonts/rb_custom/Alternates.ttf");
+    let shape = |text: &str, not_found: Option<u16>| {
+        let mut buffer = harfruzz::UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.set_flags(harfruzz::BufferFlags::REMOVE_NOTDEF);
+        if let Some(glyph) = not_found {
+            buffer.set_not_found_glyph(harfruzz::ttf_parser::GlyphId(glyph));
+        }
+        harfruzz::shape(&face, &[], buffer).serialize(&face, harfruzz::SerializeFlags::default())
+    };
+
+    // Clusters are UTF-8 offsets, the snowman taking three bytes.
+    assert_eq!(shape("a\u{2603}b", None), "a=0+450|b=4+460");
+    // Marks on a missing base stay attached to the glyph before them.
+    assert_eq!(shape("\u{2603}a\u{0301}b\u{2603}", None), "a=0+450|b=6+460");
+    assert_eq!(shape("\u{2603}", None), "");
+
+    // The not-found glyph isn't `.notdef` and is kept.
+    assert_eq!(shape("a\u{2603}b", Some(2)), "a=0+450|b=1+460|b=4+460");
+}
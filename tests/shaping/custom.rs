@@ -2,160 +2,2422 @@
 
 use crate::shape;
 
+#[test]
+fn aat_features_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AatSmallCaps.ttf",
+            "\u{0061}\u{0061}",
+            "",
+        ),
+        "a=0+450|\
+         a=1+450"
+    );
+}
+
+#[test]
+fn aat_features_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AatSmallCaps.ttf",
+            "\u{0061}\u{0061}",
+            "--features=smcp",
+        ),
+        "a.sc=0+550|\
+         a.sc=1+550"
+    );
+}
+
+#[test]
+fn aat_features_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AatSmallCaps.ttf",
+            "\u{0061}\u{0061}",
+            "--features=smcp[1:]",
+        ),
+        "a=0+450|\
+         a.sc=1+550"
+    );
+}
+
+#[test]
+fn aat_features_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AatSmallCaps.ttf",
+            "\u{0061}\u{0061}",
+            "--features=c2sc",
+        ),
+        "a=0+450|\
+         a=1+450"
+    );
+}
+
+#[test]
+fn aat_features_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AatSmallCapsDeprecated.ttf",
+            "\u{0061}\u{0061}",
+            "",
+        ),
+        "a=0+450|\
+         a=1+450"
+    );
+}
+
+#[test]
+fn aat_features_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AatSmallCapsDeprecated.ttf",
+            "\u{0061}\u{0061}",
+            "--features=smcp",
+        ),
+        "a.sc=0+550|\
+         a.sc=1+550"
+    );
+}
+
+#[test]
+fn alternates_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Alternates.ttf",
+            "\u{0061}\u{0062}\u{0061}",
+            "--features=salt",
+        ),
+        "a.alt1=0+470|\
+         b=1+460|\
+         a.alt1=2+470"
+    );
+}
+
+#[test]
+fn alternates_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Alternates.ttf",
+            "\u{0061}\u{0062}\u{0061}",
+            "--features=salt=2",
+        ),
+        "a.alt2=0+480|\
+         b=1+460|\
+         a.alt2=2+480"
+    );
+}
+
+#[test]
+fn alternates_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Alternates.ttf",
+            "\u{0061}\u{0062}\u{0061}",
+            "--features=salt[2:]=3",
+        ),
+        "a=0+450|\
+         b=1+460|\
+         a.alt3=2+490"
+    );
+}
+
+#[test]
+fn alternates_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Alternates.ttf",
+            "\u{0061}\u{0062}\u{0061}",
+            "--features=salt=4",
+        ),
+        "a=0+450|\
+         b=1+460|\
+         a=2+450"
+    );
+}
+
+#[test]
+fn arabic_context_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/65984dfce552a785f564422aadf4715fa07795ad.ttf",
+            "\u{0628}\u{0628}",
+            "",
+        ),
+        "uniFE90=1+821|\
+         uniFE91=0+301"
+    );
+}
+
+#[test]
+fn arabic_context_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/65984dfce552a785f564422aadf4715fa07795ad.ttf",
+            "\u{0628}\u{0628}",
+            "--unicodes-before=U+0644",
+        ),
+        "uniFE90=1+821|\
+         uniFE92=0+341"
+    );
+}
+
+#[test]
+fn arabic_context_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/65984dfce552a785f564422aadf4715fa07795ad.ttf",
+            "\u{0628}\u{0628}",
+            "--unicodes-before=U+0627",
+        ),
+        "uniFE90=1+821|\
+         uniFE91=0+301"
+    );
+}
+
+#[test]
+fn arabic_context_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/65984dfce552a785f564422aadf4715fa07795ad.ttf",
+            "\u{0628}\u{0628}",
+            "--unicodes-before=U+0644 --unicodes-after=U+0644",
+        ),
+        "uniFE92=1+341|\
+         uniFE92=0+341"
+    );
+}
+
+#[test]
+fn arabic_context_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/65984dfce552a785f564422aadf4715fa07795ad.ttf",
+            "\u{0628}\u{0628}",
+            "--bot --eot",
+        ),
+        "uniFE90=1+821|\
+         uniFE91=0+301"
+    );
+}
+
+#[test]
+fn arabic_context_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/65984dfce552a785f564422aadf4715fa07795ad.ttf",
+            "\u{0628}\u{0628}",
+            "--bot --unicodes-before=U+0644",
+        ),
+        "uniFE90=1+821|\
+         uniFE92=0+341"
+    );
+}
+
+#[test]
+fn contour_anchors_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/ContourAnchors.ttf",
+            "\u{0061}\u{0301}\u{0062}\u{0301}\u{0063}\u{0301}",
+            "",
+        ),
+        "a=0+500|\
+         acutecomb=0@-350,710+0|\
+         b=2+600|\
+         acutecomb=2@-450,710+0|\
+         c=4+500|\
+         acutecomb=4@-350,710+0"
+    );
+}
+
+#[test]
+fn contour_anchors_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/ContourAnchors.ttf",
+            "\u{0061}\u{0301}\u{0062}\u{0301}\u{0063}\u{0301}",
+            "--font-ppem=12",
+        ),
+        "a=0+500|\
+         acutecomb=0@-100,600+0|\
+         b=2+600|\
+         acutecomb=2@-100,600+0|\
+         c=4+500|\
+         acutecomb=4@-300,700+0"
+    );
+}
+
+#[test]
+fn contour_anchors_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/ContourAnchors.ttf",
+            "\u{0061}\u{0301}",
+            "--font-ppem=12,0",
+        ),
+        "a=0+500|\
+         acutecomb=0@-100,710+0"
+    );
+}
+
+#[test]
+fn cursive_flags_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Cursive.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0020}\u{0061}",
+            "--show-flags",
+        ),
+        "a=0+380|\
+         b=1@-20,100+460#1|\
+         c=2@-20,200+430#1|\
+         space=3+250|\
+         a=4+400"
+    );
+}
+
+#[test]
+fn cursive_flags_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Cursive.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0020}\u{0061}",
+            "--show-flags --features=-curs",
+        ),
+        "a=0+400|\
+         b=1+500|\
+         c=2+450|\
+         space=3+250|\
+         a=4+400"
+    );
+}
+
+#[test]
+fn cursive_flags_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Cursive.ttf",
+            "\u{0063}\u{0061}\u{0062}",
+            "--show-flags",
+        ),
+        "c=0+450|\
+         a=1+380|\
+         b=2@-20,100+480#1"
+    );
+}
+
+#[test]
+fn cursive_flags_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/NotoNastaliqUrdu-Regular.ttf",
+            "\u{0628}\u{0633}\u{0645}",
+            "--show-flags",
+        ),
+        "MeemFin=2+438#1|\
+         SeenMed.inT2outD2M=1@0,573+665#1|\
+         OneDotBelowNS=0@41,604+0|\
+         sp0=0+0|\
+         BehxIni.outT2tall=0@0,805+249"
+    );
+}
+
+#[test]
+fn default_ignorables_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0061}\u{200C}\u{0062}",
+            "",
+        ),
+        "a=0+582|\
+         space=1+0|\
+         b=2+629"
+    );
+}
+
+#[test]
+fn default_ignorables_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0061}\u{200C}\u{0062}",
+            "--preserve-default-ignorables",
+        ),
+        "a=0+582|\
+         .notdef=1+750|\
+         b=2+629"
+    );
+}
+
+#[test]
+fn default_ignorables_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0061}\u{200C}\u{0062}",
+            "--remove-default-ignorables",
+        ),
+        "a=0+582|\
+         b=2+629"
+    );
+}
+
+#[test]
+fn default_ignorables_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0061}\u{200C}\u{0062}",
+            "--invisible-glyph=1",
+        ),
+        "a=0+582|\
+         .null=1+0|\
+         b=2+629"
+    );
+}
+
+#[test]
+fn default_ignorables_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0066}\u{200D}\u{0069}",
+            "",
+        ),
+        "fi=0+643|\
+         space=0+0"
+    );
+}
+
+#[test]
+fn default_ignorables_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0066}\u{200D}\u{0069}",
+            "--remove-default-ignorables",
+        ),
+        "fi=0+643"
+    );
+}
+
+#[test]
+fn directional_features_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Directional.ttf",
+            "\u{0061}\u{0062}\u{005B}",
+            "--direction=ltr",
+        ),
+        "a.ltra=0+360|\
+         b.ltrm=1+380|\
+         bracketleft=2+330"
+    );
+}
+
+#[test]
+fn directional_features_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Directional.ttf",
+            "\u{0061}\u{0062}\u{005B}",
+            "--direction=rtl",
+        ),
+        "bracketleft.rtlm=2+390|\
+         b=1+350|\
+         a.rtla=0+370"
+    );
+}
+
+#[test]
+fn directional_features_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Directional.ttf",
+            "\u{0028}\u{0061}\u{0029}",
+            "--direction=rtl",
+        ),
+        "parenleft=2+310|\
+         a.rtla=1+370|\
+         parenright=0+320"
+    );
+}
+
+#[test]
+fn directional_features_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Directional.ttf",
+            "\u{0061}\u{005B}",
+            "--direction=rtl --features=-rtla,-rtlm",
+        ),
+        "bracketleft=1+330|\
+         a=0+340"
+    );
+}
+
+#[test]
+fn disable_features_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0066}\u{0069}",
+            "",
+        ),
+        "fi=0+643"
+    );
+}
+
+#[test]
+fn disable_features_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0066}\u{0069}",
+            "--features=liga=0",
+        ),
+        "f=0+364|\
+         i=1+317"
+    );
+}
+
+#[test]
+fn disable_features_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0066}\u{0069}",
+            "--features=-ccmp",
+        ),
+        "fi=0+643"
+    );
+}
+
+#[test]
+fn disable_features_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0066}\u{0069}\u{0020}\u{0066}\u{0069}",
+            "--features=-liga[1:2]",
+        ),
+        "f=0+364|\
+         i=1+317|\
+         space=2+291|\
+         fi=3+643"
+    );
+}
+
+#[test]
+fn disable_features_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SmallCaps.ttf",
+            "\u{0061}\u{0062}",
+            "--language=tr",
+        ),
+        "A.sc=0+520|\
+         B.sc=1+510"
+    );
+}
+
+#[test]
+fn disable_features_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SmallCaps.ttf",
+            "\u{0061}\u{0062}",
+            "--language=tr --features=-locl",
+        ),
+        "A.sc=0+520|\
+         B.sc=1+510"
+    );
+}
+
+#[test]
+fn disable_features_007() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SmallCaps.ttf",
+            "\u{0062}\u{0061}",
+            "--features=-kern",
+        ),
+        "b=0+450|\
+         a=1+450"
+    );
+}
+
+#[test]
+fn disable_features_008() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SmallCaps.ttf",
+            "\u{0062}\u{0061}",
+            "",
+        ),
+        "b=0+410|\
+         a=1+450"
+    );
+}
+
+#[test]
+fn dist_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Dist.ttf",
+            "\u{0915}\u{0915}\u{093E}",
+            "",
+        ),
+        "kadeva=0+570|\
+         kadeva=1+660|\
+         aavowelsigndeva=1+300"
+    );
+}
+
+#[test]
+fn dist_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Dist.ttf",
+            "\u{0915}\u{0915}\u{093E}",
+            "--features=-dist",
+        ),
+        "kadeva=0+570|\
+         kadeva=1+600|\
+         aavowelsigndeva=1+300"
+    );
+}
+
+#[test]
+fn dist_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Dist.ttf",
+            "\u{0915}\u{0915}\u{093E}",
+            "--features=-kern",
+        ),
+        "kadeva=0+600|\
+         kadeva=1+660|\
+         aavowelsigndeva=1+300"
+    );
+}
+
+#[test]
+fn dist_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Dist.ttf",
+            "\u{0915}\u{093E}",
+            "--script=deva",
+        ),
+        "kadeva=0+660|\
+         aavowelsigndeva=0+300"
+    );
+}
+
+#[test]
+fn dotted_circle_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/1a5face3fcbd929d228235c2f72bbd6f8eb37424.ttf",
+            "\u{0946}",
+            "",
+        ),
+        "uni25CC=0+510|\
+         eshortvowelsigndeva=0+0"
+    );
+}
+
+#[test]
+fn dotted_circle_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/1a5face3fcbd929d228235c2f72bbd6f8eb37424.ttf",
+            "\u{0946}",
+            "--do-not-insert-dotted-circle",
+        ),
+        "eshortvowelsigndeva=0+0"
+    );
+}
+
+#[test]
+fn dotted_circle_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/1a5face3fcbd929d228235c2f72bbd6f8eb37424.ttf",
+            "\u{0301}\u{0905}",
+            "--bot",
+        ),
+        "uni25CC=0+510|\
+         .notdef=0+600|\
+         adeva=1+764"
+    );
+}
+
+#[test]
+fn dotted_circle_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/1a5face3fcbd929d228235c2f72bbd6f8eb37424.ttf",
+            "\u{0301}\u{0905}",
+            "--bot --do-not-insert-dotted-circle",
+        ),
+        ".notdef=0+600|\
+         adeva=1+764"
+    );
+}
+
+#[test]
+fn dotted_circle_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/1a5face3fcbd929d228235c2f72bbd6f8eb37424.ttf",
+            "\u{0301}\u{0905}",
+            "",
+        ),
+        ".notdef=0+600|\
+         adeva=1+764"
+    );
+}
+
+#[test]
+fn dotted_circle_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0301}\u{0061}",
+            "--bot",
+        ),
+        "acutecomb=0+0|\
+         a=1+582"
+    );
+}
+
+#[test]
+fn emoji_variants_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/EmojiVariants.ttf",
+            "\u{2764}\u{FE0E}",
+            "",
+        ),
+        "heart.text=0+800"
+    );
+}
+
+#[test]
+fn emoji_variants_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/EmojiVariants.ttf",
+            "\u{2764}\u{FE0F}",
+            "",
+        ),
+        "heart=0+700"
+    );
+}
+
+#[test]
+fn emoji_variants_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/EmojiVariants.ttf",
+            "\u{263A}\u{FE0F}",
+            "",
+        ),
+        "smile.emoji=0+800"
+    );
+}
+
+#[test]
+fn emoji_variants_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/EmojiVariants.ttf",
+            "\u{263A}\u{FE0E}",
+            "",
+        ),
+        "smile=0+700"
+    );
+}
+
+#[test]
+fn emoji_variants_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/EmojiVariants.ttf",
+            "\u{2764}\u{263A}",
+            "",
+        ),
+        "heart=0+700|\
+         smile=1+700"
+    );
+}
+
+#[test]
+fn fallback_marks_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/8228d035fcd65d62ec9728fb34f42c63be93a5d3.ttf",
+            "\u{0078}\u{0301}\u{0301}",
+            "",
+        ),
+        "x=0+1030|\
+         acutecomb=0@-19,-27+0|\
+         acutecomb=0@-19,453+0"
+    );
+}
+
+#[test]
+fn fallback_marks_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/8228d035fcd65d62ec9728fb34f42c63be93a5d3.ttf",
+            "\u{0058}\u{0301}\u{0301}",
+            "",
+        ),
+        "X=0+1295|\
+         acutecomb=0@-151,320+0|\
+         acutecomb=0@-151,800+0"
+    );
+}
+
+#[test]
+fn feature_ranges_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SmallCaps.ttf",
+            "\u{0061}\u{0062}\u{0020}\u{0061}\u{0062}",
+            "--features=smcp[3:]",
+        ),
+        "a=0+450|\
+         b=1+450|\
+         space=2+250|\
+         A.sc=3+520|\
+         B.sc=4+510"
+    );
+}
+
+#[test]
+fn feature_ranges_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SmallCaps.ttf",
+            "\u{0066}\u{0069}",
+            "--features=smcp[0:1]",
+        ),
+        "F.sc=0+480|\
+         i=1+300"
+    );
+}
+
+#[test]
+fn feature_ranges_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SmallCaps.ttf",
+            "\u{0066}\u{0069}",
+            "--features=liga=0,liga[0:1]",
+        ),
+        "f=0+300|\
+         i=1+300"
+    );
+}
+
+#[test]
+fn feature_ranges_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SmallCaps.ttf",
+            "\u{0066}\u{0069}\u{0020}\u{0066}\u{0069}",
+            "--features=liga=0,liga[3:]",
+        ),
+        "f=0+300|\
+         i=1+300|\
+         space=2+250|\
+         f_i=3+550"
+    );
+}
+
+#[test]
+fn feature_variations_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/FeatureVariations.ttf",
+            "\u{0041}\u{0056}",
+            "--no-glyph-names",
+        ),
+        "1=0+450|\
+         2=1+500"
+    );
+}
+
+#[test]
+fn feature_variations_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/FeatureVariations.ttf",
+            "\u{0041}\u{0056}",
+            "--variations=wght=640 --no-glyph-names",
+        ),
+        "1=0+450|\
+         2=1+500"
+    );
+}
+
+#[test]
+fn feature_variations_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/FeatureVariations.ttf",
+            "\u{0041}\u{0056}",
+            "--variations=wght=650 --no-glyph-names",
+        ),
+        "1=0+300|\
+         2=1+500"
+    );
+}
+
+#[test]
+fn feature_variations_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/FeatureVariations.ttf",
+            "\u{0041}\u{0056}",
+            "--variations=wght=900 --no-glyph-names",
+        ),
+        "1=0+300|\
+         2=1+500"
+    );
+}
+
+#[test]
+fn feature_variations_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/FeatureVariations.ttf",
+            "\u{0041}\u{0056}",
+            "--variations=wght=260 --no-glyph-names",
+        ),
+        "1=0+450|\
+         2=1+500"
+    );
+}
+
+#[test]
+fn feature_variations_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/FeatureVariations.ttf",
+            "\u{0041}\u{0056}",
+            "--variations=wght=250 --no-glyph-names",
+        ),
+        "1=0+500|\
+         2=1+500"
+    );
+}
+
+#[test]
+fn fractions_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/15dfc433a135a658b9f4b1a861b5cdd9658ccbb9.ttf",
+            "\u{0031}\u{2044}\u{0032}\u{0020}\u{0033}\u{2044}\u{0034}",
+            "",
+        ),
+        "one.numr=0+600|\
+         fraction=1+252|\
+         two.small=2+600|\
+         space=3+600|\
+         three.numr=4+600|\
+         fraction=5+252|\
+         four.small=6+600"
+    );
+}
+
+#[test]
+fn fractions_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/15dfc433a135a658b9f4b1a861b5cdd9658ccbb9.ttf",
+            "\u{0031}\u{2044}\u{0032}\u{2044}\u{0033}",
+            "",
+        ),
+        "one.numr=0+600|\
+         fraction=1+252|\
+         two.numr=2+600|\
+         fraction=3+252|\
+         three.small=4+600"
+    );
+}
+
+#[test]
+fn fractions_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/15dfc433a135a658b9f4b1a861b5cdd9658ccbb9.ttf",
+            "\u{0031}\u{002F}\u{0032}\u{0020}\u{0033}\u{2044}\u{0034}",
+            "",
+        ),
+        "one=0+1090|\
+         .notdef=1+748|\
+         two=2+1090|\
+         space=3+600|\
+         three.numr=4+600|\
+         fraction=5+252|\
+         four.small=6+600"
+    );
+}
+
 #[test]
 fn fuzzer_001() {
     assert_eq!(
         shape(
-            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
-            "\u{1EA4}\u{006E}",
-            "",
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{1EA4}\u{006E}",
+            "",
+        ),
+        "Acircumflex=0+645|\
+         uniF401=0+0|\
+         n=1+641"
+    );
+}
+
+#[test]
+fn fuzzer_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AdobeBlank-Regular.ttf",
+            "\u{0F42}\u{0FB7}",
+            "--no-glyph-names",
+        ),
+        "1859=0+0|\
+         1976=0+0"
+    );
+}
+
+#[test]
+fn fuzzer_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Rasa.subset1.otf",
+            "\u{0A93}\u{0ABC}",
+            "",
+        ),
+        "gid5=0+982|\
+         gid22=0@-1,0+0|\
+         gid21=0+0"
+    );
+}
+
+#[test]
+fn fuzzer_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AdobeBlank-Regular.ttf",
+            "\u{104A}\u{102F}",
+            "",
+        ),
+        "cid00075=0+0|\
+         cid00048=0+0"
+    );
+}
+
+#[test]
+fn fuzzer_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/NotoSansMyanmarUI-Regular.subset1.otf",
+            "\u{1004}\u{103A}\u{1039}\u{1002}\u{101C}",
+            "",
+        ),
+        "gid1=0+668|\
+         gid5=0@-4,0+0|\
+         gid3=4+1126"
+    );
+}
+
+#[test]
+fn fuzzer_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/NotoSansSinhala.subset1.otf",
+            "\u{0DC1}\u{200D}\u{0DCA}\u{200D}\u{0DBB}\u{0DD3}",
+            "",
+        ),
+        "gid2=0+917|\
+         gid7=0+0|\
+         gid4=0+0"
+    );
+}
+
+#[test]
+fn fuzzer_007() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/LaBelleAurore.ttf",
+            "\u{006B}\u{0065}\u{031D}",
+            "",
+        ),
+        "k=0+479|\
+         e=1+343|\
+         .notdef=1@-172,-59+0"
+    );
+}
+
+#[test]
+fn fuzzer_008() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Linefont.ttf",
+            "\u{0054}\u{021F}",
+            "--no-glyph-names",
+        ),
+        "93=0+100|\
+         233=1@0,900+100|\
+         2=1@0,140+0|\
+         1=1+0"
+    );
+}
+
+#[test]
+fn fuzzer_009() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Linefont.ttf",
+            "\u{021F}\u{0061}",
+            "--no-glyph-names",
+        ),
+        "17=0+100|\
+         1=0+0|\
+         4=1+100"
+    );
+}
+
+#[test]
+fn fuzzer_010() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/226bc2deab3846f1a682085f70c67d0421014144.ttf",
+            "\u{0D2F}\u{0D30}\u{0D46}\u{0D4D}",
+            "--features=pref",
+        ),
+        "yamlym=0+2120|\
+         ramlym=1+1507|\
+         evowelsignmlym=1+1465|\
+         viramamlym=1+0"
+    );
+}
+
+#[test]
+fn glyph_flags_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/aots/gpos_chaining1_boundary_f1.otf",
+            "\u{0000}\u{0014}\u{0015}\u{0016}\u{0017}\u{0000}",
+            "--show-flags --features=\"test\"",
+        ),
+        ".notdef=0+1500|\
+         g20=1+1500|\
+         g21=2+1500#1|\
+         g22=3+1500#1|\
+         g23=4+1500#1|\
+         .notdef=5+1500"
+    );
+}
+
+#[test]
+fn glyph_flags_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/text-rendering-tests/TestMORXThirtyfive.ttf",
+            "\u{0058}\u{0041}",
+            "--show-flags --ned --remove-default-ignorables",
+        ),
+        "X|\
+         A@586,0#1|\
+         B@1225,0#1|\
+         C@1851,0#1|\
+         E@2447,0#1"
+    );
+}
+
+#[test]
+fn gpos_device_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/GposDevice.ttf",
+            "\u{0041}\u{0056}",
+            "",
+        ),
+        "gid1=0+450|\
+         gid2=1+500"
+    );
+}
+
+#[test]
+fn gpos_device_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/GposDevice.ttf",
+            "\u{0041}\u{0056}",
+            "--font-ppem=10",
+        ),
+        "gid1=0+550|\
+         gid2=1+500"
+    );
+}
+
+#[test]
+fn gpos_device_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/GposDevice.ttf",
+            "\u{0041}\u{0056}",
+            "--font-ppem=11",
+        ),
+        "gid1=0+269|\
+         gid2=1+500"
+    );
+}
+
+#[test]
+fn gpos_device_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/GposDevice.ttf",
+            "\u{0041}\u{0056}",
+            "--font-ppem=13",
+        ),
+        "gid1=0+450|\
+         gid2=1+500"
+    );
+}
+
+#[test]
+fn hangul_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/text-rendering-tests/FDArrayTest257.otf",
+            "\u{1100}\u{1161}",
+            "",
+        ),
+        "gid1=0+1000"
+    );
+}
+
+#[test]
+fn hangul_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/text-rendering-tests/FDArrayTest257.otf",
+            "\u{1100}\u{1161}\u{11A8}",
+            "",
+        ),
+        "gid2=0+1000"
+    );
+}
+
+#[test]
+fn hangul_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/text-rendering-tests/FDArrayTest257.otf",
+            "\u{AC00}\u{11A8}",
+            "",
+        ),
+        "gid2=0+1000"
+    );
+}
+
+#[test]
+fn hebrew_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/b895f8ff06493cc893ec44de380690ca0074edfa.ttf",
+            "\u{05E9}\u{05C1}\u{05B8}\u{05DC}\u{05B9}\u{05DD}",
+            "",
+        ),
+        "finalmem=5+1004|\
+         lamedholam=3+921|\
+         qamats=0@665,0+0|\
+         shinshindot=0+1292"
+    );
+}
+
+#[test]
+fn hebrew_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/b895f8ff06493cc893ec44de380690ca0074edfa.ttf",
+            "\u{05D1}\u{05B7}\u{05B4}\u{05BD}\u{05D9}\u{05EA}",
+            "",
+        ),
+        "tav=5+1026|\
+         yod=4+454|\
+         hiriq=0@427,0+0|\
+         meteg=0@665,0+0|\
+         patah=0@669,0+0|\
+         bet=0+967"
+    );
+}
+
+#[test]
+fn hebrew_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/b895f8ff06493cc893ec44de380690ca0074edfa.ttf",
+            "\u{05E9}\u{05BC}\u{05C1}\u{05B8}",
+            "",
+        ),
+        "qamats=0@665,0+0|\
+         shindageshshindot=0+1292"
+    );
+}
+
+#[test]
+fn hebrew_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/b895f8ff06493cc893ec44de380690ca0074edfa.ttf",
+            "\u{05D1}\u{05B8}\u{05B0}\u{05BD}",
+            "",
+        ),
+        "sheva=0@427,0+0|\
+         meteg=0@665,0+0|\
+         qamats=0@670,0+0|\
+         bet=0+967"
+    );
+}
+
+#[test]
+fn kern_table_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/text-rendering-tests/TestKERNOne.otf",
+            "\u{0054}\u{0075}",
+            "",
+        ),
+        "T=0+500|\
+         u=1@-100,0+300"
+    );
+}
+
+#[test]
+fn kern_table_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/text-rendering-tests/TestKERNOne.otf",
+            "\u{0054}\u{0075}",
+            "--features=-kern",
+        ),
+        "T=0+600|\
+         u=1+400"
+    );
+}
+
+#[test]
+fn kern_table_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/text-rendering-tests/TestKERNOne.otf",
+            "\u{0054}\u{0075}\u{0054}",
+            "--features=-kern,kern[1:]",
+        ),
+        "T=0+600|\
+         u=1+300|\
+         T=2@-100,0+500"
+    );
+}
+
+#[test]
+fn khmer_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/3998336402905b8be8301ef7f47cf7e050cbb1bd.ttf",
+            "\u{1780}\u{17D2}\u{1798}",
+            "",
+        ),
+        "uni1780=0+636|\
+         uni17D21798=0@21,-26+0"
+    );
+}
+
+#[test]
+fn khmer_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/3998336402905b8be8301ef7f47cf7e050cbb1bd.ttf",
+            "\u{1780}\u{17D2}\u{179A}\u{17C1}",
+            "",
+        ),
+        "uni17C1=0+288|\
+         uni17D2179A=0+287|\
+         uni1780=0+636"
+    );
+}
+
+#[test]
+fn ligature_marks_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/LigatureMarks.ttf",
+            "\u{0066}\u{0066}\u{0069}",
+            "",
+        ),
+        "f_f_i=0+900"
+    );
+}
+
+#[test]
+fn ligature_marks_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/LigatureMarks.ttf",
+            "\u{0066}\u{0301}\u{0066}\u{0069}",
+            "",
+        ),
+        "f_f_i=0+900|\
+         acutecomb=0@-750,200+0"
+    );
+}
+
+#[test]
+fn ligature_marks_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/LigatureMarks.ttf",
+            "\u{0066}\u{0066}\u{0301}\u{0069}",
+            "",
+        ),
+        "f_f_i=0+900|\
+         acutecomb=0@-450,200+0"
+    );
+}
+
+#[test]
+fn ligature_marks_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/LigatureMarks.ttf",
+            "\u{0066}\u{0066}\u{0069}\u{0301}",
+            "",
+        ),
+        "f_f_i=0+900|\
+         acutecomb=0@-150,200+0"
+    );
+}
+
+#[test]
+fn ligature_marks_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/LigatureMarks.ttf",
+            "\u{0066}\u{0301}\u{0066}\u{0301}\u{0069}\u{0301}",
+            "",
+        ),
+        "f_f_i=0+900|\
+         acutecomb=0@-750,200+0|\
+         acutecomb=0@-450,200+0|\
+         acutecomb=0@-150,200+0"
+    );
+}
+
+#[test]
+fn ligature_marks_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/LigatureMarks.ttf",
+            "\u{0066}\u{0066}\u{0301}\u{0301}\u{0069}",
+            "",
+        ),
+        "f_f_i=0+900|\
+         acutecomb=0@-450,200+0|\
+         acutecomb=0@-450,200+0"
+    );
+}
+
+#[test]
+fn mark_attach_type_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkAttachType.ttf",
+            "\u{0066}\u{0069}",
+            "",
+        ),
+        "f_i=0+550"
+    );
+}
+
+#[test]
+fn mark_attach_type_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkAttachType.ttf",
+            "\u{0066}\u{0300}\u{0069}",
+            "",
+        ),
+        "f_i=0+550|\
+         gravecomb=0+0"
+    );
+}
+
+#[test]
+fn mark_attach_type_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkAttachType.ttf",
+            "\u{0066}\u{0301}\u{0069}",
+            "",
+        ),
+        "f=0+300|\
+         acutecomb=0+0|\
+         i=2+300"
+    );
+}
+
+#[test]
+fn mark_attach_type_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkAttachType.ttf",
+            "\u{0066}\u{0301}\u{0069}",
+            "--features=-liga,dlig",
+        ),
+        "f_i=0+550|\
+         acutecomb=0+0"
+    );
+}
+
+#[test]
+fn mark_attach_type_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkAttachType.ttf",
+            "\u{0066}\u{0300}\u{0069}",
+            "--features=-liga,dlig",
+        ),
+        "f=0+300|\
+         gravecomb=0+0|\
+         i=2+300"
+    );
+}
+
+#[test]
+fn mark_order_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/text-rendering-tests/TestGPOSOne.ttf",
+            "\u{0078}\u{0301}\u{0323}",
+            "",
+        ),
+        ".notdef=0+500|\
+         dotbelowcomb=0+0|\
+         acutecomb=0+0"
+    );
+}
+
+#[test]
+fn mark_order_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/21b7fb9c1eeae260473809fbc1fe330f66a507cd.ttf",
+            "\u{0649}\u{0650}\u{0655}",
+            "",
+        ),
+        "uni0650.small2=0@727,-774+0|\
+         uni0655=0@727,-209+0|\
+         uni0649=0+1566"
+    );
+}
+
+#[test]
+fn mark_stacking_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{093C}\u{0941}",
+            "",
+        ),
+        "ka=0+600|\
+         nukta=0@-300,-50+0|\
+         u=0@-300,-200+0"
+    );
+}
+
+#[test]
+fn mark_stacking_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{0947}\u{0902}",
+            "",
+        ),
+        "ka=0+600|\
+         e=0@-300,700+0|\
+         anusvara=0@-300,900+0"
+    );
+}
+
+#[test]
+fn mark_stacking_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{093C}\u{0941}\u{0947}\u{0902}",
+            "",
+        ),
+        "ka=0+600|\
+         nukta=0@-300,-50+0|\
+         u=0@-300,-200+0|\
+         e=0@-300,700+0|\
+         anusvara=0@-300,900+0"
+    );
+}
+
+#[test]
+fn mark_stacking_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{093C}\u{0941}",
+            "--features=-mkmk",
+        ),
+        "ka=0+600|\
+         nukta=0@-300,-50+0|\
+         u=0@-300,-50+0"
+    );
+}
+
+#[test]
+fn mark_stacking_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{093C}\u{0941}",
+            "--features=-blwm",
+        ),
+        "ka=0+600|\
+         nukta=0+0|\
+         u=0@0,-150+0"
+    );
+}
+
+#[test]
+fn mirroring_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0028}\u{0061}\u{0029}",
+            "--direction=rtl",
+        ),
+        "parenleft=2+314|\
+         a=1+582|\
+         parenright=0+314"
+    );
+}
+
+#[test]
+fn mirroring_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0028}\u{0061}\u{0029}",
+            "--direction=ltr",
+        ),
+        "parenleft=0+314|\
+         a=1+582|\
+         parenright=2+314"
+    );
+}
+
+#[test]
+fn mirroring_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{005B}\u{00AB}\u{0031}\u{00BB}\u{005D}",
+            "--direction=rtl",
+        ),
+        "bracketleft=4+336|\
+         guillemotleft=3+524|\
+         one=2+594|\
+         guillemotright=1+524|\
+         bracketright=0+336"
+    );
+}
+
+#[test]
+fn mirroring_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{003C}\u{003E}",
+            "--direction=rtl --no-glyph-names",
+        ),
+        "31=1+554|\
+         33=0+554"
+    );
+}
+
+#[test]
+fn multiple_subst_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MultipleSubst.ttf",
+            "\u{0063}\u{0061}\u{0063}",
+            "",
+        ),
+        "c=0+470|\
+         x=1+410|\
+         y=1+420|\
+         z=1+430|\
+         c=2+470"
+    );
+}
+
+#[test]
+fn multiple_subst_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MultipleSubst.ttf",
+            "\u{0063}\u{0062}\u{0063}",
+            "",
+        ),
+        "c=0+470|\
+         c=2+470"
+    );
+}
+
+#[test]
+fn multiple_subst_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MultipleSubst.ttf",
+            "\u{0062}\u{0063}",
+            "",
+        ),
+        "c=0+470"
+    );
+}
+
+#[test]
+fn myanmar_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/98b7887cff91f722b92a8ff800120954606354f9.ttf",
+            "\u{100F}\u{103C}\u{1036}",
+            "",
+        ),
+        "uni103C=0+150|\
+         uni100F=0+550|\
+         uni1036=0@-150,0+0"
+    );
+}
+
+#[test]
+fn myanmar_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/a6c76d1bafde4a0b1026ebcc932d2e5c6fd02442.ttf",
+            "\u{1004}\u{103A}\u{1039}\u{101B}\u{102D}",
+            "",
+        ),
+        "uni101B=0+450|\
+         uni1004103A1039102D=0@-50,0+0"
+    );
+}
+
+#[test]
+fn myanmar_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/in-house/a6c76d1bafde4a0b1026ebcc932d2e5c6fd02442.ttf",
+            "\u{101B}\u{103D}\u{102D}",
+            "",
+        ),
+        "uni101B103D=0+450|\
+         uni102D=0@-150,0+0"
+    );
+}
+
+#[test]
+fn normalization_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{00E9}",
+            "",
+        ),
+        "eacute=0+598"
+    );
+}
+
+#[test]
+fn normalization_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0065}\u{0301}",
+            "",
+        ),
+        "eacute=0+598"
+    );
+}
+
+#[test]
+fn normalization_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{1EBF}",
+            "",
+        ),
+        "ecircumflex=0+598|\
+         acutecomb=0+0"
+    );
+}
+
+#[test]
+fn normalization_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0065}\u{0302}\u{0301}",
+            "",
+        ),
+        "ecircumflex=0+598|\
+         acutecomb=0+0"
+    );
+}
+
+#[test]
+fn normalization_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0071}\u{0301}",
+            "",
+        ),
+        "q=0+625|\
+         acutecomb=0+0"
+    );
+}
+
+#[test]
+fn notdef_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Alternates.ttf",
+            "\u{0061}\u{2603}\u{0062}",
+            "",
+        ),
+        "a=0+450|\
+         .notdef=1+500|\
+         b=2+460"
+    );
+}
+
+#[test]
+fn notdef_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Alternates.ttf",
+            "\u{0061}\u{2603}\u{0062}",
+            "--not-found-glyph=2",
+        ),
+        "a=0+450|\
+         b=1+460|\
+         b=2+460"
+    );
+}
+
+#[test]
+fn rtl_graphemes_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0064}",
+            "--direction=rtl",
+        ),
+        "d=3+629|\
+         c=2+529|\
+         b=1+629|\
+         a=0+582"
+    );
+}
+
+#[test]
+fn rtl_graphemes_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0061}\u{0071}\u{0301}\u{0063}",
+            "--direction=rtl",
+        ),
+        "c=3+506|\
+         q=1+625|\
+         acutecomb=1+0|\
+         a=0+582"
+    );
+}
+
+#[test]
+fn rtl_graphemes_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/PT_Sans-Caption-Web-Regular.ttf",
+            "\u{0061}\u{0071}\u{0301}\u{0063}",
+            "--direction=rtl --cluster-level=2",
+        ),
+        "c=3+506|\
+         q=1+625|\
+         acutecomb=2+0|\
+         a=0+582"
+    );
+}
+
+#[test]
+fn shaper_selection_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AdobeBlank-Regular.ttf",
+            "\u{0915}\u{093F}",
+            "--script=Deva",
+        ),
+        "cid00320=0+0|\
+         cid00278=0+0"
+    );
+}
+
+#[test]
+fn shaper_selection_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AdobeBlank-Regular.ttf",
+            "\u{0915}\u{093F}",
+            "--script=Latn",
+        ),
+        "cid00278=0+0|\
+         cid00320=0+0"
+    );
+}
+
+#[test]
+fn shaper_selection_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/AdobeBlank-Regular.ttf",
+            "\u{103C}",
+            "--script=Mymr",
         ),
-        "Acircumflex=0+645|\
-         uniF401=0+0|\
-         n=1+641"
+        "cid00061=0+0|\
+         cid01485=0+0"
     );
 }
 
 #[test]
-fn fuzzer_002() {
+fn shaper_selection_004() {
     assert_eq!(
         shape(
             "tests/fonts/rb_custom/AdobeBlank-Regular.ttf",
-            "\u{0F42}\u{0FB7}",
-            "--no-glyph-names",
+            "\u{103C}",
+            "--script=Latn",
         ),
-        "1859=0+0|\
-         1976=0+0"
+        "cid00061=0+0"
     );
 }
 
 #[test]
-fn fuzzer_003() {
+fn shaper_selection_005() {
     assert_eq!(
         shape(
-            "tests/fonts/rb_custom/Rasa.subset1.otf",
-            "\u{0A93}\u{0ABC}",
+            "tests/fonts/text-rendering-tests/TestMORXTwenty.ttf",
+            "\u{103C}",
+            "--script=Mymr",
+        ),
+        ".notdef=0+599"
+    );
+}
+
+#[test]
+fn shared_lookup_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SharedLookup.ttf",
+            "\u{0061}\u{0061}\u{0061}",
             "",
         ),
-        "gid5=0+982|\
-         gid22=0@-1,0+0|\
-         gid21=0+0"
+        "a.alt=0+510|\
+         a.alt=1+510|\
+         a.alt=2+510"
     );
 }
 
 #[test]
-fn fuzzer_004() {
+fn shared_lookup_002() {
     assert_eq!(
         shape(
-            "tests/fonts/rb_custom/AdobeBlank-Regular.ttf",
-            "\u{104A}\u{102F}",
+            "tests/fonts/rb_custom/SharedLookup.ttf",
+            "\u{0061}\u{0061}\u{0061}",
+            "--features=-liga",
+        ),
+        "a.alt=0+510|\
+         a.alt=1+510|\
+         a.alt=2+510"
+    );
+}
+
+#[test]
+fn shared_lookup_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SharedLookup.ttf",
+            "\u{0061}\u{0061}\u{0061}",
+            "--features=-liga,-clig",
+        ),
+        "a=0+450|\
+         a=1+450|\
+         a=2+450"
+    );
+}
+
+#[test]
+fn shared_lookup_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SharedLookup.ttf",
+            "\u{0061}\u{0061}\u{0061}",
+            "--features=ss01[1:2]",
+        ),
+        "a.alt=0+510|\
+         a.alt=1+510|\
+         a.alt=2+510"
+    );
+}
+
+#[test]
+fn shared_lookup_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SharedLookup.ttf",
+            "\u{0061}\u{0061}\u{0061}",
+            "--features=-kern,ss01[1:2]",
+        ),
+        "a.alt=0+460|\
+         a.alt=1+510|\
+         a.alt=2+460"
+    );
+}
+
+#[test]
+fn single_pos_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SinglePos.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0064}\u{0065}\u{0061}\u{0062}",
+            "--features=sups",
+        ),
+        "a=0@0,350+450|\
+         b=1@0,350+450|\
+         c=2@0,300+450|\
+         d=3@0,250+450|\
+         e=4+450|\
+         a=5@0,350+450|\
+         b=6@0,350+450"
+    );
+}
+
+#[test]
+fn single_pos_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SinglePos.ttf",
+            "\u{0063}\u{0061}\u{0061}\u{0065}",
+            "--features=sups",
+        ),
+        "c=0@0,300+450|\
+         a=1@0,350+450|\
+         a=2@0,350+450|\
+         e=3+450"
+    );
+}
+
+#[test]
+fn single_pos_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SinglePos.ttf",
+            "\u{0061}\u{0061}\u{0061}\u{0061}",
+            "--features=sups[1:3]",
+        ),
+        "a=0+450|\
+         a=1@0,350+450|\
+         a=2@0,350+450|\
+         a=3+450"
+    );
+}
+
+#[test]
+fn single_pos_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SinglePos.ttf",
+            "\u{0061}\u{0062}\u{0063}",
             "",
         ),
-        "cid00075=0+0|\
-         cid00048=0+0"
+        "a=0+450|\
+         b=1+450|\
+         c=2+450"
     );
 }
 
 #[test]
-fn fuzzer_005() {
+fn single_subst_001() {
     assert_eq!(
         shape(
-            "tests/fonts/rb_custom/NotoSansMyanmarUI-Regular.subset1.otf",
-            "\u{1004}\u{103A}\u{1039}\u{1002}\u{101C}",
+            "tests/fonts/rb_custom/SingleSubst.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0064}",
+            "--features=ss01",
+        ),
+        "A=0+600|\
+         B=1+600|\
+         C=2+600|\
+         D=3+600"
+    );
+}
+
+#[test]
+fn single_subst_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SingleSubst.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0064}",
+            "--features=ss02",
+        ),
+        "A=0+600|\
+         B=1+600|\
+         C=2+600|\
+         D=3+600"
+    );
+}
+
+#[test]
+fn single_subst_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SingleSubst.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0064}\u{0061}",
+            "--features=ss01[1:3]",
+        ),
+        "a=0+450|\
+         B=1+600|\
+         C=2+600|\
+         d=3+450|\
+         a=4+450"
+    );
+}
+
+#[test]
+fn single_subst_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SingleSubst.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0064}\u{0061}",
+            "--features=ss02[1:3]",
+        ),
+        "a=0+450|\
+         B=1+600|\
+         C=2+600|\
+         d=3+450|\
+         a=4+450"
+    );
+}
+
+#[test]
+fn single_subst_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SingleSubst.ttf",
+            "\u{0061}\u{0062}\u{0063}\u{0064}\u{0062}",
+            "--features=ss03",
+        ),
+        "A=0+600|\
+         D=1+600|\
+         C=2+600|\
+         D=3+600|\
+         D=4+600"
+    );
+}
+
+#[test]
+fn single_subst_006() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/SingleSubst.ttf",
+            "\u{0064}\u{0061}\u{0062}",
+            "--features=ss04",
+        ),
+        "gid65535=0+0|\
+         a=1+450|\
+         b=2+450"
+    );
+}
+
+#[test]
+fn synthetic_bold_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{093C}\u{0941}\u{0947}\u{0902}",
+            "--font-bold=0.02",
+        ),
+        "ka=0+620|\
+         nukta=0@-320,-50+0|\
+         u=0@-320,-200+0|\
+         e=0@-320,700+0|\
+         anusvara=0@-320,900+0"
+    );
+}
+
+#[test]
+fn synthetic_bold_002() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--font-bold=0.05,0",
+        ),
+        "a=0+450|\
+         a=1+500"
+    );
+}
+
+#[test]
+fn synthetic_bold_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--direction=t --font-bold=0,0.05",
+        ),
+        "a.vert=0@-230,-800+0,-1050|\
+         a.vert=1@-230,-800+0,-1050"
+    );
+}
+
+#[test]
+fn synthetic_bold_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--direction=t --font-bold=0.05,0",
+        ),
+        "a.vert=0@-255,-800+0,-1000|\
+         a.vert=1@-255,-800+0,-1000"
+    );
+}
+
+#[test]
+fn synthetic_slant_001() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{093C}\u{0941}\u{0947}\u{0902}",
             "",
         ),
-        "gid1=0+668|\
-         gid5=0@-4,0+0|\
-         gid3=4+1126"
+        "ka=0+600|\
+         nukta=0@-300,-50+0|\
+         u=0@-300,-200+0|\
+         e=0@-300,700+0|\
+         anusvara=0@-300,900+0"
     );
 }
 
 #[test]
-fn fuzzer_006() {
+fn synthetic_slant_002() {
     assert_eq!(
         shape(
-            "tests/fonts/rb_custom/NotoSansSinhala.subset1.otf",
-            "\u{0DC1}\u{200D}\u{0DCA}\u{200D}\u{0DBB}\u{0DD3}",
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{093C}\u{0941}\u{0947}\u{0902}",
+            "--font-slant=0.2",
+        ),
+        "ka=0+600|\
+         nukta=0@-310,-50+0|\
+         u=0@-340,-200+0|\
+         e=0@-160,700+0|\
+         anusvara=0@-120,900+0"
+    );
+}
+
+#[test]
+fn synthetic_slant_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/MarkStacking.ttf",
+            "\u{0915}\u{093C}\u{0941}\u{0947}\u{0902}",
+            "--font-slant=-0.25",
+        ),
+        "ka=0+600|\
+         nukta=0@-287,-50+0|\
+         u=0@-250,-200+0|\
+         e=0@-475,700+0|\
+         anusvara=0@-525,900+0"
+    );
+}
+
+#[test]
+fn synthetic_slant_004() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--direction=t",
+        ),
+        "a.vert=0@-230,-800+0,-1000|\
+         a.vert=1@-230,-800+0,-1000"
+    );
+}
+
+#[test]
+fn synthetic_slant_005() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--direction=t --font-slant=0.2",
+        ),
+        "a.vert=0@-390,-800+0,-1000|\
+         a.vert=1@-390,-800+0,-1000"
+    );
+}
+
+#[test]
+fn thai_pua_001() {
+    assert_eq!(
+        shape("tests/fonts/rb_custom/ThaiPua.ttf", "\u{0E01}\u{0E48}", "",),
+        "uni0E01=0+600|\
+         uniF70A=0+0"
+    );
+}
+
+#[test]
+fn thai_pua_002() {
+    assert_eq!(
+        shape("tests/fonts/rb_custom/ThaiPua.ttf", "\u{0E1B}\u{0E48}", "",),
+        "uni0E1B=0+600|\
+         uniF705=0+0"
+    );
+}
+
+#[test]
+fn thai_pua_003() {
+    assert_eq!(
+        shape(
+            "tests/fonts/rb_custom/ThaiPua.ttf",
+            "\u{0E1B}\u{0E34}\u{0E48}",
             "",
         ),
-        "gid2=0+917|\
-         gid7=0+0|\
-         gid4=0+0"
+        "uni0E1B=0+600|\
+         uniF701=0+0|\
+         uniF713=0+0"
     );
 }
 
 #[test]
-fn fuzzer_007() {
+fn thai_pua_004() {
+    assert_eq!(
+        shape("tests/fonts/rb_custom/ThaiPua.ttf", "\u{0E0D}\u{0E38}", "",),
+        "uniF70F=0+600|\
+         uni0E38=0+0"
+    );
+}
+
+#[test]
+fn thai_pua_005() {
     assert_eq!(
         shape(
-            "tests/fonts/rb_custom/LaBelleAurore.ttf",
-            "\u{006B}\u{0065}\u{031D}",
+            "tests/fonts/rb_custom/ThaiPua.ttf",
+            "\u{0E01}\u{0E48}\u{0E33}",
             "",
         ),
-        "k=0+479|\
-         e=1+343|\
-         .notdef=1@-172,-59+0"
+        "uni0E01=0+600|\
+         uni0E4D=0+0|\
+         uni0E48=0+0|\
+         uni0E32=0+600"
     );
 }
 
 #[test]
-fn fuzzer_008() {
+fn thai_pua_006() {
+    assert_eq!(
+        shape("tests/fonts/rb_custom/ThaiPua.ttf", "\u{0E1B}\u{0E33}", "",),
+        "uni0E1B=0+600|\
+         uniF711=0+0|\
+         uni0E32=0+600"
+    );
+}
+
+#[test]
+fn vertical_features_001() {
+    assert_eq!(
+        shape("tests/fonts/rb_custom/Vertical.ttf", "\u{0061}\u{0061}", "",),
+        "a=0+400|\
+         a=1+450"
+    );
+}
+
+#[test]
+fn vertical_features_002() {
     assert_eq!(
         shape(
-            "tests/fonts/rb_custom/Linefont.ttf",
-            "\u{0054}\u{021F}",
-            "--no-glyph-names",
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--direction=t",
         ),
-        "93=0+100|\
-         233=1@0,900+100|\
-         2=1@0,140+0|\
-         1=1+0"
+        "a.vert=0@-230,-800+0,-1000|\
+         a.vert=1@-230,-800+0,-1000"
     );
 }
 
 #[test]
-fn fuzzer_009() {
+fn vertical_features_003() {
     assert_eq!(
         shape(
-            "tests/fonts/rb_custom/Linefont.ttf",
-            "\u{021F}\u{0061}",
-            "--no-glyph-names",
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--direction=b",
         ),
-        "17=0+100|\
-         1=0+0|\
-         4=1+100"
+        "a.vert=1@-230,-800+0,-1000|\
+         a.vert=0@-230,-800+0,-1000"
     );
 }
 
 #[test]
-fn glyph_flags_001() {
+fn vertical_features_004() {
     assert_eq!(
         shape(
-            "tests/fonts/aots/gpos_chaining1_boundary_f1.otf",
-            "\u{0000}\u{0014}\u{0015}\u{0016}\u{0017}\u{0000}",
-            "--show-flags --features=\"test\"",
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--features=vert",
         ),
-        ".notdef=0+1500|\
-         g20=1+1500|\
-         g21=2+1500#1|\
-         g22=3+1500#1|\
-         g23=4+1500#1|\
-         .notdef=5+1500"
+        "a.vert=0+460|\
+         a.vert=1+460"
     );
 }
 
 #[test]
-fn glyph_flags_003() {
+fn vertical_features_005() {
     assert_eq!(
         shape(
-            "tests/fonts/text-rendering-tests/TestMORXThirtyfive.ttf",
-            "\u{0058}\u{0041}",
-            "--show-flags --ned --remove-default-ignorables",
+            "tests/fonts/rb_custom/Vertical.ttf",
+            "\u{0061}\u{0061}",
+            "--direction=t --features=vkrn",
         ),
-        "X|\
-         A@586,0#1|\
-         B@1225,0#1|\
-         C@1851,0#1|\
-         E@2447,0#1"
+        "a.vert=0@-230,-800+0,-930|\
+         a.vert=1@-230,-800+0,-1000"
     );
 }
@@ -1,4 +1,5 @@
 mod aots;
+mod api;
 mod custom;
 mod in_house;
 mod macos;
@@ -12,13 +13,20 @@ use harfruzz::BufferFlags;
 
 struct Args {
     face_index: u32,
+    font_ppem: Option<(u16, u16)>,
     font_ptem: Option<f32>,
+    font_slant: f32,
+    font_bold: Option<(f32, f32)>,
     variations: Vec<String>,
     direction: Option<harfruzz::Direction>,
     language: Option<harfruzz::Language>,
     script: Option<harfruzz::Script>,
     #[allow(dead_code)]
     remove_default_ignorables: bool,
+    preserve_default_ignorables: bool,
+    do_not_insert_dotted_circle: bool,
+    invisible_glyph: Option<u16>,
+    not_found_glyph: Option<u16>,
     unsafe_to_concat: bool,
     cluster_level: harfruzz::BufferClusterLevel,
     features: Vec<String>,
@@ -39,7 +47,10 @@ fn parse_args(args: Vec<std::ffi::OsString>) -> Result<Args, pico_args::Error> {
     let mut parser = pico_args::Arguments::from_vec(args);
     let args = Args {
         face_index: parser.opt_value_from_str("--face-index")?.unwrap_or(0),
+        font_ppem: parser.opt_value_from_fn("--font-ppem", parse_ppem)?,
         font_ptem: parser.opt_value_from_str("--font-ptem")?,
+        font_slant: parser.opt_value_from_str("--font-slant")?.unwrap_or(0.0),
+        font_bold: parser.opt_value_from_fn("--font-bold", parse_bold)?,
         variations: parser
             .opt_value_from_fn("--variations", parse_string_list)?
             .unwrap_or_default(),
@@ -47,6 +58,10 @@ fn parse_args(args: Vec<std::ffi::OsString>) -> Result<Args, pico_args::Error> {
         language: parser.opt_value_from_str("--language")?,
         script: parser.opt_value_from_str("--script")?,
         remove_default_ignorables: parser.contains("--remove-default-ignorables"),
+        preserve_default_ignorables: parser.contains("--preserve-default-ignorables"),
+        do_not_insert_dotted_circle: parser.contains("--do-not-insert-dotted-circle"),
+        invisible_glyph: parser.opt_value_from_str("--invisible-glyph")?,
+        not_found_glyph: parser.opt_value_from_str("--not-found-glyph")?,
         unsafe_to_concat: parser.contains("--unsafe-to-concat"),
         cluster_level: parser
             .opt_value_from_fn("--cluster-level", parse_cluster)?
@@ -78,6 +93,26 @@ fn parse_string_list(s: &str) -> Result<Vec<String>, String> {
     Ok(s.split(',').map(|s| s.to_string()).collect())
 }
 
+fn parse_ppem(s: &str) -> Result<(u16, u16), String> {
+    let mut values = s.split([' ', ',']).map(|s| s.parse::<u16>());
+    let x = values
+        .next()
+        .ok_or("missing ppem")?
+        .map_err(|e| format!("{e}"))?;
+    let y = values.next().unwrap_or(Ok(x)).map_err(|e| format!("{e}"))?;
+    Ok((x, y))
+}
+
+fn parse_bold(s: &str) -> Result<(f32, f32), String> {
+    let mut values = s.split([' ', ',']).map(|s| s.parse::<f32>());
+    let x = values
+        .next()
+        .ok_or("missing strength")?
+        .map_err(|e| format!("{e}"))?;
+    let y = values.next().unwrap_or(Ok(x)).map_err(|e| format!("{e}"))?;
+    Ok((x, y))
+}
+
 fn parse_cluster(s: &str) -> Result<harfruzz::BufferClusterLevel, String> {
     match s {
         "0" => Ok(harfruzz::BufferClusterLevel::MonotoneGraphemes),
@@ -109,7 +144,12 @@ pub fn shape(font_path: &str, text: &str, options: &str) -> String {
         std::fs::read(font_path).unwrap_or_else(|e| panic!("Could not read {}: {}", font_path, e));
     let mut face = harfruzz::Face::from_slice(&font_data, args.face_index).unwrap();
 
+    face.set_pixels_per_em(args.font_ppem);
     face.set_points_per_em(args.font_ptem);
+    face.set_synthetic_slant(args.font_slant);
+    if let Some((x, y)) = args.font_bold {
+        face.set_synthetic_bold(x, y, false);
+    }
 
     if !args.variations.is_empty() {
         let variations: Vec<_> = args
@@ -149,8 +189,24 @@ pub fn shape(font_path: &str, text: &str, options: &str) -> String {
         BufferFlags::REMOVE_DEFAULT_IGNORABLES,
         args.remove_default_ignorables,
     );
+    buffer_flags.set(
+        BufferFlags::PRESERVE_DEFAULT_IGNORABLES,
+        args.preserve_default_ignorables,
+    );
+    buffer_flags.set(
+        BufferFlags::DO_NOT_INSERT_DOTTED_CIRCLE,
+        args.do_not_insert_dotted_circle,
+    );
     buffer.set_flags(buffer_flags);
 
+    if let Some(glyph) = args.invisible_glyph {
+        buffer.set_invisible_glyph(harfruzz::ttf_parser::GlyphId(glyph));
+    }
+
+    if let Some(glyph) = args.not_found_glyph {
+        buffer.set_not_found_glyph(harfruzz::ttf_parser::GlyphId(glyph));
+    }
+
     buffer.set_cluster_level(args.cluster_level);
     buffer.reset_clusters();
 